@@ -0,0 +1,79 @@
+//! Helpers for Avro's JSON encoding, where non-null union values are wrapped
+//! in a single-member object named after the branch type (`{"int": 5}`) and
+//! bytes are written as strings with one code point per byte.
+
+use crate::JsonValue;
+
+/// Wraps a value into the given union branch, nulls are left as is.
+pub fn wrap_union<'input>(branch: &'input str, value: JsonValue<'input>) -> JsonValue<'input> {
+    match value {
+        JsonValue::Null => JsonValue::Null,
+        value => JsonValue::Object(crate::Shared::new(vec![(branch.into(), value)])),
+    }
+}
+
+/// Splits a union value into its branch name and the wrapped value, `None`
+/// if the value isn't a union encoding.
+pub fn unwrap_union<'value, 'input>(
+    value: &'value JsonValue<'input>,
+) -> Option<(&'value str, &'value JsonValue<'input>)> {
+    match value {
+        JsonValue::Null => Some(("null", value)),
+        JsonValue::Object(pairs) if pairs.len() == 1 => Some((&pairs[0].0, &pairs[0].1)),
+        _ => None,
+    }
+}
+
+/// Encodes bytes the way Avro writes them, one code point per byte, ready for
+/// `JsonValue::String`.
+pub fn encode_bytes(bytes: &[u8]) -> String {
+    bytes.iter().map(|&byte| byte as char).collect()
+}
+
+/// Decodes an Avro bytes value, `None` if the value isn't a string or holds
+/// code points past U+00FF.
+pub fn decode_bytes(value: &JsonValue) -> Option<Vec<u8>> {
+    value
+        .as_str()?
+        .chars()
+        .map(|c| u8::try_from(c).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // test the union wrapping
+    #[test]
+    fn test_union() {
+        let wrapped = wrap_union("int", JsonValue::Number(5u64.into()));
+        assert_eq!(wrapped, crate::from_str("{\"int\": 5}").unwrap());
+        assert_eq!(
+            unwrap_union(&wrapped),
+            Some(("int", &JsonValue::Number(5u64.into())))
+        );
+        assert_eq!(wrap_union("int", JsonValue::Null), JsonValue::Null);
+        assert_eq!(
+            unwrap_union(&JsonValue::Null),
+            Some(("null", &JsonValue::Null))
+        );
+        assert_eq!(unwrap_union(&JsonValue::Number(5u64.into())), None);
+    }
+
+    // test the bytes encoding
+    #[test]
+    fn test_bytes() {
+        let bytes = [0x00, b'a', b'"', 0xff];
+        let encoded = encode_bytes(&bytes);
+        assert_eq!(encoded, "\u{0}a\"\u{ff}");
+        assert_eq!(
+            decode_bytes(&JsonValue::String(encoded.into())),
+            Some(bytes.to_vec())
+        );
+        let value = crate::from_str("\"\\u0000a\\\"\\u00ff\"").unwrap();
+        assert_eq!(decode_bytes(&value), Some(bytes.to_vec()));
+        assert_eq!(decode_bytes(&JsonValue::String("\u{100}".into())), None);
+        assert_eq!(decode_bytes(&JsonValue::Null), None);
+    }
+}
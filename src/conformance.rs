@@ -0,0 +1,100 @@
+//! The JSONTestSuite corpus (github.com/nst/JSONTestSuite), its
+//! `test_parsing` files vendored under `tests/JSONTestSuite`. The `y_`
+//! documents must parse and the `n_` ones must be rejected in strict mode,
+//! those that aren't valid UTF-8 never make it to the `&str` parser and count
+//! as rejected. The implementation defined `i_` ones only must not panic.
+
+use std::path::Path;
+
+use crate::{from_str_with_options, ParserError, ParserOptions};
+
+/// The name and contents of every file in the corpus starting with `prefix`.
+fn corpus(prefix: &str) -> Vec<(String, Vec<u8>)> {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/JSONTestSuite/test_parsing");
+    let mut files: Vec<_> = std::fs::read_dir(directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "json")
+        })
+        .map(|path| {
+            let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+            (name, std::fs::read(path).unwrap())
+        })
+        .filter(|(name, _)| name.starts_with(prefix))
+        .collect();
+    files.sort();
+    assert!(!files.is_empty(), "no {prefix} files");
+    files
+}
+
+/// Whether the document parses in strict mode, bytes that aren't UTF-8 are
+/// rejected before getting to the parser.
+fn accepted(bytes: &[u8]) -> bool {
+    std::str::from_utf8(bytes)
+        .is_ok_and(|input| from_str_with_options(input, ParserOptions::strict()).is_ok())
+}
+
+// test the accepted documents
+#[test]
+fn test_accept() {
+    let failures: Vec<_> = corpus("y_")
+        .into_iter()
+        .filter(|(_, bytes)| !accepted(bytes))
+        .map(|(name, _)| name)
+        .collect();
+    assert!(failures.is_empty(), "rejected {failures:?}");
+}
+
+// test the rejected documents
+#[test]
+fn test_reject() {
+    let failures: Vec<_> = corpus("n_")
+        .into_iter()
+        .filter(|(_, bytes)| accepted(bytes))
+        .map(|(name, _)| name)
+        .collect();
+    assert!(failures.is_empty(), "accepted {failures:?}");
+}
+
+// test the implementation defined documents
+#[test]
+fn test_implementation_defined() {
+    for (_, bytes) in corpus("i_") {
+        accepted(&bytes);
+    }
+}
+
+// test the error collection against the parser
+#[test]
+fn test_collect_errors() {
+    let options = ParserOptions::strict();
+    let failures: Vec<_> = corpus("")
+        .into_iter()
+        .filter_map(|(name, bytes)| Some((name, String::from_utf8(bytes).ok()?)))
+        .filter(|(_, input)| {
+            let first = crate::collect_errors(input, options).first().cloned();
+            let error = from_str_with_options(input, options).err();
+            first.as_ref() != error.as_ref().map(ParserError::root)
+        })
+        .map(|(name, _)| name)
+        .collect();
+    assert!(failures.is_empty(), "disagreed on {failures:?}");
+}
+
+// test the validation against the parser
+#[test]
+fn test_validate() {
+    let options = ParserOptions::strict();
+    let failures: Vec<_> = corpus("")
+        .into_iter()
+        .filter_map(|(name, bytes)| Some((name, String::from_utf8(bytes).ok()?)))
+        .filter(|(_, input)| {
+            let valid = crate::validate(input, options).is_ok();
+            valid != from_str_with_options(input, options).is_ok()
+        })
+        .map(|(name, _)| name)
+        .collect();
+    assert!(failures.is_empty(), "disagreed on {failures:?}");
+}
@@ -0,0 +1,801 @@
+use std::ops::Range;
+
+use crate::unescape::unescape;
+use crate::{tokenize, Replacement, Token, TokenKind};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SyntaxKind {
+    /// The whole input, holds the top level value and whatever trails it.
+    Root,
+    Object,
+    /// A key, a colon and a value within an object.
+    Member,
+    Key,
+    List,
+    String,
+    Number,
+    Literal,
+    /// A valid container nested past the depth limit, kept as a single node
+    /// without children. Invalid ones are `Error` nodes.
+    Skipped,
+    /// An invalid region of the input, or an empty span where something is
+    /// missing.
+    Error,
+}
+
+/// A step from a container down to one of its values. Keys from syntax trees
+/// are the raw text between the quotes, escapes not decoded, those from
+/// `ParserError::path` are decoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PathSegment<'input> {
+    Key(&'input str),
+    Index(usize),
+}
+
+pub type JsonPath<'input> = Vec<PathSegment<'input>>;
+
+/// Writes the path in JSONPath notation, as in `$.items[1402].price`. Keys
+/// that aren't identifiers go in brackets, `$["a b"]`.
+pub fn format_path(path: &[PathSegment]) -> String {
+    let mut formatted = String::from("$");
+    for segment in path {
+        match segment {
+            PathSegment::Key(key)
+                if key.starts_with(|c: char| !c.is_ascii_digit())
+                    && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') =>
+            {
+                formatted.push('.');
+                formatted.push_str(key);
+            }
+            PathSegment::Key(key) => formatted.push_str(&format!("[\"{key}\"]")),
+            PathSegment::Index(index) => formatted.push_str(&format!("[{index}]")),
+        }
+    }
+    formatted
+}
+
+/// A node as the tree stores it. Nodes know their length and how far they
+/// start after the previous sibling, or after the start of their parent for
+/// the first child, but not where they are in the input, so that an edit only
+/// changes the nodes around it. While the parser builds a node, and before its
+/// parent places it, `offset` is from the start of the input instead.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct GreenNode {
+    kind: SyntaxKind,
+    offset: usize,
+    len: usize,
+    children: Vec<GreenNode>,
+}
+
+/// A node of a `SyntaxTree`, placed in the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SyntaxNode<'tree> {
+    green: &'tree GreenNode,
+    start: usize,
+}
+
+/// Concrete syntax tree of some input, built no matter how broken the input is.
+/// Whitespace and comments are not part of the tree but are covered by the
+/// spans of the enclosing nodes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxTree<'input> {
+    input: &'input str,
+    root: GreenNode,
+}
+
+impl GreenNode {
+    fn leaf(kind: SyntaxKind, span: Range<usize>) -> Self {
+        GreenNode {
+            kind,
+            offset: span.start,
+            len: span.len(),
+            children: Vec::new(),
+        }
+    }
+
+    fn missing(at: usize) -> Self {
+        GreenNode::leaf(SyntaxKind::Error, at..at)
+    }
+
+    /// A node over the span, placing the children built by the parser after
+    /// one another.
+    fn new(kind: SyntaxKind, span: Range<usize>, mut children: Vec<GreenNode>) -> Self {
+        let mut end = span.start;
+        for child in &mut children {
+            let start = child.offset;
+            child.offset = start - end;
+            end = start + child.len;
+        }
+        GreenNode {
+            kind,
+            offset: span.start,
+            len: span.len(),
+            children,
+        }
+    }
+
+    /// Where the node is, while the parser builds it.
+    fn span(&self) -> Range<usize> {
+        self.offset..self.offset + self.len
+    }
+
+    /// Whether the closer of the container was found, unclosed containers end
+    /// in a missing piece right at their end.
+    fn is_closed(&self) -> bool {
+        let end: usize = self
+            .children
+            .iter()
+            .map(|child| child.offset + child.len)
+            .sum();
+        self.children
+            .last()
+            .is_none_or(|last| !(last.len == 0 && end == self.len))
+    }
+}
+
+impl<'tree> SyntaxNode<'tree> {
+    pub fn kind(self) -> SyntaxKind {
+        self.green.kind
+    }
+
+    pub fn span(self) -> Range<usize> {
+        self.start..self.start + self.green.len
+    }
+
+    pub fn children(self) -> impl Iterator<Item = SyntaxNode<'tree>> {
+        let mut end = self.start;
+        self.green.children.iter().map(move |green| {
+            let start = end + green.offset;
+            end = start + green.len;
+            SyntaxNode { green, start }
+        })
+    }
+
+    /// Elements of a list, missing pieces are not elements.
+    fn elements(self) -> impl Iterator<Item = SyntaxNode<'tree>> {
+        self.children().filter(|element| !element.span().is_empty())
+    }
+
+    /// The value of a member.
+    fn member_value(self) -> SyntaxNode<'tree> {
+        self.children().last().expect("a member has a value")
+    }
+
+    fn errors(self, errors: &mut Vec<SyntaxNode<'tree>>) {
+        if self.kind() == SyntaxKind::Error {
+            errors.push(self);
+        }
+        self.children().for_each(|child| child.errors(errors));
+    }
+}
+
+impl<'input> SyntaxTree<'input> {
+    pub fn root(&self) -> SyntaxNode<'_> {
+        SyntaxNode {
+            green: &self.root,
+            start: 0,
+        }
+    }
+
+    pub fn text(&self, node: SyntaxNode) -> &'input str {
+        &self.input[node.span()]
+    }
+
+    /// Every error node of the tree in document order, an empty tree of errors
+    /// means the input is valid.
+    pub fn errors(&self) -> Vec<SyntaxNode<'_>> {
+        let mut errors = Vec::new();
+        self.root().errors(&mut errors);
+        errors
+    }
+
+    /// The top level value.
+    fn value(&self) -> SyntaxNode<'_> {
+        self.root().children().next().expect("the root has a value")
+    }
+
+    fn key(&self, member: SyntaxNode) -> Option<&'input str> {
+        let key = member.children().next().expect("a member has a key");
+        let text = self.text(key);
+        (key.kind() == SyntaxKind::Key).then(|| &text[1..text.len() - 1])
+    }
+
+    /// Byte range of the value a JSON pointer (RFC 6901) refers to, keys are
+    /// compared with their escapes decoded. The first member wins when a key
+    /// is repeated.
+    pub fn span_of(&self, pointer: &str) -> Option<Range<usize>> {
+        let mut node = self.value();
+        if pointer.is_empty() {
+            return Some(node.span());
+        }
+        for token in pointer.strip_prefix('/')?.split('/') {
+            let token = token.replace("~1", "/").replace("~0", "~");
+            node = match node.kind() {
+                SyntaxKind::Object => node
+                    .children()
+                    .find(|member| {
+                        member.kind() == SyntaxKind::Member
+                            && self
+                                .key(*member)
+                                .is_some_and(|key| unescape(key, Replacement::default()) == token)
+                    })?
+                    .member_value(),
+                SyntaxKind::List => node.elements().nth(crate::pointer_index(&token)?)?,
+                _ => return None,
+            };
+        }
+        Some(node.span())
+    }
+
+    /// The tree of `input`, which is the text of this tree with the bytes in
+    /// `edited` replaced by `replacement`. Only the innermost container around
+    /// the edit is parsed again and only its ancestors change length, nodes
+    /// after it are placed relative to their siblings and stay as they are, so
+    /// the work stays proportional to that container rather than the document.
+    /// Edits that touch the brackets of every container around them, or change
+    /// where the container ends, parse the whole input again.
+    pub fn reparse<'new>(
+        self,
+        input: &'new str,
+        edited: Range<usize>,
+        replacement: &str,
+    ) -> SyntaxTree<'new> {
+        let new_end = edited.start + replacement.len();
+        if edited.start > edited.end
+            || edited.end > self.input.len()
+            || input.len() + edited.len() != self.input.len() + replacement.len()
+        {
+            return parse_cst(input);
+        }
+        // child indices down to the container, with its kind, span and how
+        // deep it is nested
+        let mut path = Vec::new();
+        let mut container = None;
+        let mut node = self.root();
+        let mut depth = 0;
+        while let Some((index, child)) = node.children().enumerate().find(|(_, child)| {
+            matches!(
+                child.kind(),
+                SyntaxKind::Object | SyntaxKind::List | SyntaxKind::Member
+            ) && child.span().start < edited.start
+                && edited.end < child.span().end
+        }) {
+            path.push(index);
+            node = child;
+            if node.kind() != SyntaxKind::Member {
+                if node.green.is_closed() {
+                    container = Some((path.len(), node.kind(), node.span(), depth));
+                }
+                depth += 1;
+            }
+        }
+        let Some((length, kind, span, depth)) = container else {
+            return parse_cst(input);
+        };
+        path.truncate(length);
+        let span = span.start..span.end - edited.end + new_end;
+        let Some(text) = input.get(..span.end) else {
+            return parse_cst(input);
+        };
+        let tokens = syntax_tokens(&text[span.start..])
+            .map(|token| Token {
+                span: token.span.start + span.start..token.span.end + span.start,
+                ..token
+            })
+            .collect();
+        let mut parser = CstParser {
+            input: text,
+            tokens,
+            position: 0,
+            depth,
+        };
+        let mut container = parser.value();
+        if container.kind != kind
+            || container.span() != span
+            || !container.is_closed()
+            || parser.peek().is_some()
+        {
+            return parse_cst(input);
+        }
+        let mut root = self.root;
+        let mut node = &mut root;
+        for &index in &path {
+            node.len = node.len + new_end - edited.end;
+            node = &mut node.children[index];
+        }
+        container.offset = node.offset;
+        *node = container;
+        SyntaxTree { input, root }
+    }
+
+    /// Path to the innermost value, or object member, that the byte offset
+    /// falls inside. Offsets outside of the top level value give the empty
+    /// path.
+    pub fn path_at(&self, offset: usize) -> JsonPath<'input> {
+        let mut path = JsonPath::new();
+        let mut node = self.value();
+        loop {
+            let child = match node.kind() {
+                SyntaxKind::Object => node
+                    .children()
+                    .find(|member| member.span().contains(&offset)),
+                SyntaxKind::List => node
+                    .elements()
+                    .enumerate()
+                    .find(|(_, element)| element.span().contains(&offset))
+                    .map(|(index, element)| {
+                        path.push(PathSegment::Index(index));
+                        element
+                    }),
+                _ => None,
+            };
+            match child {
+                Some(member) if member.kind() == SyntaxKind::Member => {
+                    if let Some(key) = self.key(member) {
+                        path.push(PathSegment::Key(key));
+                    }
+                    node = member.member_value();
+                }
+                Some(element) => node = element,
+                None => return path,
+            }
+        }
+    }
+}
+
+/// How deep containers nest in the tree. The parser recurses, and so do the
+/// walks over the tree, containers deeper than this are skipped over as a
+/// single `Skipped` node, or an `Error` node when they aren't valid.
+const MAX_DEPTH: usize = 128;
+
+/// What comes next in a container being skipped over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Skipping {
+    Value,
+    /// The first value of a list, or its closer.
+    FirstValue,
+    Key,
+    /// The first key of an object, or its closer.
+    FirstKey,
+    Colon,
+    /// A comma or the closer after a value.
+    Separator,
+}
+
+impl Skipping {
+    /// What comes after the token, `None` when it doesn't belong here.
+    fn next(
+        self,
+        kind: TokenKind,
+        punctuation: Option<&str>,
+        closers: &mut Vec<&'static str>,
+    ) -> Option<Skipping> {
+        let string = matches!(kind, TokenKind::Key | TokenKind::String);
+        let scalar = string || matches!(kind, TokenKind::Number | TokenKind::Literal);
+        match (self, punctuation) {
+            (Skipping::Value | Skipping::FirstValue, _) if scalar => Some(Skipping::Separator),
+            (Skipping::Value | Skipping::FirstValue, Some("[")) => {
+                closers.push("]");
+                Some(Skipping::FirstValue)
+            }
+            (Skipping::Value | Skipping::FirstValue, Some("{")) => {
+                closers.push("}");
+                Some(Skipping::FirstKey)
+            }
+            (Skipping::Key | Skipping::FirstKey, _) if string => Some(Skipping::Colon),
+            (Skipping::Colon, Some(":")) => Some(Skipping::Value),
+            (Skipping::Separator, Some(",")) => match closers.last() {
+                Some(&"]") => Some(Skipping::Value),
+                Some(_) => Some(Skipping::Key),
+                None => None,
+            },
+            (Skipping::Separator | Skipping::FirstValue | Skipping::FirstKey, Some(closer))
+                if closers.last() == Some(&closer) =>
+            {
+                closers.pop();
+                Some(Skipping::Separator)
+            }
+            _ => None,
+        }
+    }
+}
+
+struct CstParser<'input> {
+    input: &'input str,
+    tokens: Vec<Token>,
+    position: usize,
+    /// How many containers the parser is in.
+    depth: usize,
+}
+
+impl<'input> CstParser<'input> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn peek_punctuation(&self) -> Option<&'input str> {
+        self.peek()
+            .filter(|token| token.kind == TokenKind::Punctuation)
+            .map(|token| &self.input[token.span.clone()])
+    }
+
+    /// Start of the next token, or the end of the input.
+    fn offset(&self) -> usize {
+        self.peek()
+            .map_or(self.input.len(), |token| token.span.start)
+    }
+
+    fn bump(&mut self) -> Range<usize> {
+        self.position += 1;
+        self.tokens[self.position - 1].span.clone()
+    }
+
+    fn value(&mut self) -> GreenNode {
+        let Some(token) = self.peek() else {
+            return GreenNode::missing(self.input.len());
+        };
+        let kind = match token.kind {
+            TokenKind::Key | TokenKind::String => SyntaxKind::String,
+            TokenKind::Number => SyntaxKind::Number,
+            TokenKind::Literal => SyntaxKind::Literal,
+            TokenKind::Error => SyntaxKind::Error,
+            _ => match self.peek_punctuation() {
+                Some("{" | "[") if self.depth >= MAX_DEPTH => return self.skip_container(),
+                Some("{") => return self.container(SyntaxKind::Object, "}"),
+                Some("[") => return self.container(SyntaxKind::List, "]"),
+                // a closer or a separator, leave it to the enclosing container
+                Some("}" | "]" | ",") => return GreenNode::missing(token.span.start),
+                // a stray colon
+                _ => SyntaxKind::Error,
+            },
+        };
+        GreenNode::leaf(kind, self.bump())
+    }
+
+    fn member(&mut self) -> GreenNode {
+        let key = match self.peek() {
+            Some(token) if matches!(token.kind, TokenKind::Key | TokenKind::String) => {
+                GreenNode::leaf(SyntaxKind::Key, self.bump())
+            }
+            _ if self.peek_punctuation() == Some(":") => GreenNode::missing(self.offset()),
+            _ => {
+                let value = self.value();
+                GreenNode::new(SyntaxKind::Error, value.span(), vec![value])
+            }
+        };
+        let colon = match self.peek_punctuation() {
+            Some(":") => {
+                self.bump();
+                None
+            }
+            _ => Some(GreenNode::missing(self.offset())),
+        };
+        let value = self.value();
+        let span = key.span().start..value.span().end;
+        let children = std::iter::once(key).chain(colon).chain([value]).collect();
+        GreenNode::new(SyntaxKind::Member, span, children)
+    }
+
+    /// A single node over the container and everything in it, up to its
+    /// closing or the end of the input. The tokens are checked against the
+    /// grammar on the way, with the closers still open on a stack, a valid
+    /// container is `Skipped` and an invalid one an `Error`.
+    fn skip_container(&mut self) -> GreenNode {
+        let start = self.offset();
+        let mut open = 0;
+        let mut closers = Vec::new();
+        let mut next = Some(Skipping::Value);
+        while let Some(token) = self.peek() {
+            let end = token.span.end;
+            let kind = token.kind;
+            let punctuation = self.peek_punctuation();
+            match punctuation {
+                Some("{" | "[") => open += 1,
+                Some("}" | "]") => open -= 1,
+                _ => {}
+            }
+            next = next.and_then(|skipping| skipping.next(kind, punctuation, &mut closers));
+            self.bump();
+            if open == 0 {
+                let kind = match next {
+                    Some(Skipping::Separator) => SyntaxKind::Skipped,
+                    _ => SyntaxKind::Error,
+                };
+                return GreenNode::leaf(kind, start..end);
+            }
+        }
+        GreenNode::leaf(SyntaxKind::Error, start..self.input.len())
+    }
+
+    fn container(&mut self, kind: SyntaxKind, closer: &str) -> GreenNode {
+        self.depth += 1;
+        let node = self.container_items(kind, closer);
+        self.depth -= 1;
+        node
+    }
+
+    fn container_items(&mut self, kind: SyntaxKind, closer: &str) -> GreenNode {
+        let start = self.bump().start;
+        let mut children = Vec::new();
+        let mut expect_item = true;
+        let end = loop {
+            match self.peek_punctuation() {
+                Some(p) if p == closer => {
+                    if expect_item && !children.is_empty() {
+                        // trailing comma
+                        children.push(GreenNode::missing(self.offset()));
+                    }
+                    break self.bump().end;
+                }
+                // unclosed, the outer container gets to handle its own closer
+                Some("}" | "]") => {
+                    children.push(GreenNode::missing(self.offset()));
+                    break self.offset();
+                }
+                Some(",") => {
+                    if expect_item {
+                        children.push(GreenNode::missing(self.offset()));
+                    }
+                    self.bump();
+                    expect_item = true;
+                }
+                _ if self.peek().is_none() => {
+                    children.push(GreenNode::missing(self.input.len()));
+                    break self.input.len();
+                }
+                _ => {
+                    if !expect_item {
+                        // missing comma
+                        children.push(GreenNode::missing(self.offset()));
+                    }
+                    let item = match kind {
+                        SyntaxKind::Object => self.member(),
+                        _ => self.value(),
+                    };
+                    children.push(item);
+                    expect_item = false;
+                }
+            }
+        };
+        GreenNode::new(kind, start..end, children)
+    }
+}
+
+/// The tokens that make up the tree, leaving out whitespace and comments.
+fn syntax_tokens(input: &str) -> impl Iterator<Item = Token> + '_ {
+    tokenize(input)
+        .filter(|token| !matches!(token.kind, TokenKind::Whitespace | TokenKind::Comment))
+}
+
+/// Parses the input into a concrete syntax tree. Parsing never fails, invalid
+/// regions and missing pieces show up as `SyntaxKind::Error` nodes so that
+/// editor features keep working on documents in the middle of an edit.
+/// Containers nested more than 128 deep become a single `Skipped` node each,
+/// or an error node when they aren't valid.
+pub fn parse_cst(input: &str) -> SyntaxTree<'_> {
+    let tokens = syntax_tokens(input).collect();
+    let mut parser = CstParser {
+        input,
+        tokens,
+        position: 0,
+        depth: 0,
+    };
+    let mut children = vec![parser.value()];
+    if let Some(token) = parser.peek() {
+        children.push(GreenNode::leaf(
+            SyntaxKind::Error,
+            token.span.start..input.len(),
+        ));
+    }
+    SyntaxTree {
+        input,
+        root: GreenNode::new(SyntaxKind::Root, 0..input.len(), children),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outline(tree: &SyntaxTree, node: SyntaxNode) -> String {
+        match node.kind() {
+            SyntaxKind::Error if node.span().is_empty() => "!".to_string(),
+            _ if node.children().next().is_none() => tree.text(node).to_string(),
+            _ => format!(
+                "{:?}({})",
+                node.kind(),
+                node.children()
+                    .map(|child| outline(tree, child))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            ),
+        }
+    }
+
+    // test the syntax tree of a valid document
+    #[test]
+    fn test_parse_cst() {
+        let tree = parse_cst(" {\"a\": [1, true], /* c */ \"b\": {}} ");
+        assert_eq!(
+            outline(&tree, tree.root()),
+            "Root(Object(Member(\"a\" List(1 true)) Member(\"b\" {})))"
+        );
+        assert_eq!(tree.root().children().next().unwrap().span(), 1..34);
+        assert!(tree.errors().is_empty());
+    }
+
+    // test the recovery from broken input
+    #[test]
+    fn test_parse_cst_errors() {
+        let tree = parse_cst("{\"a\": [1 2,], \"b\" 3, : @]");
+        assert_eq!(
+            outline(&tree, tree.root()),
+            "Root(Object(Member(\"a\" List(1 ! 2 !)) Member(\"b\" ! 3) Member(! @) !) ])"
+        );
+        assert_eq!(
+            tree.errors()
+                .iter()
+                .map(|node| node.span())
+                .collect::<Vec<_>>(),
+            vec![9..9, 11..11, 18..18, 21..21, 23..24, 24..24, 24..25]
+        );
+        let tree = parse_cst("[{\"a\": ");
+        assert_eq!(
+            outline(&tree, tree.root()),
+            "Root(List(Object(Member(\"a\" !) !) !))"
+        );
+        let tree = parse_cst("[\"a\": 1]");
+        assert_eq!(outline(&tree, tree.root()), "Root(List(\"a\" ! : ! 1))");
+    }
+
+    // test the offset to path lookup
+    #[test]
+    fn test_path_at() {
+        let input = "{\"a\": [1, {\"b\": null}], \"c\": 2}";
+        let tree = parse_cst(input);
+        let at = |needle: &str| tree.path_at(input.find(needle).unwrap());
+        assert_eq!(at("1"), vec![PathSegment::Key("a"), PathSegment::Index(0)]);
+        assert_eq!(
+            at("ull"),
+            vec![
+                PathSegment::Key("a"),
+                PathSegment::Index(1),
+                PathSegment::Key("b")
+            ]
+        );
+        assert_eq!(at("\"c"), vec![PathSegment::Key("c")]);
+        assert_eq!(at(", {"), vec![PathSegment::Key("a")]);
+        assert_eq!(tree.path_at(0), vec![]);
+        assert_eq!(tree.path_at(input.len()), vec![]);
+        let tree = parse_cst("[1 2, ");
+        assert_eq!(tree.path_at(3), vec![PathSegment::Index(1)]);
+    }
+
+    // test the JSONPath formatting
+    #[test]
+    fn test_format_path() {
+        assert_eq!(format_path(&[]), "$");
+        assert_eq!(
+            format_path(&[
+                PathSegment::Key("items"),
+                PathSegment::Index(1402),
+                PathSegment::Key("price")
+            ]),
+            "$.items[1402].price"
+        );
+        assert_eq!(
+            format_path(&[PathSegment::Key("a b"), PathSegment::Key("1")]),
+            "$[\"a b\"][\"1\"]"
+        );
+    }
+
+    // test the pointer to span lookup
+    #[test]
+    fn test_span_of() {
+        let input = "{\"a\": [1, {\"b/c\": null}], \"d\\u0065\": \"e\"} ";
+        let tree = parse_cst(input);
+        let text = |pointer| tree.span_of(pointer).map(|span| &input[span]);
+        assert_eq!(text(""), Some(input.trim()));
+        assert_eq!(text("/a/0"), Some("1"));
+        assert_eq!(text("/a/1/b~1c"), Some("null"));
+        assert_eq!(text("/de"), Some("\"e\""));
+        assert_eq!(text("/a/01"), None);
+        assert_eq!(text("/a/+1"), None);
+        assert_eq!(text("/a/2"), None);
+        assert_eq!(text("/a/0/x"), None);
+        assert_eq!(text("a"), None);
+    }
+
+    // test that reparsing after an edit gives the tree of the edited text
+    #[test]
+    fn test_reparse() {
+        let input = r#"{"a": [1, 2, {"b": null}], "c": "d"} "#;
+        let edits = [
+            (7..8, "10"),
+            (10..10, ", 3"),
+            (19..23, "[true, false]"),
+            (7..11, ""),
+            (13..13, "{"),
+            (12..13, "]"),
+            (28..30, "\"ee"),
+            (0..1, "["),
+            (35..36, ""),
+            (36..37, "x"),
+        ];
+        for (edited, replacement) in edits {
+            let mut edited_input = input.to_string();
+            edited_input.replace_range(edited.clone(), replacement);
+            let tree = parse_cst(input).reparse(&edited_input, edited, replacement);
+            assert_eq!(tree, parse_cst(&edited_input), "{edited_input}");
+        }
+        // the member after the edit is left as it was
+        let mut edited_input = input.to_string();
+        edited_input.replace_range(7..8, "10");
+        let tree = parse_cst(input).reparse(&edited_input, 7..8, "10");
+        assert_eq!(
+            tree.root.children[0].children[1],
+            parse_cst(input).root.children[0].children[1]
+        );
+        let deep = format!("{}{}", "[".repeat(200), "]".repeat(200));
+        let mut edited_input = deep.clone();
+        edited_input.insert(150, '1');
+        let tree = parse_cst(&deep).reparse(&edited_input, 150..150, "1");
+        assert_eq!(tree, parse_cst(&edited_input));
+    }
+
+    // test that containers past the depth limit are skipped over
+    #[test]
+    fn test_parse_cst_deep() {
+        let depth = 100_000;
+        let input = format!("{}{}, 1", "[".repeat(depth), "]".repeat(depth));
+        let tree = parse_cst(&input);
+        let errors = tree.errors();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].span(), 2 * depth..input.len());
+        let mut node = tree.root();
+        while let Some(child) = node.children().next() {
+            node = child;
+        }
+        assert_eq!(node.kind(), SyntaxKind::Skipped);
+        assert_eq!(node.span(), MAX_DEPTH..2 * depth - MAX_DEPTH);
+        assert_eq!(
+            tree.span_of(&"/0".repeat(MAX_DEPTH - 1)),
+            Some(MAX_DEPTH - 1..2 * depth - MAX_DEPTH + 1)
+        );
+        assert_eq!(tree.path_at(depth).len(), MAX_DEPTH);
+        assert_eq!(
+            tree.root().children().nth(1).unwrap().span(),
+            2 * depth..input.len()
+        );
+
+        let deep = |inner: &str| format!("{}{inner}{}", "[".repeat(200), "]".repeat(200));
+        for inner in [
+            "",
+            "1",
+            "{}",
+            "{\"a\": [1, {\"b\": null}]}, \"c\"",
+            "[], []",
+        ] {
+            assert_eq!(
+                parse_cst(&deep(inner)).errors(),
+                Vec::<SyntaxNode>::new(),
+                "{inner}"
+            );
+        }
+        for inner in [
+            "1,",
+            ",1",
+            "{\"a\"}",
+            "{\"a\": 1,}",
+            "{1: 2}",
+            "1 2",
+            "[}",
+            "@",
+        ] {
+            let input = deep(inner);
+            let tree = parse_cst(&input);
+            assert_eq!(tree.errors().len(), 1, "{inner}");
+            assert_eq!(tree.errors()[0].span().start, MAX_DEPTH, "{inner}");
+        }
+    }
+}
@@ -0,0 +1,628 @@
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::fmt;
+
+use serde::de::{self, DeserializeSeed, IntoDeserializer, Visitor};
+
+use crate::{
+    decoded_string, scalar, whitespace, DuplicateKeys, Expected, JsonValue, Number, Parser,
+    ParserError, ParserOptions, State, N,
+};
+
+/// How deep containers can nest when `ParserOptions::max_depth` is `None`.
+/// Unlike the parser, deserializing recurses, so there has to be a limit.
+const DEFAULT_MAX_DEPTH: usize = 128;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum DeserializeError {
+    #[error(transparent)]
+    Parse(#[from] ParserError),
+    /// A value the type refused, such as one of the wrong type or an object
+    /// missing a field, at the start of the value.
+    #[error("{1} at position {0}")]
+    Invalid(usize, String),
+}
+
+/// Errors of the type's own making have no position when they are made, the
+/// value they were made for sets it once they get back to the deserializer.
+const UNKNOWN_POSITION: usize = usize::MAX;
+
+impl DeserializeError {
+    /// Byte offset into the input where the error was found.
+    pub fn position(&self) -> usize {
+        match self {
+            DeserializeError::Parse(error) => error.position(),
+            DeserializeError::Invalid(position, _) => *position,
+        }
+    }
+
+    fn at(self, position: usize) -> Self {
+        match self {
+            DeserializeError::Invalid(UNKNOWN_POSITION, message) => {
+                DeserializeError::Invalid(position, message)
+            }
+            error => error,
+        }
+    }
+}
+
+impl de::Error for DeserializeError {
+    fn custom<T: fmt::Display>(message: T) -> Self {
+        DeserializeError::Invalid(UNKNOWN_POSITION, message.to_string())
+    }
+}
+
+/// Deserializes a `T` straight from the input, with no `JsonValue` built in
+/// between. Strings without escapes are lent out of the input, so `&str`
+/// fields, and `Cow<str>` ones marked `#[serde(borrow)]`, cost no copy.
+pub fn from_str_as<'input, T: de::Deserialize<'input>>(
+    input: &'input str,
+) -> Result<T, DeserializeError> {
+    from_str_as_with_options(input, ParserOptions::default())
+}
+
+/// Same as `from_str_as`. Of the duplicate key policies only
+/// `DuplicateKeys::Error` applies, with the others it is up to the type what
+/// becomes of repeated keys.
+pub fn from_str_as_with_options<'input, T: de::Deserialize<'input>>(
+    input: &'input str,
+    options: ParserOptions,
+) -> Result<T, DeserializeError> {
+    let mut deserializer = Deserializer {
+        input,
+        options,
+        position: 0,
+        depth: 0,
+    };
+    let value = T::deserialize(&mut deserializer)?;
+    if deserializer.peek().is_some() {
+        return Err(ParserError::TrailingCharacters(deserializer.position).into());
+    }
+    Ok(value)
+}
+
+struct Deserializer<'input> {
+    input: &'input str,
+    options: ParserOptions,
+    position: usize,
+    /// How many containers the current value is in.
+    depth: usize,
+}
+
+impl<'input> Deserializer<'input> {
+    fn state(&self) -> State {
+        State {
+            current: self.position,
+        }
+    }
+
+    /// The next character past whitespace, which gets skipped.
+    fn peek(&mut self) -> Option<char> {
+        if let Ok((_, state)) = whitespace(self.options).parse(self.input, self.state()) {
+            self.position = state.current;
+        }
+        self.input[self.position..].chars().next()
+    }
+
+    /// Skips the character if it is next.
+    fn eat(&mut self, c: char) -> bool {
+        let next = self.peek() == Some(c);
+        if next {
+            self.position += c.len_utf8();
+        }
+        next
+    }
+
+    /// What the input ends where it had to go on, as `json_value` tells it.
+    fn unexpected(&self, position: usize, expected: Expected) -> DeserializeError {
+        match position == self.input.len() {
+            true => ParserError::UnexpectedEof(position, expected).into(),
+            false => ParserError::Unexpected(position, expected).into(),
+        }
+    }
+
+    fn scalar(&mut self) -> Result<JsonValue<'input>, DeserializeError> {
+        match scalar(self.options).parse(self.input, self.state()) {
+            Ok((value, state)) => {
+                self.position = state.current;
+                Ok(value)
+            }
+            Err(ParserError::NoParse(position)) => Err(self.unexpected(position, Expected::VALUE)),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// An object key along with the colon after it, `expected` tells what
+    /// else could have been there instead of the key.
+    fn key(&mut self, expected: Expected) -> Result<Cow<'input, str>, DeserializeError> {
+        self.peek();
+        let key = match decoded_string(self.options).parse(self.input, self.state()) {
+            Ok((key, state)) => {
+                self.position = state.current;
+                key
+            }
+            Err(ParserError::NoParse(position)) => return Err(self.unexpected(position, expected)),
+            Err(error) => return Err(error.into()),
+        };
+        match self.eat(':') {
+            true => Ok(key),
+            false => Err(self.unexpected(self.position, Expected::COLON)),
+        }
+    }
+
+    /// Opens the container the next character starts.
+    fn enter(&mut self) -> Result<(), DeserializeError> {
+        let max_depth = self.options.max_depth.unwrap_or(DEFAULT_MAX_DEPTH);
+        if self.depth >= max_depth {
+            return Err(ParserError::DepthLimit(self.position).into());
+        }
+        self.depth += 1;
+        self.position += 1;
+        Ok(())
+    }
+
+    /// Closes the container, a visitor that didn't take every element leaves
+    /// the rest in the way of the closing. So does one that took them all
+    /// without looking for more, before a trailing comma.
+    fn leave(&mut self, closing: char, expected: Expected) -> Result<(), DeserializeError> {
+        self.depth -= 1;
+        let position = self.position;
+        if self.trailing_commas() && self.eat(',') && self.eat(closing) {
+            return Ok(());
+        }
+        self.position = position;
+        match self.eat(closing) {
+            true => Ok(()),
+            false => Err(self.unexpected(self.position, expected)),
+        }
+    }
+
+    fn trailing_commas(&self) -> bool {
+        self.options.trailing_commas && !self.options.strict
+    }
+}
+
+/// Hands the number over as the widest type that holds it exactly, integers
+/// beyond 64 bits are only there in arbitrary precision mode.
+fn visit_number<'de, V: Visitor<'de>>(
+    number: &Number<'de>,
+    visitor: V,
+) -> Result<V::Value, DeserializeError> {
+    match number.resolve() {
+        N::PosInt(n) => visitor.visit_u64(n),
+        N::NegInt(n) => visitor.visit_i64(n),
+        N::Float(n) => match number.as_str().map(|text| (text.parse(), text.parse())) {
+            Some((Ok(n), _)) => visitor.visit_u128(n),
+            Some((_, Ok(n))) => visitor.visit_i128(n),
+            _ => visitor.visit_f64(n),
+        },
+        N::Raw(_) => unreachable!(),
+    }
+}
+
+fn visit_str<'de, V: Visitor<'de>>(
+    s: Cow<'de, str>,
+    visitor: V,
+) -> Result<V::Value, DeserializeError> {
+    match s {
+        Cow::Borrowed(s) => visitor.visit_borrowed_str(s),
+        Cow::Owned(s) => visitor.visit_string(s),
+    }
+}
+
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
+    type Error = DeserializeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        let next = self.peek();
+        let start = self.position;
+        let result = match next {
+            Some('[') => {
+                self.enter()?;
+                let value = visitor.visit_seq(Elements {
+                    de: &mut *self,
+                    first: true,
+                });
+                value.and_then(|value| {
+                    self.leave(']', Expected::CLOSE_BRACKET)?;
+                    Ok(value)
+                })
+            }
+            Some('{') => {
+                self.enter()?;
+                let value = visitor.visit_map(Members {
+                    de: &mut *self,
+                    first: true,
+                    keys: HashSet::new(),
+                });
+                value.and_then(|value| {
+                    self.leave('}', Expected::CLOSE_BRACE)?;
+                    Ok(value)
+                })
+            }
+            _ => match &mut self.scalar()? {
+                JsonValue::String(s) => visit_str(std::mem::take(s), visitor),
+                JsonValue::Number(n) => visit_number(n, visitor),
+                JsonValue::Boolean(b) => visitor.visit_bool(*b),
+                _ => visitor.visit_unit(),
+            },
+        };
+        result.map_err(|error| error.at(start))
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        self.peek();
+        if self.input[self.position..].starts_with("null") {
+            self.position += "null".len();
+            return visitor.visit_none();
+        }
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    /// Unit variants are strings, the others objects with the variant as
+    /// their single key, as `serde_json` has them.
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        let start = match self.peek() {
+            Some('"' | '\'') => self.position,
+            Some('{') => {
+                let start = self.position;
+                self.enter()?;
+                let value = visitor.visit_enum(&mut *self);
+                return value
+                    .and_then(|value| {
+                        self.leave('}', Expected::CLOSE_BRACE)?;
+                        Ok(value)
+                    })
+                    .map_err(|error| error.at(start));
+            }
+            _ => return self.deserialize_any(visitor),
+        };
+        let JsonValue::String(variant) = &mut self.scalar()? else {
+            unreachable!()
+        };
+        visitor
+            .visit_enum(std::mem::take(variant).into_deserializer())
+            .map_err(|error: DeserializeError| error.at(start))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+struct Elements<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+    first: bool,
+}
+
+impl<'de> de::SeqAccess<'de> for Elements<'_, 'de> {
+    type Error = DeserializeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, DeserializeError> {
+        if self.de.peek() == Some(']') {
+            return Ok(None);
+        }
+        if !std::mem::replace(&mut self.first, false) {
+            if !self.de.eat(',') {
+                let position = self.de.position;
+                return Err(self
+                    .de
+                    .unexpected(position, Expected::COMMA | Expected::CLOSE_BRACKET));
+            }
+            if self.de.trailing_commas() && self.de.peek() == Some(']') {
+                return Ok(None);
+            }
+        }
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+}
+
+struct Members<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+    first: bool,
+    /// The keys so far, kept only to find duplicates.
+    keys: HashSet<Cow<'de, str>>,
+}
+
+impl<'de> de::MapAccess<'de> for Members<'_, 'de> {
+    type Error = DeserializeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, DeserializeError> {
+        if self.de.peek() == Some('}') {
+            return Ok(None);
+        }
+        let trailing_commas = self.de.trailing_commas();
+        let expected = match std::mem::replace(&mut self.first, false) {
+            true => Expected::STRING | Expected::CLOSE_BRACE,
+            false => {
+                if !self.de.eat(',') {
+                    let position = self.de.position;
+                    return Err(self
+                        .de
+                        .unexpected(position, Expected::COMMA | Expected::CLOSE_BRACE));
+                }
+                if trailing_commas && self.de.peek() == Some('}') {
+                    return Ok(None);
+                }
+                match trailing_commas {
+                    true => Expected::STRING | Expected::CLOSE_BRACE,
+                    false => Expected::STRING,
+                }
+            }
+        };
+        self.de.peek();
+        let position = self.de.position;
+        let key = self.de.key(expected)?;
+        if self.de.options.duplicate_keys == DuplicateKeys::Error && !self.keys.insert(key.clone())
+        {
+            return Err(ParserError::DuplicateKey(position).into());
+        }
+        seed.deserialize(MapKey(key))
+            .map(Some)
+            .map_err(|error| error.at(position))
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, DeserializeError> {
+        seed.deserialize(&mut *self.de)
+    }
+}
+
+impl<'de> de::EnumAccess<'de> for &mut Deserializer<'de> {
+    type Error = DeserializeError;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self), DeserializeError> {
+        let key = self.key(Expected::STRING)?;
+        Ok((seed.deserialize(MapKey(key))?, self))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for &mut Deserializer<'de> {
+    type Error = DeserializeError;
+
+    fn unit_variant(self) -> Result<(), DeserializeError> {
+        de::Deserialize::deserialize(self)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, DeserializeError> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        de::Deserializer::deserialize_any(self, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        de::Deserializer::deserialize_any(self, visitor)
+    }
+}
+
+/// Object keys are strings, those of maps with integer keys get parsed.
+struct MapKey<'de>(Cow<'de, str>);
+
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+                match self.0.parse() {
+                    Ok(n) => visitor.$visit(n),
+                    Err(_) => self.deserialize_any(visitor),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for MapKey<'de> {
+    type Error = DeserializeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        visit_str(self.0, visitor)
+    }
+
+    deserialize_parsed! {
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        visitor.visit_enum(self.0.into_deserializer())
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool f32 f64 char str string bytes byte_buf unit unit_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::from_str;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Event<'input> {
+        name: &'input str,
+        #[serde(borrow)]
+        note: Cow<'input, str>,
+        tags: Vec<String>,
+        count: Option<u32>,
+        kind: Kind,
+        #[serde(default)]
+        scores: BTreeMap<u8, f64>,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    enum Kind {
+        Click,
+        Move { x: i32, y: i32 },
+        Key(char),
+    }
+
+    // test the struct decoding with borrowed strings
+    #[test]
+    fn test_from_str_as() {
+        let input = r#"{"name": "open", "note": "a\tb", "tags": ["x", "y"], "count": null,
+            "kind": {"Move": {"x": -1, "y": 2}}, "scores": {"1": 0.5}}"#;
+        let event: Event = from_str_as(input).unwrap();
+        assert_eq!(
+            event,
+            Event {
+                name: "open",
+                note: "a\tb".into(),
+                tags: vec!["x".into(), "y".into()],
+                count: None,
+                kind: Kind::Move { x: -1, y: 2 },
+                scores: BTreeMap::from([(1, 0.5)]),
+            }
+        );
+        assert!(matches!(event.note, Cow::Owned(_)));
+
+        let input = r#"{"name": "n", "note": "plain", "tags": [], "count": 3, "kind": "Click"}"#;
+        let event: Event = from_str_as(input).unwrap();
+        assert!(matches!(event.note, Cow::Borrowed("plain")));
+        assert_eq!((event.count, event.kind), (Some(3), Kind::Click));
+        assert_eq!(
+            from_str_as::<Kind>("{\"Key\": \"k\"}").unwrap(),
+            Kind::Key('k')
+        );
+
+        let input = "[1, -2, 2.5, \"s\", [true, null], {\"a\": {}}]";
+        assert_eq!(
+            from_str_as::<serde_json::Value>(input).unwrap(),
+            serde_json::from_str::<serde_json::Value>(input).unwrap()
+        );
+        assert_eq!(
+            from_str_as::<crate::Value>(input).unwrap(),
+            from_str(input).unwrap()
+        );
+        let options = ParserOptions {
+            arbitrary_precision: true,
+            ..ParserOptions::lenient()
+        };
+        assert_eq!(
+            from_str_as_with_options::<(u128, Vec<i8>)>(
+                "[340282366920938463463374607431768211455, [1, 2,],]",
+                options
+            )
+            .unwrap(),
+            (u128::MAX, vec![1, 2])
+        );
+    }
+
+    // test the syntax errors and those of the type
+    #[test]
+    fn test_from_str_as_errors() {
+        for input in [
+            "[1, 2",
+            "[1 2]",
+            "{\"a\" 1}",
+            "[01]",
+            "[1] 2",
+            "",
+            "{\"a\":1,}",
+        ] {
+            assert_eq!(
+                from_str_as::<crate::Value>(input),
+                Err(from_str(input).unwrap_err().root().clone().into()),
+                "{input}"
+            );
+        }
+        assert_eq!(
+            from_str_as::<Vec<u8>>("[1, 256]"),
+            Err(DeserializeError::Invalid(
+                4,
+                "invalid value: integer `256`, expected u8".into()
+            ))
+        );
+        let error = from_str_as::<Event>("{\"name\": \"a\\n\"}").unwrap_err();
+        assert_eq!(error.position(), 9);
+        let error = from_str_as::<Kind>("{\"Move\": {\"x\": 1}}").unwrap_err();
+        assert_eq!(error.to_string(), "missing field `y` at position 9");
+        assert_eq!(
+            from_str_as::<(u8,)>("[1, 2]"),
+            Err(ParserError::Unexpected(2, Expected::CLOSE_BRACKET).into())
+        );
+        let options = ParserOptions {
+            duplicate_keys: DuplicateKeys::Error,
+            max_depth: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(
+            from_str_as_with_options::<crate::Value>("{\"a\": 1, \"a\": 2}", options),
+            Err(ParserError::DuplicateKey(9).into())
+        );
+        assert_eq!(
+            from_str_as_with_options::<crate::Value>("[[]]", options),
+            Err(ParserError::DepthLimit(1).into())
+        );
+        assert!(from_str_as::<crate::Value>(&"[".repeat(1000)).is_err());
+    }
+}
@@ -0,0 +1,624 @@
+use std::borrow::Cow;
+use std::fmt::{self, Write};
+
+use crate::ser::CanonicalFormatter;
+use crate::{
+    expect, from_str, member_key, pat_ws, scalar, whitespace, Expected, Formatter, JsonValue,
+    Number, Parser, ParserError, ParserOptions, SerializeError, State,
+};
+
+/// Structural record of a `Document`. Scalars point into the side arrays,
+/// containers know where their subtree ends, object members are laid out as
+/// a `Key` record followed by the value subtree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Node {
+    String(u32),
+    Number(u32),
+    Boolean(bool),
+    Null,
+    Key(u32),
+    List { len: u32, end: u32 },
+    Object { len: u32, end: u32 },
+}
+
+/// Alternative layout of a parsed document, all the nodes live in a single
+/// pre-ordered array, strings and numbers are kept in parallel arrays and
+/// referred to by index. Traversing the whole document walks memory linearly,
+/// hashing it included.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Document<'input> {
+    nodes: Vec<Node>,
+    strings: Vec<Cow<'input, str>>,
+    numbers: Vec<Number<'input>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Item<'document, 'input> {
+    String(&'document str),
+    Number(&'document Number<'input>),
+    Boolean(bool),
+    Null,
+    List(Elements<'document, 'input>),
+    Object(Entries<'document, 'input>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NodeRef<'document, 'input> {
+    document: &'document Document<'input>,
+    index: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Elements<'document, 'input> {
+    document: &'document Document<'input>,
+    index: usize,
+    remaining: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Entries<'document, 'input> {
+    elements: Elements<'document, 'input>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum DocumentError {
+    #[error(transparent)]
+    Parse(#[from] ParserError),
+    /// More nodes, strings or numbers than 32 bit indices reach.
+    #[error("Document too large")]
+    TooLarge,
+}
+
+/// A container being written by `Document::to_string_canonical`, objects with
+/// their members sorted.
+enum Writing<'document, 'input> {
+    List(Elements<'document, 'input>),
+    Object(std::vec::IntoIter<(&'document str, NodeRef<'document, 'input>)>),
+}
+
+/// What is left to lay out of a value, members and containers being closed
+/// included, so that documents of any depth get laid out without recursing.
+enum Pending<'value, 'input> {
+    Value(&'value JsonValue<'input>),
+    Member(&'value (Cow<'input, str>, JsonValue<'input>)),
+    /// The container starting at the node is complete, with so many values.
+    Close(usize, usize),
+}
+
+fn index(len: usize) -> Result<u32, DocumentError> {
+    u32::try_from(len).map_err(|_| DocumentError::TooLarge)
+}
+
+impl<'input> Document<'input> {
+    pub fn from_value(value: &JsonValue<'input>) -> Result<Self, DocumentError> {
+        let mut document = Document::default();
+        document.push(value)?;
+        Ok(document)
+    }
+
+    pub fn root<'document>(&'document self) -> NodeRef<'document, 'input> {
+        NodeRef {
+            document: self,
+            index: 0,
+        }
+    }
+
+    /// Every string of the document, object keys included, in document order.
+    pub fn strings(&self) -> &[Cow<'input, str>] {
+        &self.strings
+    }
+
+    /// Every number of the document in document order.
+    pub fn numbers(&self) -> &[Number<'input>] {
+        &self.numbers
+    }
+
+    pub fn to_value(&self) -> JsonValue<'input> {
+        self.root().to_value()
+    }
+
+    /// The canonical form of the document after RFC 8785, the text that
+    /// `to_string_canonical` gives for its value, written straight from the
+    /// layout. Fails on `NaN` and the infinities.
+    pub fn to_string_canonical(&self) -> Result<String, SerializeError> {
+        let mut out = String::new();
+        let mut formatter = CanonicalFormatter { non_finite: false };
+        match self.write_canonical(&mut out, &mut formatter) {
+            Ok(()) => Ok(out),
+            Err(_) if formatter.non_finite => Err(SerializeError::NonFinite),
+            Err(_) => unreachable!("writing to a String doesn't fail"),
+        }
+    }
+
+    /// Walks the nodes in order, only the members of each object are gathered
+    /// to be sorted by the UTF-16 code units of their keys.
+    fn write_canonical(&self, out: &mut impl Write, formatter: &mut impl Formatter) -> fmt::Result {
+        // the containers being written, with whether they are still empty
+        let mut stack: Vec<(Writing, bool)> = Vec::new();
+        let mut next = Some(self.root());
+        loop {
+            match next.take().map(|node| node.item()) {
+                Some(Item::List(elements)) => {
+                    formatter.begin_array(out)?;
+                    stack.push((Writing::List(elements), true));
+                }
+                Some(Item::Object(entries)) => {
+                    formatter.begin_object(out)?;
+                    let mut sorted: Vec<_> = entries.collect();
+                    sorted.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+                    stack.push((Writing::Object(sorted.into_iter()), true));
+                }
+                Some(Item::String(s)) => formatter.write_string(out, s)?,
+                Some(Item::Number(n)) => formatter.write_number(out, n)?,
+                Some(Item::Boolean(b)) => formatter.write_bool(out, b)?,
+                Some(Item::Null) => formatter.write_null(out)?,
+                None => {}
+            }
+            let Some((writing, empty)) = stack.last_mut() else {
+                return Ok(());
+            };
+            let first = std::mem::replace(empty, false);
+            match writing {
+                Writing::List(elements) => {
+                    if !first {
+                        formatter.end_array_value(out)?;
+                    }
+                    match elements.next() {
+                        Some(element) => {
+                            formatter.begin_array_value(out, first)?;
+                            next = Some(element);
+                        }
+                        None => {
+                            formatter.end_array(out)?;
+                            stack.pop();
+                        }
+                    }
+                }
+                Writing::Object(members) => {
+                    if !first {
+                        formatter.end_object_value(out)?;
+                    }
+                    match members.next() {
+                        Some((key, value)) => {
+                            formatter.begin_object_key(out, first)?;
+                            formatter.write_string(out, key)?;
+                            formatter.begin_object_value(out)?;
+                            next = Some(value);
+                        }
+                        None => {
+                            formatter.end_object(out)?;
+                            stack.pop();
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Lays the input out as it is parsed, with the grammar `from_str` has.
+    /// Containers are kept on an explicit stack along with the node they
+    /// start at and the number of values they have so far.
+    fn parse(input: &'input str) -> Result<Self, DocumentError> {
+        let options = ParserOptions::default();
+        let scalar = scalar(options);
+        let [list_start, list_end, object_start, object_end, comma] =
+            ["[", "]", "{", "}", ","].map(|p| pat_ws(p, options));
+        let first_key = member_key(options, Expected::STRING | Expected::CLOSE_BRACE);
+        let next_key = member_key(options, Expected::STRING);
+        let list_next = expect(
+            pat_ws("]", options),
+            Expected::COMMA | Expected::CLOSE_BRACKET,
+        );
+        let object_next = expect(
+            pat_ws("}", options),
+            Expected::COMMA | Expected::CLOSE_BRACE,
+        );
+        let mut document = Document::default();
+        let mut stack: Vec<(usize, usize)> = Vec::new();
+        // whether the value would be the first of a list, which could be
+        // closed instead
+        let mut first_element = false;
+        let (_, mut state) = whitespace(options).parse(input, State { current: 0 })?;
+        'value: loop {
+            let first = std::mem::take(&mut first_element);
+            match scalar.parse(input, state) {
+                Ok((value, new_state)) => {
+                    state = new_state;
+                    match value {
+                        JsonValue::String(s) => {
+                            let index = document.push_string(s)?;
+                            document.nodes.push(Node::String(index));
+                        }
+                        JsonValue::Number(n) => {
+                            document.numbers.push(n);
+                            let index = index(document.numbers.len() - 1)?;
+                            document.nodes.push(Node::Number(index));
+                        }
+                        JsonValue::Boolean(b) => document.nodes.push(Node::Boolean(b)),
+                        _ => document.nodes.push(Node::Null),
+                    }
+                }
+                Err(ParserError::NoParse(position)) => {
+                    let start = document.nodes.len();
+                    if let Ok((_, new_state)) = list_start.parse(input, state) {
+                        state = new_state;
+                        document.nodes.push(Node::List { len: 0, end: 0 });
+                        match list_end.parse(input, state) {
+                            Ok((_, new_state)) => state = new_state,
+                            Err(_) => {
+                                stack.push((start, 0));
+                                first_element = true;
+                                continue 'value;
+                            }
+                        }
+                    } else if let Ok((_, new_state)) = object_start.parse(input, state) {
+                        state = new_state;
+                        document.nodes.push(Node::Object { len: 0, end: 0 });
+                        match object_end.parse(input, state) {
+                            Ok((_, new_state)) => state = new_state,
+                            Err(_) => {
+                                let (key, new_state) = first_key.parse(input, state)?;
+                                state = new_state;
+                                document.push_key(key)?;
+                                stack.push((start, 0));
+                                continue 'value;
+                            }
+                        }
+                    } else {
+                        let expected = match first {
+                            true => Expected::VALUE | Expected::CLOSE_BRACKET,
+                            false => Expected::VALUE,
+                        };
+                        return Err(ParserError::Unexpected(position, expected).into());
+                    }
+                    document.close(start, 0)?;
+                }
+                Err(error) => return Err(error.into()),
+            }
+            // move on to the next value, closing the containers that end here
+            while let Some((start, len)) = stack.last_mut() {
+                *len += 1;
+                let is_list = matches!(document.nodes[*start], Node::List { .. });
+                if let Ok((_, new_state)) = comma.parse(input, state) {
+                    state = new_state;
+                    if !is_list {
+                        let (key, new_state) = next_key.parse(input, state)?;
+                        state = new_state;
+                        document.push_key(key)?;
+                    }
+                    continue 'value;
+                }
+                (_, state) = match is_list {
+                    true => list_next.parse(input, state)?,
+                    false => object_next.parse(input, state)?,
+                };
+                let (start, len) = stack.pop().expect("a container was on the stack");
+                document.close(start, len)?;
+            }
+            break;
+        }
+        let (_, state) = whitespace(options).parse(input, state)?;
+        if state.current != input.len() {
+            return Err(ParserError::TrailingCharacters(state.current).into());
+        }
+        Ok(document)
+    }
+
+    fn push_key(&mut self, key: Cow<'input, str>) -> Result<(), DocumentError> {
+        let index = self.push_string(key)?;
+        self.nodes.push(Node::Key(index));
+        Ok(())
+    }
+
+    /// Completes the container starting at the node, with its subtree ending
+    /// at the last node.
+    fn close(&mut self, start: usize, len: usize) -> Result<(), DocumentError> {
+        let (len, end) = (index(len)?, index(self.nodes.len())?);
+        match &mut self.nodes[start] {
+            Node::List {
+                len: at,
+                end: end_at,
+            }
+            | Node::Object {
+                len: at,
+                end: end_at,
+            } => {
+                (*at, *end_at) = (len, end);
+            }
+            _ => panic!("internal error in Document::close, node is not a container"),
+        }
+        Ok(())
+    }
+
+    fn push_string(&mut self, s: Cow<'input, str>) -> Result<u32, DocumentError> {
+        self.strings.push(s);
+        index(self.strings.len() - 1)
+    }
+
+    fn push(&mut self, value: &JsonValue<'input>) -> Result<(), DocumentError> {
+        let mut pending = vec![Pending::Value(value)];
+        while let Some(next) = pending.pop() {
+            let value = match next {
+                Pending::Value(value) => value,
+                Pending::Member((key, value)) => {
+                    self.push_key(key.clone())?;
+                    pending.push(Pending::Value(value));
+                    continue;
+                }
+                Pending::Close(start, len) => {
+                    self.close(start, len)?;
+                    continue;
+                }
+            };
+            match value {
+                JsonValue::String(s) => {
+                    let index = self.push_string(s.clone())?;
+                    self.nodes.push(Node::String(index));
+                }
+                JsonValue::Number(n) => {
+                    self.numbers.push(n.clone());
+                    let index = index(self.numbers.len() - 1)?;
+                    self.nodes.push(Node::Number(index));
+                }
+                JsonValue::Boolean(b) => self.nodes.push(Node::Boolean(*b)),
+                JsonValue::Null => self.nodes.push(Node::Null),
+                JsonValue::List(values) => {
+                    pending.push(Pending::Close(self.nodes.len(), values.len()));
+                    self.nodes.push(Node::List { len: 0, end: 0 });
+                    pending.extend(values.iter().rev().map(Pending::Value));
+                }
+                JsonValue::Object(pairs) => {
+                    pending.push(Pending::Close(self.nodes.len(), pairs.len()));
+                    self.nodes.push(Node::Object { len: 0, end: 0 });
+                    pending.extend(pairs.iter().rev().map(Pending::Member));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn next_sibling(&self, index: usize) -> usize {
+        match self.nodes[index] {
+            Node::List { end, .. } | Node::Object { end, .. } => end as usize,
+            Node::Key(_) => self.next_sibling(index + 1),
+            _ => index + 1,
+        }
+    }
+}
+
+impl<'document, 'input> NodeRef<'document, 'input> {
+    pub fn item(&self) -> Item<'document, 'input> {
+        let document = self.document;
+        match document.nodes[self.index] {
+            Node::String(index) => Item::String(&document.strings[index as usize]),
+            Node::Number(index) => Item::Number(&document.numbers[index as usize]),
+            Node::Boolean(b) => Item::Boolean(b),
+            Node::Null => Item::Null,
+            Node::List { len, .. } => Item::List(Elements {
+                document,
+                index: self.index + 1,
+                remaining: len as usize,
+            }),
+            Node::Object { len, .. } => Item::Object(Entries {
+                elements: Elements {
+                    document,
+                    index: self.index + 1,
+                    remaining: len as usize,
+                },
+            }),
+            Node::Key(_) => panic!("internal error in NodeRef::item, node is a key"),
+        }
+    }
+
+    /// Builds the subtree from its last node back to the node itself, the
+    /// values of a container being on top of the stack once it is reached.
+    pub fn to_value(&self) -> JsonValue<'input> {
+        let document = self.document;
+        let end = document.next_sibling(self.index);
+        // values along with their key if they are members of an object
+        let mut stack: Vec<(Option<Cow<'input, str>>, JsonValue<'input>)> = Vec::new();
+        for node in document.nodes[self.index..end].iter().rev() {
+            let value = match *node {
+                Node::String(index) => JsonValue::String(document.strings[index as usize].clone()),
+                Node::Number(index) => JsonValue::Number(document.numbers[index as usize].clone()),
+                Node::Boolean(b) => JsonValue::Boolean(b),
+                Node::Null => JsonValue::Null,
+                Node::Key(index) => {
+                    let Some((None, value)) = stack.pop() else {
+                        panic!("internal error in NodeRef::to_value, key without a value")
+                    };
+                    stack.push((Some(document.strings[index as usize].clone()), value));
+                    continue;
+                }
+                Node::List { len, .. } => {
+                    let values = stack.drain(stack.len() - len as usize..).rev();
+                    JsonValue::List(crate::Shared::new(values.map(|(_, value)| value).collect()))
+                }
+                Node::Object { len, .. } => {
+                    let members = stack.drain(stack.len() - len as usize..).rev();
+                    JsonValue::Object(crate::Shared::new(
+                        members
+                            .map(|(key, value)| (key.unwrap_or_default(), value))
+                            .collect(),
+                    ))
+                }
+            };
+            stack.push((None, value));
+        }
+        let Some((None, value)) = stack.pop() else {
+            panic!("internal error in NodeRef::to_value, no value built")
+        };
+        value
+    }
+}
+
+impl<'document, 'input> Iterator for Elements<'document, 'input> {
+    type Item = NodeRef<'document, 'input>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let node = NodeRef {
+            document: self.document,
+            index: self.index,
+        };
+        self.index = self.document.next_sibling(self.index);
+        self.remaining -= 1;
+        Some(node)
+    }
+}
+
+impl<'document, 'input> Iterator for Entries<'document, 'input> {
+    type Item = (&'document str, NodeRef<'document, 'input>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let key = self.elements.next()?;
+        let Node::Key(index) = key.document.nodes[key.index] else {
+            panic!("internal error in Entries::next, node is not a key")
+        };
+        Some((
+            &key.document.strings[index as usize],
+            NodeRef {
+                document: key.document,
+                index: key.index + 1,
+            },
+        ))
+    }
+}
+
+/// Parses the input and lays it out as a `Document` along the way, without
+/// building a `JsonValue` first. Errors are those of `from_str`.
+pub fn document_from_str(input: &str) -> Result<Document<'_>, DocumentError> {
+    Document::parse(input).map_err(|error| match error {
+        // the labels of the error come from parsing the input again, failing
+        // is the rare case
+        DocumentError::Parse(unlabeled) => match from_str(input) {
+            Err(error) => error.into(),
+            Ok(value) => {
+                value.drop_iteratively();
+                unlabeled.into()
+            }
+        },
+        error => error,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::to_string_canonical;
+
+    // test the document round trip
+    #[test]
+    fn test_document_to_value() {
+        let input = "{\"a\": [1, {\"b\": null}, \"c\"], \"d\": true}";
+        let document = document_from_str(input).unwrap();
+        assert_eq!(document.to_value(), from_str(input).unwrap());
+        assert_eq!(document.strings(), &["a", "b", "c", "d"]);
+        assert_eq!(document.numbers(), &[1u64.into()]);
+    }
+
+    // test the document traversal
+    #[test]
+    fn test_document_traversal() {
+        let document = document_from_str("[[1, 2], {\"k\": 3}, 4]").unwrap();
+        let Item::List(mut elements) = document.root().item() else {
+            panic!("root is not a list")
+        };
+        assert!(matches!(elements.next().unwrap().item(), Item::List(_)));
+        let Item::Object(mut entries) = elements.next().unwrap().item() else {
+            panic!("second element is not an object")
+        };
+        let (key, value) = entries.next().unwrap();
+        assert_eq!((key, value.item()), ("k", Item::Number(&3u64.into())));
+        assert_eq!(entries.next(), None);
+        assert_eq!(elements.next().unwrap().item(), Item::Number(&4u64.into()));
+        assert_eq!(elements.next(), None);
+    }
+
+    // test the layout of a document too deep to recurse into
+    #[test]
+    fn test_document_deep() {
+        let depth = 100_000;
+        let input = format!("{}{{\"a\": 1}}{}", "[".repeat(depth), "]".repeat(depth));
+        let document = document_from_str(&input).unwrap();
+        assert_eq!(document.nodes.len(), depth + 3);
+        assert_eq!(
+            document.nodes[0],
+            Node::List {
+                len: 1,
+                end: depth as u32 + 3
+            }
+        );
+        let value = document.to_value();
+        assert_eq!(value.pointer(&"/0".repeat(depth)).unwrap()["a"], 1.into());
+        assert_eq!(Document::from_value(&value), Ok(document.clone()));
+        value.drop_iteratively();
+        let canonical = document.to_string_canonical().unwrap();
+        assert_eq!(canonical, input.replace(' ', ""));
+        assert_eq!(index(u32::MAX as usize + 1), Err(DocumentError::TooLarge));
+    }
+
+    // test the document parser against from_str
+    #[test]
+    fn test_document_parse() {
+        for input in [
+            "[]",
+            " {} ",
+            "[[], {}, [{}]]",
+            "{\"a\": {\"b\": [1, 2]}, \"c\": \"d\\n\"}",
+        ] {
+            let value = from_str(input).unwrap();
+            let document = document_from_str(input).unwrap();
+            assert_eq!(document, Document::from_value(&value).unwrap());
+            assert_eq!(document.to_value(), value);
+        }
+        for input in [
+            "",
+            "[",
+            "[,]",
+            "[1,]",
+            "{\"a\" 1}",
+            "{\"a\": 1,}",
+            "[1] 2",
+            "{1: 2}",
+        ] {
+            let error = from_str(input).unwrap_err();
+            assert_eq!(document_from_str(input), Err(error.into()));
+        }
+    }
+
+    // test the canonical form and the hash of a document
+    #[test]
+    fn test_document_canonical() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        let hash = |document: &Document| {
+            let mut hasher = DefaultHasher::new();
+            document.hash(&mut hasher);
+            hasher.finish()
+        };
+        let input = "{\"b\": [1.50, -0, 1e2], \"\\u20ac\": {}, \"a\": {\"\\ud83d\\ude00\": null, \"z\": true}}";
+        let document = document_from_str(input).unwrap();
+        let canonical = document.to_string_canonical().unwrap();
+        assert_eq!(
+            canonical,
+            to_string_canonical(&from_str(input).unwrap()).unwrap()
+        );
+        assert_eq!(
+            canonical,
+            "{\"a\":{\"z\":true,\"\u{1f600}\":null},\"b\":[1.5,0,100],\"\u{20ac}\":{}}"
+        );
+        let again = document_from_str(input).unwrap();
+        assert_eq!(hash(&document), hash(&again));
+        let other = document_from_str("{\"b\": [1.50, -0, 1e2], \"\\u20ac\": {}}").unwrap();
+        assert_ne!(hash(&document), hash(&other));
+        let non_finite = Document::from_value(&JsonValue::Number(f64::NAN.into())).unwrap();
+        assert_eq!(
+            non_finite.to_string_canonical(),
+            Err(SerializeError::NonFinite)
+        );
+    }
+}
@@ -0,0 +1,138 @@
+use std::borrow::Cow;
+
+use crate::JsonValue;
+
+type Members<'input> = Vec<(Cow<'input, str>, JsonValue<'input>)>;
+
+/// A member of an object, there or not, as `JsonValue::entry` gives.
+#[derive(Debug)]
+pub enum Entry<'value, 'input> {
+    Occupied(OccupiedEntry<'value, 'input>),
+    Vacant(VacantEntry<'value, 'input>),
+}
+
+/// The first member with the key.
+#[derive(Debug)]
+pub struct OccupiedEntry<'value, 'input> {
+    pairs: &'value mut Members<'input>,
+    index: usize,
+}
+
+#[derive(Debug)]
+pub struct VacantEntry<'value, 'input> {
+    pairs: &'value mut Members<'input>,
+    key: Cow<'input, str>,
+}
+
+impl<'value, 'input> Entry<'value, 'input> {
+    pub(crate) fn new(pairs: &'value mut Members<'input>, key: Cow<'input, str>) -> Self {
+        match pairs.iter().position(|(k, _)| *k == key) {
+            Some(index) => Entry::Occupied(OccupiedEntry { pairs, index }),
+            None => Entry::Vacant(VacantEntry { pairs, key }),
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    pub fn or_insert(self, default: JsonValue<'input>) -> &'value mut JsonValue<'input> {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with(
+        self,
+        default: impl FnOnce() -> JsonValue<'input>,
+    ) -> &'value mut JsonValue<'input> {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    /// Inserts `null` when the member isn't there.
+    pub fn or_default(self) -> &'value mut JsonValue<'input> {
+        self.or_insert_with(JsonValue::default)
+    }
+
+    pub fn and_modify(mut self, f: impl FnOnce(&mut JsonValue<'input>)) -> Self {
+        if let Entry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+        self
+    }
+}
+
+impl<'value, 'input> OccupiedEntry<'value, 'input> {
+    pub fn key(&self) -> &str {
+        &self.pairs[self.index].0
+    }
+
+    pub fn get(&self) -> &JsonValue<'input> {
+        &self.pairs[self.index].1
+    }
+
+    pub fn get_mut(&mut self) -> &mut JsonValue<'input> {
+        &mut self.pairs[self.index].1
+    }
+
+    pub fn into_mut(self) -> &'value mut JsonValue<'input> {
+        &mut self.pairs[self.index].1
+    }
+
+    /// Replaces the value, returning the previous one.
+    pub fn insert(&mut self, value: JsonValue<'input>) -> JsonValue<'input> {
+        std::mem::replace(self.get_mut(), value)
+    }
+
+    /// Removes the member, keeping the order of the others.
+    pub fn remove(self) -> JsonValue<'input> {
+        let (_, value) = self.pairs.remove(self.index);
+        value
+    }
+}
+
+impl<'value, 'input> VacantEntry<'value, 'input> {
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Adds the member after the others.
+    pub fn insert(self, value: JsonValue<'input>) -> &'value mut JsonValue<'input> {
+        self.pairs.push((self.key, value));
+        &mut self.pairs.last_mut().unwrap().1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::from_str;
+
+    // test the building of nested objects through entries
+    #[test]
+    fn test_entry() {
+        let mut value = from_str("{\"a\": 1}").unwrap();
+        value
+            .entry("config")
+            .or_insert_with(|| JsonValue::from_map(Vec::<(&str, _)>::new()))
+            .entry("depth")
+            .or_insert(2.into());
+        value.entry("a").and_modify(|a| *a = 3.into()).or_default();
+        value.entry("b").or_default();
+        assert_eq!(
+            value.to_string(),
+            "{\"a\":3,\"config\":{\"depth\":2},\"b\":null}"
+        );
+
+        let Entry::Occupied(entry) = value.entry("a") else {
+            panic!("not occupied")
+        };
+        assert_eq!(entry.key(), "a");
+        assert_eq!(entry.remove(), JsonValue::from(3));
+        assert!(matches!(value.entry("a"), Entry::Vacant(_)));
+    }
+}
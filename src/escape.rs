@@ -0,0 +1,113 @@
+use std::borrow::Cow;
+use std::fmt::Write;
+
+/// How strings get escaped when written out. Quotes, backslashes and control
+/// characters are escaped regardless.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct EscapeOptions {
+    /// Escape `<`, `>`, `&`, U+2028 and U+2029 as well, so that the output
+    /// can be embedded in a `<script>` element or evaluated as JavaScript.
+    pub html_safe: bool,
+    /// Escape everything outside of ASCII as well, characters beyond U+FFFF as
+    /// surrogate pairs, for consumers that can't handle UTF-8.
+    pub escape_non_ascii: bool,
+}
+
+enum Escape {
+    Short(&'static str),
+    /// The `\uXXXX` form.
+    Unicode,
+}
+
+fn escape_char(c: char, options: EscapeOptions) -> Option<Escape> {
+    match c {
+        '"' => Some(Escape::Short("\\\"")),
+        '\\' => Some(Escape::Short("\\\\")),
+        '\u{8}' => Some(Escape::Short("\\b")),
+        '\u{c}' => Some(Escape::Short("\\f")),
+        '\n' => Some(Escape::Short("\\n")),
+        '\r' => Some(Escape::Short("\\r")),
+        '\t' => Some(Escape::Short("\\t")),
+        '\u{0}'..='\u{1f}' => Some(Escape::Unicode),
+        '<' | '>' | '&' | '\u{2028}' | '\u{2029}' if options.html_safe => Some(Escape::Unicode),
+        c if options.escape_non_ascii && !c.is_ascii() => Some(Escape::Unicode),
+        _ => None,
+    }
+}
+
+/// Escapes the text to go between the quotes of a JSON string, borrowing it
+/// as is when there is nothing to escape.
+pub fn escape_str(s: &str, options: EscapeOptions) -> Cow<'_, str> {
+    let Some(first) = s.find(|c| escape_char(c, options).is_some()) else {
+        return Cow::Borrowed(s);
+    };
+    let mut escaped = String::with_capacity(s.len() + 8);
+    escaped.push_str(&s[..first]);
+    for c in s[first..].chars() {
+        match escape_char(c, options) {
+            None => escaped.push(c),
+            Some(Escape::Short(escape)) => escaped.push_str(escape),
+            Some(Escape::Unicode) => {
+                for unit in c.encode_utf16(&mut [0; 2]) {
+                    write!(escaped, "\\u{unit:04x}").unwrap();
+                }
+            }
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // test the string escaping
+    #[test]
+    fn test_escape_str() {
+        let options = EscapeOptions::default();
+        assert!(matches!(
+            escape_str("plain", options),
+            Cow::Borrowed("plain")
+        ));
+        assert_eq!(
+            escape_str("a\"b\\c\n\u{1}</script>", options),
+            "a\\\"b\\\\c\\n\\u0001</script>"
+        );
+        let html = EscapeOptions {
+            html_safe: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            escape_str("</script>&\u{2028}\u{2029}", html),
+            "\\u003c/script\\u003e\\u0026\\u2028\\u2029"
+        );
+    }
+
+    // test the escaping against the parser
+    #[test]
+    fn test_escape_round_trip() {
+        let text = "tab\there \"é\" \u{0} <&> \u{2028}";
+        let html = EscapeOptions {
+            html_safe: true,
+            ..Default::default()
+        };
+        let input = format!("\"{}\"", escape_str(text, html));
+        assert_eq!(crate::from_str(&input).unwrap().as_str(), Some(text));
+    }
+
+    // test the escaping of everything outside of ASCII
+    #[test]
+    fn test_escape_non_ascii() {
+        let ascii = EscapeOptions {
+            escape_non_ascii: true,
+            ..Default::default()
+        };
+        let text = "é\u{2028}€😀\n";
+        assert_eq!(
+            escape_str(text, ascii),
+            "\\u00e9\\u2028\\u20ac\\ud83d\\ude00\\n"
+        );
+        let input = format!("\"{}\"", escape_str(text, ascii));
+        assert_eq!(crate::from_str(&input).unwrap().as_str(), Some(text));
+    }
+}
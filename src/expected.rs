@@ -0,0 +1,84 @@
+use std::fmt;
+use std::ops::BitOr;
+
+/// The set of tokens that would have been accepted where parsing failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Expected(u8);
+
+const NAMES: [&str; 6] = ["a value", "a string", "`:`", "`,`", "`]`", "`}`"];
+const TOKENS: [&str; 6] = ["value", "string", ":", ",", "]", "}"];
+
+impl Expected {
+    pub const VALUE: Expected = Expected(1);
+    /// An object key.
+    pub const STRING: Expected = Expected(1 << 1);
+    pub const COLON: Expected = Expected(1 << 2);
+    pub const COMMA: Expected = Expected(1 << 3);
+    pub const CLOSE_BRACKET: Expected = Expected(1 << 4);
+    pub const CLOSE_BRACE: Expected = Expected(1 << 5);
+
+    pub fn contains(self, other: Expected) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// The tokens one by one, `value` and `string` for those two and the
+    /// character itself for the others.
+    pub fn tokens(self) -> Vec<&'static str> {
+        TOKENS
+            .iter()
+            .enumerate()
+            .filter(|(bit, _)| self.0 & 1 << bit != 0)
+            .map(|(_, token)| *token)
+            .collect()
+    }
+}
+
+impl BitOr for Expected {
+    type Output = Expected;
+
+    fn bitor(self, other: Expected) -> Expected {
+        Expected(self.0 | other.0)
+    }
+}
+
+/// Lists the tokens as in "`,` or `]`".
+impl fmt::Display for Expected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<_> = NAMES
+            .iter()
+            .enumerate()
+            .filter(|(bit, _)| self.0 & 1 << bit != 0)
+            .map(|(_, name)| *name)
+            .collect();
+        match names.split_last() {
+            None => f.write_str("nothing"),
+            Some((last, [])) => f.write_str(last),
+            Some((last, rest)) => write!(f, "{} or {}", rest.join(", "), last),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // test the expected tokens listing
+    #[test]
+    fn test_expected_display() {
+        assert_eq!(Expected::VALUE.to_string(), "a value");
+        assert_eq!(
+            (Expected::COMMA | Expected::CLOSE_BRACKET).to_string(),
+            "`,` or `]`"
+        );
+        let any = Expected::STRING | Expected::COLON | Expected::CLOSE_BRACE;
+        assert_eq!(any.to_string(), "a string, `:` or `}`");
+        assert!(any.contains(Expected::COLON));
+        assert!(!any.contains(Expected::COMMA));
+        assert_eq!(Expected::default().to_string(), "nothing");
+        assert_eq!(any.tokens(), vec!["string", ":", "}"]);
+    }
+}
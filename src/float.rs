@@ -0,0 +1,330 @@
+use std::sync::OnceLock;
+
+const SMALLEST_POWER_OF_FIVE: i64 = -342;
+const LARGEST_POWER_OF_FIVE: i64 = 308;
+const MANTISSA_EXPLICIT_BITS: i32 = 52;
+const MINIMUM_EXPONENT: i32 = -1023;
+const INFINITE_POWER: i32 = 0x7ff;
+const MIN_EXPONENT_ROUND_TO_EVEN: i64 = -4;
+const MAX_EXPONENT_ROUND_TO_EVEN: i64 = 23;
+
+const F64_POWERS_OF_TEN: [f64; 23] = [
+    1e0, 1e1, 1e2, 1e3, 1e4, 1e5, 1e6, 1e7, 1e8, 1e9, 1e10, 1e11, 1e12, 1e13, 1e14, 1e15, 1e16,
+    1e17, 1e18, 1e19, 1e20, 1e21, 1e22,
+];
+
+/// Little endian limbs, just enough arithmetic to build the table of powers.
+type BigUint = Vec<u64>;
+
+fn big_mul_small(a: &mut BigUint, x: u64) {
+    let mut carry = 0;
+    for limb in a.iter_mut() {
+        let product = *limb as u128 * x as u128 + carry;
+        *limb = product as u64;
+        carry = product >> 64;
+    }
+    if carry != 0 {
+        a.push(carry as u64);
+    }
+}
+
+fn big_bits(a: &BigUint) -> usize {
+    a.iter().rposition(|&limb| limb != 0).map_or(0, |index| {
+        64 * index + 64 - a[index].leading_zeros() as usize
+    })
+}
+
+fn big_bit(a: &BigUint, index: usize) -> bool {
+    a.get(index / 64)
+        .is_some_and(|limb| limb >> (index % 64) & 1 == 1)
+}
+
+/// The 128 most significant bits of `a`, truncated.
+fn big_top128(a: &BigUint) -> (u64, u64) {
+    let bits = big_bits(a);
+    let top = (1..=128).fold(0u128, |top, index| {
+        (top << 1) | (bits >= index && big_bit(a, bits - index)) as u128
+    });
+    ((top >> 64) as u64, top as u64)
+}
+
+/// `floor(2^exponent / divisor)` by shift and subtract.
+fn big_pow2_div(exponent: usize, divisor: &BigUint) -> BigUint {
+    let greater_or_equal = |a: &BigUint, b: &BigUint| {
+        let len = a.len().max(b.len());
+        (0..len)
+            .rev()
+            .map(|index| {
+                let a = a.get(index).copied().unwrap_or(0);
+                let b = b.get(index).copied().unwrap_or(0);
+                a.cmp(&b)
+            })
+            .find(|ordering| ordering.is_ne())
+            .is_none_or(|ordering| ordering.is_gt())
+    };
+    let mut quotient = vec![0; exponent / 64 + 1];
+    let mut remainder: BigUint = vec![0; divisor.len() + 1];
+    for index in (0..=exponent).rev() {
+        // remainder = remainder * 2 + bit, the only set bit is the top one
+        let mut carry = (index == exponent) as u64;
+        for limb in remainder.iter_mut() {
+            let next = *limb >> 63;
+            *limb = (*limb << 1) | carry;
+            carry = next;
+        }
+        if greater_or_equal(&remainder, divisor) {
+            let mut borrow = false;
+            for (position, limb) in remainder.iter_mut().enumerate() {
+                let subtrahend = divisor.get(position).copied().unwrap_or(0);
+                let (difference, first) = limb.overflowing_sub(subtrahend);
+                let (difference, second) = difference.overflowing_sub(borrow as u64);
+                *limb = difference;
+                borrow = first || second;
+            }
+            quotient[index / 64] |= 1 << (index % 64);
+        }
+    }
+    quotient
+}
+
+/// 128 bit approximations of the powers of five from 5^-342 to 5^308,
+/// normalized so that the most significant bit is set. Positive powers are
+/// truncated, negative ones are reciprocals rounded up, as in the reference
+/// implementation of the Eisel-Lemire algorithm.
+fn powers_of_five() -> &'static [(u64, u64)] {
+    static POWERS: OnceLock<Vec<(u64, u64)>> = OnceLock::new();
+    POWERS.get_or_init(|| {
+        (SMALLEST_POWER_OF_FIVE..=LARGEST_POWER_OF_FIVE)
+            .map(|q| {
+                let mut power: BigUint = vec![1];
+                (0..q.unsigned_abs()).for_each(|_| big_mul_small(&mut power, 5));
+                if q >= 0 {
+                    return big_top128(&power);
+                }
+                let z = big_bits(&power);
+                let exponent = if q >= -27 { z + 127 } else { 2 * z + 128 };
+                let mut reciprocal = big_pow2_div(exponent, &power);
+                // + 1, there is always a zero bit to carry into
+                let zero = reciprocal
+                    .iter()
+                    .position(|&limb| limb != u64::MAX)
+                    .unwrap();
+                reciprocal[..zero].fill(0);
+                reciprocal[zero] += 1;
+                big_top128(&reciprocal)
+            })
+            .collect()
+    })
+}
+
+fn compute_product_approx(q: i64, w: u64, precision: i32) -> (u64, u64) {
+    let mask = u64::MAX >> precision;
+    let (hi5, lo5) = powers_of_five()[(q - SMALLEST_POWER_OF_FIVE) as usize];
+    let product = w as u128 * hi5 as u128;
+    let (mut first_lo, mut first_hi) = (product as u64, (product >> 64) as u64);
+    if first_hi & mask == mask {
+        let second_hi = ((w as u128 * lo5 as u128) >> 64) as u64;
+        first_lo = first_lo.wrapping_add(second_hi);
+        if second_hi > first_lo {
+            first_hi += 1;
+        }
+    }
+    (first_lo, first_hi)
+}
+
+/// Approximation of `floor(log2(10^q)) + 63`.
+fn power(q: i32) -> i32 {
+    (q.wrapping_mul(152_170 + 65536) >> 16) + 63
+}
+
+/// Eisel-Lemire, `w * 10^q` as biased mantissa and exponent bits, `None` when
+/// the 128 bit product is too coarse to round correctly.
+fn compute_float(q: i64, mut w: u64) -> Option<(u64, i32)> {
+    if w == 0 || q < SMALLEST_POWER_OF_FIVE {
+        return Some((0, 0));
+    } else if q > LARGEST_POWER_OF_FIVE {
+        return Some((0, INFINITE_POWER));
+    }
+    let lz = w.leading_zeros();
+    w <<= lz;
+    let (lo, hi) = compute_product_approx(q, w, MANTISSA_EXPLICIT_BITS + 3);
+    if lo == u64::MAX && !(-27..=55).contains(&q) {
+        return None;
+    }
+    let upperbit = (hi >> 63) as i32;
+    let shift = upperbit + 64 - MANTISSA_EXPLICIT_BITS - 3;
+    let mut mantissa = hi >> shift;
+    let mut power2 = power(q as i32) + upperbit - lz as i32 - MINIMUM_EXPONENT;
+    if power2 <= 0 {
+        if -power2 + 1 >= 64 {
+            return Some((0, 0));
+        }
+        // subnormal
+        mantissa >>= -power2 + 1;
+        mantissa += mantissa & 1;
+        mantissa >>= 1;
+        power2 = (mantissa >= (1 << MANTISSA_EXPLICIT_BITS)) as i32;
+        return Some((mantissa, power2));
+    }
+    // exactly halfway between two floats, round to even
+    if lo <= 1
+        && (MIN_EXPONENT_ROUND_TO_EVEN..=MAX_EXPONENT_ROUND_TO_EVEN).contains(&q)
+        && mantissa & 3 == 1
+        && (mantissa << shift) == hi
+    {
+        mantissa &= !1;
+    }
+    mantissa += mantissa & 1;
+    mantissa >>= 1;
+    if mantissa >= (2 << MANTISSA_EXPLICIT_BITS) {
+        mantissa = 1 << MANTISSA_EXPLICIT_BITS;
+        power2 += 1;
+    }
+    mantissa &= !(1 << MANTISSA_EXPLICIT_BITS);
+    if power2 >= INFINITE_POWER {
+        return Some((0, INFINITE_POWER));
+    }
+    Some((mantissa, power2))
+}
+
+/// Clinger's fast path, exact whenever both the mantissa and the power of ten
+/// are exactly representable.
+fn fast_path(mantissa: u64, q: i64) -> Option<f64> {
+    if mantissa > 1 << 53 {
+        return None;
+    }
+    match q {
+        -22..=-1 => Some(mantissa as f64 / F64_POWERS_OF_TEN[-q as usize]),
+        0..=22 => Some(mantissa as f64 * F64_POWERS_OF_TEN[q as usize]),
+        // the extra zeros may fit in the mantissa
+        23..=37 => {
+            let mantissa = mantissa.checked_mul(10u64.pow(q as u32 - 22))?;
+            (mantissa <= 1 << 53).then_some(mantissa as f64 * 1e22)
+        }
+        _ => None,
+    }
+}
+
+/// Converts the text of a number already validated by the parser, without
+/// scanning it twice like `str::parse` does. The first 19 significant digits
+/// go through the fast path or Eisel-Lemire, the rare inputs neither can round
+/// correctly fall back to `str::parse`.
+pub(crate) fn parse_f64(text: &str) -> f64 {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text),
+    };
+    if !digits.starts_with(|c: char| c.is_ascii_digit()) {
+        // NaN and Infinity
+        return text.parse().unwrap();
+    }
+    let (digits, explicit) = match digits.split_once(['e', 'E']) {
+        Some((digits, explicit)) => {
+            let magnitude = explicit
+                .trim_start_matches(['+', '-'])
+                .bytes()
+                .fold(0i64, |e, b| (e * 10 + (b - b'0') as i64).min(1 << 20));
+            if explicit.starts_with('-') {
+                (digits, -magnitude)
+            } else {
+                (digits, magnitude)
+            }
+        }
+        None => (digits, 0),
+    };
+    let mut mantissa = 0u64;
+    let mut significant = 0;
+    let mut exponent = 0i64;
+    let mut many_digits = false;
+    let mut fraction = false;
+    for byte in digits.bytes() {
+        match byte {
+            b'.' => fraction = true,
+            b'0' if significant == 0 => exponent -= fraction as i64,
+            _ if significant < 19 => {
+                mantissa = mantissa * 10 + (byte - b'0') as u64;
+                significant += 1;
+                exponent -= fraction as i64;
+            }
+            _ => {
+                many_digits |= byte != b'0';
+                exponent += !fraction as i64;
+            }
+        }
+    }
+    let q = exponent + explicit;
+
+    let value = match fast_path(mantissa, q).filter(|_| !many_digits) {
+        Some(value) => value,
+        None => match compute_float(q, mantissa) {
+            // the truncated digits can't change the outcome
+            Some(fp) if !many_digits || compute_float(q, mantissa + 1) == Some(fp) => {
+                f64::from_bits(fp.0 | (fp.1 as u64) << MANTISSA_EXPLICIT_BITS)
+            }
+            _ => return text.parse().unwrap(),
+        },
+    };
+    if negative {
+        -value
+    } else {
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // test the table of powers against the reference values
+    #[test]
+    fn test_powers_of_five() {
+        let powers = powers_of_five();
+        assert_eq!(powers[0], (0xeef453d6923bd65a, 0x113faa2906a13b3f));
+        assert_eq!(powers[342], (0x8000000000000000, 0x0000000000000000));
+        assert_eq!(powers[342 - 1], (0xcccccccccccccccc, 0xcccccccccccccccd));
+        assert_eq!(powers[342 + 28], (0x813f3978f8940984, 0x4000000000000000));
+    }
+
+    // test the conversion against the standard library
+    #[test]
+    fn test_parse_f64() {
+        let mut inputs = vec![
+            "0".to_string(),
+            "-0".to_string(),
+            "0.1".to_string(),
+            "-65.613616999999977".to_string(),
+            "1.7976931348623157e308".to_string(),
+            "1.7976931348623159e308".to_string(),
+            "4.9e-324".to_string(),
+            "2.2250738585072011e-308".to_string(),
+            "9007199254740993".to_string(),
+            "123456789012345678901234567890e-10".to_string(),
+            "0.000000000000000000000000000000000001e+40".to_string(),
+            "2.4703282292062328e-324".to_string(),
+            "1e400".to_string(),
+            "1e-400".to_string(),
+        ];
+        // xorshift, to cover many digit counts and exponents
+        let mut state = 0x2545f4914f6cdd1du64;
+        for _ in 0..10000 {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            let digits = state % 10u64.pow((state >> 60) as u32 % 19 + 1);
+            let point = (state >> 32) as usize % 20;
+            let mut text = digits.to_string();
+            if point < text.len() {
+                text.insert(point.max(1), '.');
+            }
+            inputs.push(format!("{}e{}", text, ((state >> 40) % 700) as i64 - 350));
+        }
+        for input in &inputs {
+            assert_eq!(
+                parse_f64(input).to_bits(),
+                input.parse::<f64>().unwrap().to_bits(),
+                "{}",
+                input
+            );
+        }
+    }
+}
@@ -0,0 +1,430 @@
+use std::borrow::Cow;
+
+use crate::JsonValue;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum GeoJsonError {
+    #[error("Expected {0}")]
+    Expected(&'static str),
+    #[error("Missing `{0}` member")]
+    MissingMember(&'static str),
+    #[error("Unknown GeoJSON type `{0}`")]
+    UnknownType(String),
+    #[error("Positions are arrays of two or more numbers")]
+    InvalidPosition,
+    #[error("LineStrings need at least two positions")]
+    ShortLineString,
+    #[error("Linear rings need at least four positions and have to be closed")]
+    InvalidRing,
+    #[error("GeometryCollections nested too deep")]
+    TooDeep,
+}
+
+/// How deep `GeometryCollection`s nest in a geometry read from a value. RFC
+/// 7946 discourages nesting them at all, the limit keeps untrusted input from
+/// exhausting the stack.
+const MAX_COLLECTION_DEPTH: usize = 128;
+
+pub type Position = Vec<f64>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Geometry {
+    Point(Position),
+    MultiPoint(Vec<Position>),
+    LineString(Vec<Position>),
+    MultiLineString(Vec<Vec<Position>>),
+    Polygon(Vec<Vec<Position>>),
+    MultiPolygon(Vec<Vec<Vec<Position>>>),
+    GeometryCollection(Vec<Geometry>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Feature<'input> {
+    pub id: Option<JsonValue<'input>>,
+    pub geometry: Option<Geometry>,
+    pub properties: Option<JsonValue<'input>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeatureCollection<'input> {
+    pub features: Vec<Feature<'input>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeoJson<'input> {
+    Geometry(Geometry),
+    Feature(Feature<'input>),
+    FeatureCollection(FeatureCollection<'input>),
+}
+
+type Pairs<'value, 'input> = &'value [(Cow<'input, str>, JsonValue<'input>)];
+
+fn as_object<'value, 'input>(
+    value: &'value JsonValue<'input>,
+) -> Result<Pairs<'value, 'input>, GeoJsonError> {
+    match value {
+        JsonValue::Object(pairs) => Ok(pairs),
+        _ => Err(GeoJsonError::Expected("an object")),
+    }
+}
+
+fn as_list<'value, 'input>(
+    value: &'value JsonValue<'input>,
+) -> Result<&'value [JsonValue<'input>], GeoJsonError> {
+    match value {
+        JsonValue::List(values) => Ok(values),
+        _ => Err(GeoJsonError::Expected("an array")),
+    }
+}
+
+fn member<'value, 'input>(
+    pairs: Pairs<'value, 'input>,
+    key: &'static str,
+) -> Option<&'value JsonValue<'input>> {
+    pairs
+        .iter()
+        .find(|(k, _)| *k == key)
+        .map(|(_, value)| value)
+}
+
+fn required<'value, 'input>(
+    pairs: Pairs<'value, 'input>,
+    key: &'static str,
+) -> Result<&'value JsonValue<'input>, GeoJsonError> {
+    member(pairs, key).ok_or(GeoJsonError::MissingMember(key))
+}
+
+fn type_of<'value>(pairs: Pairs<'value, '_>) -> Result<&'value str, GeoJsonError> {
+    match required(pairs, "type")? {
+        JsonValue::String(kind) => Ok(kind),
+        _ => Err(GeoJsonError::Expected("a string type")),
+    }
+}
+
+fn position(value: &JsonValue) -> Result<Position, GeoJsonError> {
+    let position = as_list(value)?
+        .iter()
+        .map(|n| match n {
+            JsonValue::Number(n) => Ok(n.as_f64()),
+            _ => Err(GeoJsonError::InvalidPosition),
+        })
+        .collect::<Result<Position, _>>()?;
+    if position.len() < 2 {
+        return Err(GeoJsonError::InvalidPosition);
+    }
+    Ok(position)
+}
+
+fn positions(value: &JsonValue) -> Result<Vec<Position>, GeoJsonError> {
+    as_list(value)?.iter().map(position).collect()
+}
+
+fn line_string(value: &JsonValue) -> Result<Vec<Position>, GeoJsonError> {
+    let line = positions(value)?;
+    if line.len() < 2 {
+        return Err(GeoJsonError::ShortLineString);
+    }
+    Ok(line)
+}
+
+fn polygon(value: &JsonValue) -> Result<Vec<Vec<Position>>, GeoJsonError> {
+    as_list(value)?
+        .iter()
+        .map(|ring| {
+            let ring = positions(ring)?;
+            if ring.len() < 4 || ring.first() != ring.last() {
+                return Err(GeoJsonError::InvalidRing);
+            }
+            Ok(ring)
+        })
+        .collect()
+}
+
+fn numbers<'input>(position: &[f64]) -> JsonValue<'input> {
+    JsonValue::List(crate::Shared::new(
+        position
+            .iter()
+            .map(|n| JsonValue::Number((*n).into()))
+            .collect(),
+    ))
+}
+
+fn nested<'input, T>(items: &[T], f: impl Fn(&T) -> JsonValue<'input>) -> JsonValue<'input> {
+    JsonValue::List(crate::Shared::new(items.iter().map(f).collect()))
+}
+
+/// An object of the given `type` with a single other member.
+fn typed<'input>(
+    kind: &'static str,
+    member: &'static str,
+    value: JsonValue<'input>,
+) -> JsonValue<'input> {
+    JsonValue::Object(crate::Shared::new(vec![
+        ("type".into(), JsonValue::String(kind.into())),
+        (member.into(), value),
+    ]))
+}
+
+impl Geometry {
+    /// Fails on `GeometryCollection`s nested more than 128 deep.
+    pub fn from_value(value: &JsonValue) -> Result<Self, GeoJsonError> {
+        Geometry::from_value_at(value, 0)
+    }
+
+    /// The geometry within `depth` collections.
+    fn from_value_at(value: &JsonValue, depth: usize) -> Result<Self, GeoJsonError> {
+        let pairs = as_object(value)?;
+        let kind = type_of(pairs)?;
+        if kind == "GeometryCollection" {
+            if depth == MAX_COLLECTION_DEPTH {
+                return Err(GeoJsonError::TooDeep);
+            }
+            let geometries = as_list(required(pairs, "geometries")?)?;
+            return Ok(Geometry::GeometryCollection(
+                geometries
+                    .iter()
+                    .map(|geometry| Geometry::from_value_at(geometry, depth + 1))
+                    .collect::<Result<_, _>>()?,
+            ));
+        }
+        let coordinates = required(pairs, "coordinates")?;
+        Ok(match kind {
+            "Point" => Geometry::Point(position(coordinates)?),
+            "MultiPoint" => Geometry::MultiPoint(positions(coordinates)?),
+            "LineString" => Geometry::LineString(line_string(coordinates)?),
+            "MultiLineString" => Geometry::MultiLineString(
+                as_list(coordinates)?
+                    .iter()
+                    .map(line_string)
+                    .collect::<Result<_, _>>()?,
+            ),
+            "Polygon" => Geometry::Polygon(polygon(coordinates)?),
+            "MultiPolygon" => Geometry::MultiPolygon(
+                as_list(coordinates)?
+                    .iter()
+                    .map(polygon)
+                    .collect::<Result<_, _>>()?,
+            ),
+            other => return Err(GeoJsonError::UnknownType(other.to_string())),
+        })
+    }
+
+    /// Collections are kept on an explicit stack, so that geometries of any
+    /// depth convert.
+    pub fn to_value(&self) -> JsonValue<'static> {
+        // the collections being converted with their geometries converted so
+        // far
+        let mut stack: Vec<(std::slice::Iter<Geometry>, Vec<JsonValue<'static>>)> = Vec::new();
+        let mut geometry = self;
+        loop {
+            let mut value = match geometry {
+                Geometry::GeometryCollection(geometries) => {
+                    stack.push((geometries.iter(), Vec::with_capacity(geometries.len())));
+                    None
+                }
+                geometry => Some(geometry.coordinates_value()),
+            };
+            loop {
+                let Some((geometries, values)) = stack.last_mut() else {
+                    return value.expect("a converted geometry");
+                };
+                values.extend(value.take());
+                if let Some(next) = geometries.next() {
+                    geometry = next;
+                    break;
+                }
+                let Some((_, values)) = stack.pop() else {
+                    unreachable!("a collection was on the stack")
+                };
+                value = Some(typed("GeometryCollection", "geometries", values.into()));
+            }
+        }
+    }
+
+    /// The value of a geometry other than a collection.
+    fn coordinates_value(&self) -> JsonValue<'static> {
+        let (kind, value) = match self {
+            Geometry::Point(p) => ("Point", numbers(p)),
+            Geometry::MultiPoint(ps) => ("MultiPoint", nested(ps, |p| numbers(p))),
+            Geometry::LineString(ps) => ("LineString", nested(ps, |p| numbers(p))),
+            Geometry::MultiLineString(lines) => (
+                "MultiLineString",
+                nested(lines, |ps| nested(ps, |p| numbers(p))),
+            ),
+            Geometry::Polygon(rings) => ("Polygon", nested(rings, |ps| nested(ps, |p| numbers(p)))),
+            Geometry::MultiPolygon(polygons) => (
+                "MultiPolygon",
+                nested(polygons, |rings| {
+                    nested(rings, |ps| nested(ps, |p| numbers(p)))
+                }),
+            ),
+            Geometry::GeometryCollection(_) => {
+                unreachable!("collections are converted by Geometry::to_value")
+            }
+        };
+        typed(kind, "coordinates", value)
+    }
+}
+
+impl<'input> Feature<'input> {
+    pub fn from_value(value: &JsonValue<'input>) -> Result<Self, GeoJsonError> {
+        let pairs = as_object(value)?;
+        match type_of(pairs)? {
+            "Feature" => {}
+            _ => return Err(GeoJsonError::Expected("a Feature")),
+        }
+        let geometry = match required(pairs, "geometry")? {
+            JsonValue::Null => None,
+            geometry => Some(Geometry::from_value(geometry)?),
+        };
+        let properties = match member(pairs, "properties") {
+            None | Some(JsonValue::Null) => None,
+            Some(properties @ JsonValue::Object(_)) => Some(properties.clone()),
+            Some(_) => return Err(GeoJsonError::Expected("an object of properties")),
+        };
+        Ok(Feature {
+            id: member(pairs, "id").cloned(),
+            geometry,
+            properties,
+        })
+    }
+
+    pub fn to_value(&self) -> JsonValue<'input> {
+        let mut pairs = vec![("type".into(), JsonValue::String("Feature".into()))];
+        if let Some(id) = &self.id {
+            pairs.push(("id".into(), id.clone()));
+        }
+        pairs.push((
+            "geometry".into(),
+            self.geometry
+                .as_ref()
+                .map_or(JsonValue::Null, Geometry::to_value),
+        ));
+        pairs.push((
+            "properties".into(),
+            self.properties.clone().unwrap_or(JsonValue::Null),
+        ));
+        JsonValue::Object(crate::Shared::new(pairs))
+    }
+}
+
+impl<'input> FeatureCollection<'input> {
+    pub fn from_value(value: &JsonValue<'input>) -> Result<Self, GeoJsonError> {
+        let pairs = as_object(value)?;
+        match type_of(pairs)? {
+            "FeatureCollection" => {}
+            _ => return Err(GeoJsonError::Expected("a FeatureCollection")),
+        }
+        Ok(FeatureCollection {
+            features: as_list(required(pairs, "features")?)?
+                .iter()
+                .map(Feature::from_value)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    pub fn to_value(&self) -> JsonValue<'input> {
+        JsonValue::Object(crate::Shared::new(vec![
+            ("type".into(), JsonValue::String("FeatureCollection".into())),
+            ("features".into(), nested(&self.features, Feature::to_value)),
+        ]))
+    }
+}
+
+impl<'input> GeoJson<'input> {
+    pub fn from_value(value: &JsonValue<'input>) -> Result<Self, GeoJsonError> {
+        match type_of(as_object(value)?)? {
+            "Feature" => Ok(GeoJson::Feature(Feature::from_value(value)?)),
+            "FeatureCollection" => Ok(GeoJson::FeatureCollection(FeatureCollection::from_value(
+                value,
+            )?)),
+            _ => Ok(GeoJson::Geometry(Geometry::from_value(value)?)),
+        }
+    }
+
+    pub fn to_value(&self) -> JsonValue<'input> {
+        match self {
+            GeoJson::Geometry(geometry) => geometry.to_value(),
+            GeoJson::Feature(feature) => feature.to_value(),
+            GeoJson::FeatureCollection(collection) => collection.to_value(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // test the feature collection parsing
+    #[test]
+    fn test_feature_collection() {
+        let input = "{\"type\": \"FeatureCollection\", \"features\": [{\"type\": \"Feature\", \"id\": 1, \"geometry\": {\"type\": \"Point\", \"coordinates\": [102.0, 0.5]}, \"properties\": {\"name\": \"spot\"}}, {\"type\": \"Feature\", \"geometry\": null, \"properties\": null}]}";
+        let value = crate::from_str(input).unwrap();
+        let collection = FeatureCollection::from_value(&value).unwrap();
+        assert_eq!(collection.features.len(), 2);
+        assert_eq!(
+            collection.features[0].geometry,
+            Some(Geometry::Point(vec![102.0, 0.5]))
+        );
+        assert_eq!(
+            collection.features[0].id,
+            Some(JsonValue::Number(1u64.into()))
+        );
+        assert_eq!(collection.features[1].geometry, None);
+        assert_eq!(collection.to_value(), value);
+        assert_eq!(
+            GeoJson::from_value(&value).unwrap(),
+            GeoJson::FeatureCollection(collection)
+        );
+    }
+
+    // test the coordinates validation
+    #[test]
+    fn test_geometry_validation() {
+        let parse = |input| Geometry::from_value(&crate::from_str(input).unwrap());
+        assert_eq!(
+            parse("{\"type\": \"Point\", \"coordinates\": [1]}"),
+            Err(GeoJsonError::InvalidPosition)
+        );
+        assert_eq!(
+            parse("{\"type\": \"LineString\", \"coordinates\": [[1, 2]]}"),
+            Err(GeoJsonError::ShortLineString)
+        );
+        assert_eq!(
+            parse("{\"type\": \"Polygon\", \"coordinates\": [[[0, 0], [1, 0], [1, 1], [0, 1]]]}"),
+            Err(GeoJsonError::InvalidRing)
+        );
+        assert_eq!(
+            parse("{\"type\": \"Circle\", \"coordinates\": [0, 0]}"),
+            Err(GeoJsonError::UnknownType("Circle".to_string()))
+        );
+        let polygon =
+            "{\"type\": \"Polygon\", \"coordinates\": [[[0, 0], [1, 0], [1, 1], [0, 0]]]}";
+        assert_eq!(
+            parse(polygon).unwrap().to_value(),
+            crate::from_str(polygon).unwrap()
+        );
+    }
+
+    // test the collections nested too deep
+    #[test]
+    fn test_geometry_deep() {
+        let nested = |depth: usize| {
+            let input = format!(
+                "{}{{\"type\": \"Point\", \"coordinates\": [1, 2]}}{}",
+                "{\"type\": \"GeometryCollection\", \"geometries\": [".repeat(depth),
+                "]}".repeat(depth)
+            );
+            crate::from_str(&input).unwrap().into_owned()
+        };
+        let value = nested(50_000);
+        assert_eq!(Geometry::from_value(&value), Err(GeoJsonError::TooDeep));
+        value.drop_iteratively();
+        let value = nested(MAX_COLLECTION_DEPTH);
+        assert_eq!(Geometry::from_value(&value).unwrap().to_value(), value);
+        assert_eq!(
+            Geometry::from_value(&nested(MAX_COLLECTION_DEPTH + 1)),
+            Err(GeoJsonError::TooDeep)
+        );
+    }
+}
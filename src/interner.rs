@@ -0,0 +1,275 @@
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::HashSet;
+
+use crate::{parse_with, JsonValue, Number, ParserError, ParserOptions, Strings};
+
+/// A pool of strings, identical strings share a single allocation. Values
+/// parsed with `from_str_interned` borrow from the pool rather than from the
+/// input, so the input can be dropped and the pool reused across documents.
+#[derive(Debug, Default)]
+pub struct Interner {
+    strings: RefCell<HashSet<Box<str>>>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn intern(&self, s: &str) -> &str {
+        let mut strings = self.strings.borrow_mut();
+        let interned: &str = match strings.get(s) {
+            Some(interned) => interned,
+            None => {
+                strings.insert(s.into());
+                strings.get(s).unwrap()
+            }
+        };
+        // strings are boxed and never removed while the pool is borrowed,
+        // so they stay put for as long as `self` lives
+        unsafe { &*(interned as *const str) }
+    }
+
+    pub fn len(&self) -> usize {
+        self.strings.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.borrow().is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.strings.get_mut().clear()
+    }
+}
+
+/// How much a value would benefit from interning, see `JsonValue::sharing_stats`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SharingStats {
+    pub strings: usize,
+    pub unique_strings: usize,
+    pub keys: usize,
+    pub unique_keys: usize,
+    /// Bytes taken by repeated occurrences of strings and keys, this is the
+    /// amount an `Interner` would save.
+    pub duplicated_bytes: usize,
+}
+
+#[derive(Default)]
+struct SharingCounter<'value> {
+    stats: SharingStats,
+    strings: HashSet<&'value str>,
+    keys: HashSet<&'value str>,
+    all: HashSet<&'value str>,
+}
+
+impl<'value> SharingCounter<'value> {
+    fn count_str(&mut self, s: &'value str) {
+        if !self.all.insert(s) {
+            self.stats.duplicated_bytes += s.len();
+        }
+    }
+
+    /// Counts the value and everything in it, containers are walked with an
+    /// explicit stack so that values of any depth can be counted.
+    fn count(&mut self, value: &'value JsonValue<'_>) {
+        let mut pending = vec![value];
+        while let Some(value) = pending.pop() {
+            match value {
+                JsonValue::String(s) => {
+                    self.stats.strings += 1;
+                    self.strings.insert(s);
+                    self.count_str(s);
+                }
+                JsonValue::Object(pairs) => {
+                    for (key, value) in pairs.iter() {
+                        self.stats.keys += 1;
+                        self.keys.insert(key);
+                        self.count_str(key);
+                        pending.push(value);
+                    }
+                }
+                JsonValue::List(values) => pending.extend(values.iter()),
+                JsonValue::Number(_) | JsonValue::Boolean(_) | JsonValue::Null => {}
+            }
+        }
+    }
+}
+
+impl<'input> JsonValue<'input> {
+    /// Counts duplicated strings and object keys within the value.
+    pub fn sharing_stats(&self) -> SharingStats {
+        let mut counter = SharingCounter::default();
+        counter.count(self);
+        SharingStats {
+            unique_strings: counter.strings.len(),
+            unique_keys: counter.keys.len(),
+            ..counter.stats
+        }
+    }
+}
+
+/// Every string taken from the interner, object keys and escaped strings
+/// included.
+#[derive(Clone, Copy)]
+struct Interned<'interner>(&'interner Interner);
+
+impl<'input, 'interner: 'input> Strings<'input, 'interner> for Interned<'interner> {
+    fn key(self, key: Cow<'input, str>) -> Cow<'interner, str> {
+        Cow::Borrowed(self.0.intern(&key))
+    }
+
+    fn string(self, s: Cow<'input, str>) -> Cow<'interner, str> {
+        Cow::Borrowed(self.0.intern(&s))
+    }
+
+    fn number(self, n: Number<'input>) -> Number<'interner> {
+        n.map_str(&|text| self.0.intern(text))
+    }
+}
+
+/// Parses the input with every string and object key taken from `interner`
+/// as it is parsed, decoded escapes and all.
+pub fn from_str_interned<'interner>(
+    input: &str,
+    interner: &'interner Interner,
+) -> Result<JsonValue<'interner>, ParserError> {
+    parse_with(input, ParserOptions::default(), Interned(interner))
+}
+
+/// Object keys taken from the interner, strings borrowed from the input.
+#[derive(Clone, Copy)]
+struct KeysInterned<'output>(&'output Interner);
+
+impl<'output> Strings<'output, 'output> for KeysInterned<'output> {
+    fn key(self, key: Cow<'output, str>) -> Cow<'output, str> {
+        Cow::Borrowed(self.0.intern(&key))
+    }
+
+    fn string(self, s: Cow<'output, str>) -> Cow<'output, str> {
+        s
+    }
+
+    fn number(self, n: Number<'output>) -> Number<'output> {
+        n
+    }
+}
+
+/// Parses the input with object keys taken from `interner` as they are
+/// parsed, escaped ones included, while strings keep borrowing from the
+/// input. Arrays of objects of the same shape end up with a single copy of
+/// each key.
+pub fn from_str_keys_interned<'output>(
+    input: &'output str,
+    interner: &'output Interner,
+) -> Result<JsonValue<'output>, ParserError> {
+    parse_with(input, ParserOptions::default(), KeysInterned(interner))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::from_str;
+
+    // test the interner pool
+    #[test]
+    fn test_intern() {
+        let interner = Interner::new();
+        let first = interner.intern(&String::from("hello"));
+        let second = interner.intern(&String::from("hello"));
+        assert_eq!(first, "hello");
+        assert_eq!(first.as_ptr(), second.as_ptr());
+        assert_eq!(interner.len(), 1);
+    }
+
+    // test parsing several documents into the same interner
+    #[test]
+    fn test_from_str_interned() {
+        let interner = Interner::new();
+        let first = from_str_interned(&String::from("{\"key\": \"value\"}"), &interner).unwrap();
+        let second = from_str_interned(&String::from("[\"value\", \"key\"]"), &interner).unwrap();
+        let (JsonValue::Object(pairs), JsonValue::List(values)) = (&first, &second) else {
+            panic!("unexpected values")
+        };
+        let JsonValue::String(value) = &values[0] else {
+            panic!("unexpected value")
+        };
+        assert_eq!(pairs[0].0.as_ptr(), interner.intern("key").as_ptr());
+        assert_eq!(value.as_ptr(), interner.intern("value").as_ptr());
+        assert_eq!(interner.len(), 2);
+
+        let first = from_str_interned(&String::from("[\"a\\nb\"]"), &interner).unwrap();
+        let second =
+            from_str_interned(&String::from("{\"a\\nb\": \"a\\u000ab\"}"), &interner).unwrap();
+        let (JsonValue::List(values), JsonValue::Object(pairs)) = (&first, &second) else {
+            panic!("unexpected values")
+        };
+        let (JsonValue::String(escaped), JsonValue::String(unicode)) = (&values[0], &pairs[0].1)
+        else {
+            panic!("unexpected value")
+        };
+        assert_eq!(escaped, "a\nb");
+        assert!(matches!(escaped, Cow::Borrowed(_)));
+        assert_eq!(escaped.as_ptr(), pairs[0].0.as_ptr());
+        assert_eq!(escaped.as_ptr(), unicode.as_ptr());
+        assert_eq!(interner.len(), 3);
+    }
+
+    // test interning the object keys only
+    #[test]
+    fn test_from_str_keys_interned() {
+        let interner = Interner::new();
+        let input = "[{\"id\": \"a\", \"k\\u0069nd\": \"x\"}, {\"id\": \"b\", \"kind\": \"x\"}]";
+        let value = from_str_keys_interned(input, &interner).unwrap();
+        let JsonValue::List(values) = &value else {
+            panic!("not a list")
+        };
+        let keys: Vec<_> = values
+            .iter()
+            .flat_map(|value| match value {
+                JsonValue::Object(pairs) => pairs.iter().map(|(key, _)| key.as_ptr()).collect(),
+                _ => vec![],
+            })
+            .collect();
+        assert_eq!(keys[0], keys[2]);
+        assert_eq!(keys[1], keys[3]);
+        assert_eq!(interner.len(), 2);
+        assert_eq!(value, from_str(input).unwrap());
+        let JsonValue::String(value) = &values[0]["id"] else {
+            panic!("not a string")
+        };
+        assert_eq!(value.as_ptr(), input[9..].as_ptr());
+
+        let depth = 100_000;
+        let input = format!("{}1{}", "{\"k\": ".repeat(depth), "}".repeat(depth));
+        let value = from_str_keys_interned(&input, &interner).unwrap();
+        assert_eq!(interner.len(), 3);
+        value.drop_iteratively();
+    }
+
+    // test the sharing stats
+    #[test]
+    fn test_sharing_stats() {
+        let input = "[{\"id\": \"a\", \"kind\": \"event\"}, {\"id\": \"b\", \"kind\": \"event\"}]";
+        let stats = from_str(input).unwrap().sharing_stats();
+        assert_eq!(
+            stats,
+            SharingStats {
+                strings: 4,
+                unique_strings: 3,
+                keys: 4,
+                unique_keys: 2,
+                duplicated_bytes: "id".len() + "kind".len() + "event".len(),
+            }
+        );
+
+        let depth = 100_000;
+        let input = format!("{}\"a\"{}", "[{\"k\": ".repeat(depth), "}]".repeat(depth));
+        let value = from_str(&input).unwrap();
+        let stats = value.sharing_stats();
+        assert_eq!((stats.keys, stats.unique_keys), (depth, 1));
+        assert_eq!(stats.duplicated_bytes, depth - 1);
+        value.drop_iteratively();
+    }
+}
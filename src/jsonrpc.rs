@@ -0,0 +1,322 @@
+use std::borrow::Cow;
+
+use crate::{JsonValue, Number, ParserError};
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+pub const INTERNAL_ERROR: i64 = -32603;
+
+#[derive(Debug, Clone, thiserror::Error, PartialEq, Eq)]
+pub enum MessageError {
+    #[error(transparent)]
+    Parse(#[from] ParserError),
+    #[error("Invalid request: {0}")]
+    Invalid(&'static str),
+}
+
+impl MessageError {
+    /// The error object a server replies with when it receives such a message,
+    /// parse errors carry `ParserError::to_value` as their data.
+    pub fn to_error(&self) -> Error<'static> {
+        match self {
+            MessageError::Parse(error) => Error {
+                data: Some(error.to_value()),
+                ..Error::new(PARSE_ERROR, "Parse error")
+            },
+            MessageError::Invalid(_) => Error::new(INVALID_REQUEST, "Invalid Request"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Id<'input> {
+    Number(Number<'input>),
+    String(Cow<'input, str>),
+    Null,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Request<'input> {
+    pub id: Id<'input>,
+    pub method: Cow<'input, str>,
+    pub params: Option<JsonValue<'input>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notification<'input> {
+    pub method: Cow<'input, str>,
+    pub params: Option<JsonValue<'input>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Error<'input> {
+    pub code: i64,
+    pub message: Cow<'input, str>,
+    pub data: Option<JsonValue<'input>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response<'input> {
+    pub id: Id<'input>,
+    pub result: Result<JsonValue<'input>, Error<'input>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message<'input> {
+    Request(Request<'input>),
+    Notification(Notification<'input>),
+    Response(Response<'input>),
+}
+
+/// What arrives over the wire, either a single message or a batch of them.
+/// Every member of a batch is checked on its own, as the spec requires an
+/// error response per invalid member.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Incoming<'input> {
+    Single(Message<'input>),
+    Batch(Vec<Result<Message<'input>, MessageError>>),
+}
+
+fn member<'value, 'input>(
+    pairs: &'value [(Cow<'input, str>, JsonValue<'input>)],
+    key: &str,
+) -> Option<&'value JsonValue<'input>> {
+    pairs
+        .iter()
+        .find(|(k, _)| *k == key)
+        .map(|(_, value)| value)
+}
+
+fn params<'input>(
+    pairs: &[(Cow<'input, str>, JsonValue<'input>)],
+) -> Result<Option<JsonValue<'input>>, MessageError> {
+    match member(pairs, "params") {
+        None => Ok(None),
+        Some(params @ (JsonValue::Object(_) | JsonValue::List(_))) => Ok(Some(params.clone())),
+        Some(_) => Err(MessageError::Invalid("params must be structured")),
+    }
+}
+
+impl<'input> Id<'input> {
+    fn from_value(value: &JsonValue<'input>) -> Result<Self, MessageError> {
+        match value {
+            JsonValue::Number(n) => Ok(Id::Number(n.clone())),
+            JsonValue::String(s) => Ok(Id::String(s.clone())),
+            JsonValue::Null => Ok(Id::Null),
+            _ => Err(MessageError::Invalid(
+                "id must be a number, a string or null",
+            )),
+        }
+    }
+
+    pub fn to_value(&self) -> JsonValue<'input> {
+        match self {
+            Id::Number(n) => JsonValue::Number(n.clone()),
+            Id::String(s) => JsonValue::String(s.clone()),
+            Id::Null => JsonValue::Null,
+        }
+    }
+}
+
+impl<'input> Error<'input> {
+    pub fn new(code: i64, message: &'input str) -> Self {
+        Error {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    fn from_value(value: &JsonValue<'input>) -> Result<Self, MessageError> {
+        let JsonValue::Object(pairs) = value else {
+            return Err(MessageError::Invalid("error must be an object"));
+        };
+        let (Some(JsonValue::Number(code)), Some(JsonValue::String(message))) =
+            (member(pairs, "code"), member(pairs, "message"))
+        else {
+            return Err(MessageError::Invalid("error needs a code and a message"));
+        };
+        let Some(code) = code.as_i64() else {
+            return Err(MessageError::Invalid("error code must be an integer"));
+        };
+        Ok(Error {
+            code,
+            message: message.clone(),
+            data: member(pairs, "data").cloned(),
+        })
+    }
+
+    pub fn to_value(&self) -> JsonValue<'input> {
+        let mut pairs = vec![
+            ("code", JsonValue::Number(self.code.into())),
+            ("message", JsonValue::String(self.message.clone())),
+        ];
+        if let Some(data) = &self.data {
+            pairs.push(("data", data.clone()));
+        }
+        JsonValue::from(pairs)
+    }
+}
+
+impl<'input> Message<'input> {
+    pub fn from_value(value: &JsonValue<'input>) -> Result<Self, MessageError> {
+        let JsonValue::Object(pairs) = value else {
+            return Err(MessageError::Invalid("message must be an object"));
+        };
+        if member(pairs, "jsonrpc") != Some(&JsonValue::String("2.0".into())) {
+            return Err(MessageError::Invalid("jsonrpc must be \"2.0\""));
+        }
+        let id = member(pairs, "id").map(Id::from_value).transpose()?;
+        match (member(pairs, "method"), id) {
+            (Some(JsonValue::String(method)), Some(id)) => Ok(Message::Request(Request {
+                id,
+                method: method.clone(),
+                params: params(pairs)?,
+            })),
+            (Some(JsonValue::String(method)), None) => Ok(Message::Notification(Notification {
+                method: method.clone(),
+                params: params(pairs)?,
+            })),
+            (Some(_), _) => Err(MessageError::Invalid("method must be a string")),
+            (None, id) => {
+                let id = id.ok_or(MessageError::Invalid("response must have an id"))?;
+                let result = match (member(pairs, "result"), member(pairs, "error")) {
+                    (Some(result), None) => Ok(result.clone()),
+                    (None, Some(error)) => Err(Error::from_value(error)?),
+                    _ => {
+                        return Err(MessageError::Invalid(
+                            "response must have either a result or an error",
+                        ))
+                    }
+                };
+                Ok(Message::Response(Response { id, result }))
+            }
+        }
+    }
+
+    pub fn to_value(&self) -> JsonValue<'input> {
+        let mut pairs = vec![("jsonrpc", JsonValue::String("2.0".into()))];
+        match self {
+            Message::Request(request) => {
+                pairs.push(("id", request.id.to_value()));
+                pairs.push(("method", JsonValue::String(request.method.clone())));
+                pairs.extend(request.params.clone().map(|params| ("params", params)));
+            }
+            Message::Notification(notification) => {
+                pairs.push(("method", JsonValue::String(notification.method.clone())));
+                pairs.extend(notification.params.clone().map(|params| ("params", params)));
+            }
+            Message::Response(response) => {
+                pairs.push(("id", response.id.to_value()));
+                pairs.push(match &response.result {
+                    Ok(result) => ("result", result.clone()),
+                    Err(error) => ("error", error.to_value()),
+                });
+            }
+        }
+        JsonValue::from(pairs)
+    }
+}
+
+impl<'input> Incoming<'input> {
+    pub fn from_value(value: &JsonValue<'input>) -> Result<Self, MessageError> {
+        match value {
+            JsonValue::List(values) if values.is_empty() => {
+                Err(MessageError::Invalid("batch must not be empty"))
+            }
+            JsonValue::List(values) => Ok(Incoming::Batch(
+                values.iter().map(Message::from_value).collect(),
+            )),
+            value => Ok(Incoming::Single(Message::from_value(value)?)),
+        }
+    }
+}
+
+pub fn from_str(input: &str) -> Result<Incoming<'_>, MessageError> {
+    Incoming::from_value(&crate::from_str(input)?)
+}
+
+/// Builds the reply to a batch, responses are put into a single list, `None`
+/// when there is nothing to reply with as the batch held notifications only.
+pub fn batch_response<'input>(
+    responses: impl IntoIterator<Item = Response<'input>>,
+) -> Option<JsonValue<'input>> {
+    let responses: Vec<_> = responses
+        .into_iter()
+        .map(|response| Message::Response(response).to_value())
+        .collect();
+    (!responses.is_empty()).then(|| JsonValue::List(crate::Shared::new(responses)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // test the single message parsing
+    #[test]
+    fn test_message() {
+        let input =
+            "{\"jsonrpc\": \"2.0\", \"method\": \"subtract\", \"params\": [42, 23], \"id\": 1}";
+        let Incoming::Single(message) = from_str(input).unwrap() else {
+            panic!("not a single message")
+        };
+        assert_eq!(
+            message,
+            Message::Request(Request {
+                id: Id::Number(1u64.into()),
+                method: "subtract".into(),
+                params: Some(crate::from_str("[42, 23]").unwrap()),
+            })
+        );
+        assert_eq!(
+            message.to_value(),
+            crate::from_str(
+                "{\"jsonrpc\": \"2.0\", \"id\": 1, \"method\": \"subtract\", \"params\": [42, 23]}"
+            )
+            .unwrap()
+        );
+
+        let input = "{\"jsonrpc\": \"2.0\", \"error\": {\"code\": -32601, \"message\": \"Method not found\"}, \"id\": \"1\"}";
+        assert_eq!(
+            from_str(input).unwrap(),
+            Incoming::Single(Message::Response(Response {
+                id: Id::String("1".into()),
+                result: Err(Error::new(METHOD_NOT_FOUND, "Method not found")),
+            }))
+        );
+    }
+
+    // test the batch parsing
+    #[test]
+    fn test_batch() {
+        let input = "[{\"jsonrpc\": \"2.0\", \"method\": \"notify\"}, {\"foo\": \"boo\"}, 1]";
+        let Incoming::Batch(messages) = from_str(input).unwrap() else {
+            panic!("not a batch")
+        };
+        assert_eq!(
+            messages[0],
+            Ok(Message::Notification(Notification {
+                method: "notify".into(),
+                params: None,
+            }))
+        );
+        assert!(matches!(messages[1], Err(MessageError::Invalid(_))));
+        assert_eq!(
+            messages[2].as_ref().unwrap_err().to_error().code,
+            INVALID_REQUEST
+        );
+        assert_eq!(
+            Incoming::from_value(&JsonValue::List(crate::Shared::new(vec![]))),
+            Err(MessageError::Invalid("batch must not be empty"))
+        );
+        let error = from_str("[1,").unwrap_err();
+        let MessageError::Parse(parse_error) = &error else {
+            panic!("not a parse error")
+        };
+        assert_eq!(error.to_error().code, PARSE_ERROR);
+        assert_eq!(error.to_error().data, Some(parse_error.to_value()));
+        assert_eq!(batch_response(vec![]), None);
+    }
+}
@@ -0,0 +1,204 @@
+use std::ops::Range;
+
+use crate::{number, Parser, ParserOptions, State};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    Key,
+    String,
+    Number,
+    /// `true`, `false` and `null`.
+    Literal,
+    Punctuation,
+    Whitespace,
+    Comment,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Range<usize>,
+}
+
+/// Splits the input into classified tokens without building any values. The
+/// tokenization is lossless, spans of consecutive tokens cover the whole input,
+/// anything that can't be lexed becomes an `Error` token and lexing goes on.
+/// `//` and `/* */` comments are recognized for the sake of editors.
+pub fn tokenize(input: &str) -> Tokens<'_> {
+    Tokens { input, position: 0 }
+}
+
+#[derive(Debug, Clone)]
+pub struct Tokens<'input> {
+    input: &'input str,
+    position: usize,
+}
+
+impl<'input> Tokens<'input> {
+    fn rest(&self) -> &'input str {
+        &self.input[self.position..]
+    }
+
+    fn end_of(&self, pred: impl Fn(char) -> bool) -> usize {
+        self.rest()
+            .find(|c| !pred(c))
+            .map_or(self.input.len(), |index| self.position + index)
+    }
+
+    /// Whether the next character after `from`, past whitespace and comments,
+    /// is a colon.
+    fn is_followed_by_colon(&self, from: usize) -> bool {
+        let mut rest = &self.input[from..];
+        loop {
+            rest = rest.trim_start();
+            if let Some(comment) = rest.strip_prefix("//") {
+                rest = comment.find('\n').map_or("", |index| &comment[index..]);
+            } else if let Some(comment) = rest.strip_prefix("/*") {
+                match comment.find("*/") {
+                    Some(index) => rest = &comment[index + 2..],
+                    None => return false,
+                }
+            } else {
+                return rest.starts_with(':');
+            }
+        }
+    }
+
+    fn string(&self) -> (TokenKind, usize) {
+        let mut escaped = false;
+        for (index, c) in self.rest().char_indices().skip(1) {
+            match c {
+                '"' if !escaped => {
+                    let end = self.position + index + 1;
+                    if self.is_followed_by_colon(end) {
+                        return (TokenKind::Key, end);
+                    }
+                    return (TokenKind::String, end);
+                }
+                // unterminated, error up to the end of line
+                '\n' | '\r' => return (TokenKind::Error, self.position + index),
+                _ => escaped = c == '\\' && !escaped,
+            }
+        }
+        (TokenKind::Error, self.input.len())
+    }
+
+    fn comment(&self) -> (TokenKind, usize) {
+        if self.rest().starts_with("//") {
+            (TokenKind::Comment, self.end_of(|c| c != '\n'))
+        } else {
+            match self.rest()[2..].find("*/") {
+                Some(index) => (TokenKind::Comment, self.position + 2 + index + 2),
+                None => (TokenKind::Error, self.input.len()),
+            }
+        }
+    }
+
+    fn number(&self) -> (TokenKind, usize) {
+        let end = self.end_of(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E'));
+        let state = State {
+            current: self.position,
+        };
+        match number(ParserOptions::default()).parse(&self.input[..end], state) {
+            Ok((_, state)) if state.current == end => (TokenKind::Number, end),
+            _ => (TokenKind::Error, end),
+        }
+    }
+
+    fn word(&self) -> (TokenKind, usize) {
+        let end = self.end_of(|c| c.is_alphanumeric() || c == '_');
+        match &self.input[self.position..end] {
+            "true" | "false" | "null" => (TokenKind::Literal, end),
+            _ => (TokenKind::Error, end),
+        }
+    }
+}
+
+impl<'input> Iterator for Tokens<'input> {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let c = self.rest().chars().next()?;
+        let (kind, end) = match c {
+            c if c.is_whitespace() => (TokenKind::Whitespace, self.end_of(char::is_whitespace)),
+            '{' | '}' | '[' | ']' | ',' | ':' => (TokenKind::Punctuation, self.position + 1),
+            '"' => self.string(),
+            '/' if self.rest().starts_with("//") || self.rest().starts_with("/*") => self.comment(),
+            '-' | '0'..='9' => self.number(),
+            c if c.is_alphabetic() => self.word(),
+            c => (TokenKind::Error, self.position + c.len_utf8()),
+        };
+        let span = self.position..end;
+        self.position = end;
+        Some(Token { kind, span })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(input: &str) -> Vec<(TokenKind, &str)> {
+        tokenize(input)
+            .map(|token| (token.kind, &input[token.span]))
+            .collect()
+    }
+
+    // test the token classification
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            kinds("{\"a\" /* c */ : [1.5, \"b\", true], // end\n\"c\":null}"),
+            vec![
+                (TokenKind::Punctuation, "{"),
+                (TokenKind::Key, "\"a\""),
+                (TokenKind::Whitespace, " "),
+                (TokenKind::Comment, "/* c */"),
+                (TokenKind::Whitespace, " "),
+                (TokenKind::Punctuation, ":"),
+                (TokenKind::Whitespace, " "),
+                (TokenKind::Punctuation, "["),
+                (TokenKind::Number, "1.5"),
+                (TokenKind::Punctuation, ","),
+                (TokenKind::Whitespace, " "),
+                (TokenKind::String, "\"b\""),
+                (TokenKind::Punctuation, ","),
+                (TokenKind::Whitespace, " "),
+                (TokenKind::Literal, "true"),
+                (TokenKind::Punctuation, "]"),
+                (TokenKind::Punctuation, ","),
+                (TokenKind::Whitespace, " "),
+                (TokenKind::Comment, "// end"),
+                (TokenKind::Whitespace, "\n"),
+                (TokenKind::Key, "\"c\""),
+                (TokenKind::Punctuation, ":"),
+                (TokenKind::Literal, "null"),
+                (TokenKind::Punctuation, "}"),
+            ]
+        );
+    }
+
+    // test the error tokens
+    #[test]
+    fn test_tokenize_errors() {
+        assert_eq!(
+            kinds("[01, nope, @, \"open\n]"),
+            vec![
+                (TokenKind::Punctuation, "["),
+                (TokenKind::Error, "01"),
+                (TokenKind::Punctuation, ","),
+                (TokenKind::Whitespace, " "),
+                (TokenKind::Error, "nope"),
+                (TokenKind::Punctuation, ","),
+                (TokenKind::Whitespace, " "),
+                (TokenKind::Error, "@"),
+                (TokenKind::Punctuation, ","),
+                (TokenKind::Whitespace, " "),
+                (TokenKind::Error, "\"open"),
+                (TokenKind::Whitespace, "\n"),
+                (TokenKind::Punctuation, "]"),
+            ]
+        );
+    }
+}
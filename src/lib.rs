@@ -1,14 +1,397 @@
 #![allow(dead_code)]
 
+pub mod avro;
+#[cfg(test)]
+mod conformance;
+mod cst;
+#[cfg(feature = "serde")]
+mod de;
+mod document;
+mod entry;
+mod escape;
+mod expected;
+mod float;
+#[cfg(feature = "geojson")]
+pub mod geojson;
+mod interner;
+pub mod jsonrpc;
+mod lexer;
+mod lint;
+mod ndjson;
+#[cfg(feature = "protobuf")]
+pub mod protobuf;
+mod reader;
+mod ser;
+#[cfg(feature = "serde")]
+mod serde;
+#[cfg(feature = "serde_json")]
+mod serde_json;
+mod spanned;
+pub mod sse;
+mod stream;
+mod structural;
+#[cfg(feature = "toml")]
+pub mod toml;
+mod truncated;
+mod unescape;
+mod writer;
+#[cfg(feature = "yaml")]
+pub mod yaml;
+
+pub use cst::{format_path, parse_cst, JsonPath, PathSegment, SyntaxKind, SyntaxNode, SyntaxTree};
+#[cfg(feature = "serde")]
+pub use de::{from_str_as, from_str_as_with_options, DeserializeError};
+pub use document::{document_from_str, Document, DocumentError, Elements, Entries, Item, NodeRef};
+pub use entry::{Entry, OccupiedEntry, VacantEntry};
+pub use escape::{escape_str, EscapeOptions};
+pub use expected::Expected;
+pub use interner::{from_str_interned, from_str_keys_interned, Interner, SharingStats};
+pub use lexer::{tokenize, Token, TokenKind, Tokens};
+pub use lint::{collect_errors, validate};
+pub use ndjson::{aggregate, AggregateError, AggregateSpec, Field, GroupStats, JsonLinesWriter};
+pub use reader::{
+    from_file, from_file_with_progress, from_reader, from_reader_with_progress, Progress,
+    ProgressReader, ReadError,
+};
+pub use ser::{
+    to_string, to_string_canonical, to_string_colored, to_string_pretty, to_string_with_formatter,
+    to_string_with_options, to_vec, to_vec_pretty, to_vec_with_options, to_writer,
+    to_writer_pretty, to_writer_with_formatter, to_writer_with_options, ColorFormatter, ColorTheme,
+    Formatter, Newline, NonFinite, PrettyConfig, SerializeError, SerializerOptions,
+    StandardFormatter,
+};
+pub use spanned::{parse_spanned, Spanned};
+pub use stream::{from_str_values, from_str_values_with_options, Values};
+pub use structural::{find_structurals, Structural, Structurals};
+pub use truncated::{from_str_truncated, Truncated};
+pub use unescape::Replacement;
+pub use writer::{JsonWriter, WriterError};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 struct State {
     current: usize,
 }
 
-#[derive(Debug, Clone, Copy, thiserror::Error, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct ParserOptions {
+    /// Follow RFC 8259 to the letter, raw control characters (U+0000 to U+001F)
+    /// within strings are rejected and whitespace is limited to space, tab,
+    /// line feed and carriage return.
+    pub strict: bool,
+    /// Numbers keep their source text and get converted only when read, no
+    /// digit gets lost in a round trip and numbers that are never looked at
+    /// cost no conversion.
+    pub arbitrary_precision: bool,
+    /// Accept `NaN`, `Infinity` and `-Infinity` as numbers, has no effect in
+    /// strict mode.
+    pub allow_nan: bool,
+    /// Accept JSON5 style hexadecimal `0xFF` and binary `0b1010` integers of up
+    /// to 64 bits, has no effect in strict mode.
+    pub radix_literals: bool,
+    /// Accept strings and object keys in single quotes, where `\'` escapes a
+    /// quote, has no effect in strict mode.
+    pub single_quotes: bool,
+    /// Accept `//` and `/* */` comments wherever whitespace goes, has no effect
+    /// in strict mode.
+    pub comments: bool,
+    /// Accept a comma after the last element of a list or object, has no
+    /// effect in strict mode.
+    pub trailing_commas: bool,
+    /// How deep containers can nest, `None` for no limit.
+    pub max_depth: Option<usize>,
+    pub duplicate_keys: DuplicateKeys,
+    pub overflow: NumberOverflow,
+    /// What malformed escape sequences and unpaired surrogates within strings
+    /// turn into, with `None` they fail the parse.
+    pub replacement: Option<Replacement>,
+}
+
+impl ParserOptions {
+    /// RFC 8259 to the letter, none of the extensions.
+    pub fn strict() -> Self {
+        ParserOptions {
+            strict: true,
+            ..Default::default()
+        }
+    }
+
+    /// Every extension there is, for hand written documents such as config
+    /// files. Malformed escapes get replaced rather than rejected.
+    pub fn lenient() -> Self {
+        ParserOptions {
+            allow_nan: true,
+            radix_literals: true,
+            single_quotes: true,
+            comments: true,
+            trailing_commas: true,
+            replacement: Some(Replacement::default()),
+            ..Default::default()
+        }
+    }
+}
+
+/// What becomes of object keys that appear more than once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum DuplicateKeys {
+    /// Keep every member, lookups find the first one.
+    #[default]
+    Keep,
+    /// Keep the first occurrence and drop the later ones.
+    First,
+    /// Keep the value of the last occurrence at the place of the first one.
+    Last,
+    /// Fail to parse at the repeated key.
+    Error,
+}
+
+/// What `JsonValue::merge` does with two arrays at the same place.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum MergeStrategy {
+    /// The array merged in replaces the other, as any other value does.
+    #[default]
+    Replace,
+    /// Its values are appended.
+    Concatenate,
+    /// Its values are appended unless already there.
+    Union,
+}
+
+/// What becomes of numbers too large for `f64`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum NumberOverflow {
+    /// Round them to infinity.
+    #[default]
+    Infinity,
+    /// Fail to parse at the start of the number.
+    Error,
+    /// Keep their source text, as in arbitrary precision mode.
+    Raw,
+}
+
+#[derive(Debug, Clone, thiserror::Error, PartialEq, Eq)]
 pub enum ParserError {
+    /// Input that fits none of the grammar, the parsers backtrack on this one
+    /// only.
     #[error("Parse error at position {0}")]
     NoParse(usize),
+    /// Something other than what the document needed at this point.
+    #[error("Unexpected character at position {0}, expected {1}")]
+    Unexpected(usize, Expected),
+    /// The input ends where more of the document was expected.
+    #[error("Unexpected end of input at position {0}, expected {1}")]
+    UnexpectedEof(usize, Expected),
+    /// A string without its closing quote, at the opening one.
+    #[error("Unterminated string starting at position {0}")]
+    UnterminatedString(usize),
+    /// Malformed or, per `NumberOverflow::Error`, too large number, at its
+    /// start or at the part that is malformed.
+    #[error("Invalid number at position {0}")]
+    InvalidNumber(usize),
+    #[error("Invalid escape sequence at position {0}")]
+    InvalidEscape(usize),
+    /// A raw control character within a string in strict mode.
+    #[error("Unescaped control character at position {0}")]
+    ControlCharacter(usize),
+    /// Anything but whitespace after the document.
+    #[error("Trailing characters at position {0}")]
+    TrailingCharacters(usize),
+    /// A container past `ParserOptions::max_depth`.
+    #[error("Nesting too deep at position {0}")]
+    DepthLimit(usize),
+    #[error("Duplicate key at position {0}")]
+    DuplicateKey(usize),
+    /// An error within containers, along with what was being parsed in each
+    /// of them from the innermost out. The error under the labels is never
+    /// itself a `Context`.
+    #[error("in {}: {}", context_message(.0), .1)]
+    Context(Vec<Label>, Box<ParserError>),
+}
+
+/// What was being parsed when an error happened, see `ParserError::Context`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Label {
+    /// The value at this index of an array.
+    Element(usize),
+    /// The value of the object member with this key, decoded.
+    Member(String),
+    /// The key of an object member.
+    Key,
+}
+
+impl std::fmt::Display for Label {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Label::Element(index) => write!(f, "array element {index}"),
+            Label::Member(key) => {
+                let key = escape_str(key, EscapeOptions::default());
+                write!(f, "object member \"{key}\"")
+            }
+            Label::Key => f.write_str("object key"),
+        }
+    }
+}
+
+impl ParserError {
+    /// Byte offset into the input where the error was found.
+    pub fn position(&self) -> usize {
+        match *self {
+            ParserError::Context(_, ref error) => error.position(),
+            ParserError::NoParse(position)
+            | ParserError::Unexpected(position, _)
+            | ParserError::UnexpectedEof(position, _)
+            | ParserError::UnterminatedString(position)
+            | ParserError::InvalidNumber(position)
+            | ParserError::InvalidEscape(position)
+            | ParserError::ControlCharacter(position)
+            | ParserError::TrailingCharacters(position)
+            | ParserError::DepthLimit(position)
+            | ParserError::DuplicateKey(position) => position,
+        }
+    }
+
+    /// A stable identifier of the kind of error, for programs to tell errors
+    /// apart without parsing the message. `Context` has the code of the error
+    /// under its labels.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ParserError::NoParse(_) => "E_SYNTAX",
+            ParserError::Unexpected(_, _) => "E_UNEXPECTED_CHARACTER",
+            ParserError::UnexpectedEof(_, _) => "E_UNEXPECTED_EOF",
+            ParserError::UnterminatedString(_) => "E_UNTERMINATED_STRING",
+            ParserError::InvalidNumber(_) => "E_INVALID_NUMBER",
+            ParserError::InvalidEscape(_) => "E_INVALID_ESCAPE",
+            ParserError::ControlCharacter(_) => "E_CONTROL_CHARACTER",
+            ParserError::TrailingCharacters(_) => "E_TRAILING_CHARACTERS",
+            ParserError::DepthLimit(_) => "E_DEPTH_LIMIT",
+            ParserError::DuplicateKey(_) => "E_DUPLICATE_KEY",
+            ParserError::Context(_, error) => error.code(),
+        }
+    }
+
+    /// The error as an object with its `code`, `message` and `position`,
+    /// along with the `expected` tokens and the `labels` when there are any,
+    /// for services to hand diagnostics over to their own clients.
+    pub fn to_value(&self) -> JsonValue<'static> {
+        let strings = |strings: Vec<&str>| {
+            let strings = strings
+                .into_iter()
+                .map(|s| JsonValue::String(s.to_string().into()))
+                .collect();
+            JsonValue::List(Shared::new(strings))
+        };
+        let mut pairs = vec![
+            ("code".into(), JsonValue::String(self.code().into())),
+            ("message".into(), JsonValue::String(self.to_string().into())),
+            (
+                "position".into(),
+                JsonValue::Number((self.position() as u64).into()),
+            ),
+        ];
+        if let ParserError::Unexpected(_, expected) | ParserError::UnexpectedEof(_, expected) =
+            self.root()
+        {
+            pairs.push(("expected".into(), strings(expected.tokens())));
+        }
+        if let ParserError::Context(_, _) = self {
+            let labels = self
+                .labels()
+                .iter()
+                .map(Label::to_string)
+                .collect::<Vec<_>>();
+            pairs.push((
+                "labels".into(),
+                strings(labels.iter().map(String::as_str).collect()),
+            ));
+        }
+        JsonValue::Object(Shared::new(pairs))
+    }
+
+    /// The error under the labels of `Context`.
+    pub fn root(&self) -> &ParserError {
+        match self {
+            ParserError::Context(_, error) => error,
+            error => error,
+        }
+    }
+
+    /// Labels of `Context` from the innermost out, none for other errors.
+    pub fn labels(&self) -> &[Label] {
+        match self {
+            ParserError::Context(labels, _) => labels,
+            _ => &[],
+        }
+    }
+
+    /// The error labeled as being within one more container.
+    fn within(self, label: Label) -> ParserError {
+        match self {
+            ParserError::Context(mut labels, error) => {
+                labels.push(label);
+                ParserError::Context(labels, error)
+            }
+            error => ParserError::Context(vec![label], Box::new(error)),
+        }
+    }
+
+    /// The error with what was expected at the end of the input reported as
+    /// the input ending early.
+    fn at_end(self, end: usize) -> ParserError {
+        match self {
+            ParserError::Context(labels, error) => {
+                ParserError::Context(labels, Box::new(error.at_end(end)))
+            }
+            ParserError::Unexpected(position, expected) if position == end => {
+                ParserError::UnexpectedEof(position, expected)
+            }
+            error => error,
+        }
+    }
+
+    /// One based line and column of the error within the input it came from,
+    /// the column counted in characters rather than bytes.
+    pub fn line_column(&self, input: &str) -> (usize, usize) {
+        let mut end = self.position().min(input.len());
+        while !input.is_char_boundary(end) {
+            end -= 1;
+        }
+        let before = &input[..end];
+        let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+        let line = before.matches('\n').count() + 1;
+        (line, before[line_start..].chars().count() + 1)
+    }
+
+    /// Path to the value the error is in, from its labels, see `format_path`
+    /// to print it. Errors in an object key lead to the object, those between
+    /// array elements to the element that would have come next.
+    pub fn path(&self) -> JsonPath<'_> {
+        let labels = self.labels().iter().rev();
+        labels
+            .filter_map(|label| match label {
+                Label::Element(index) => Some(PathSegment::Index(*index)),
+                Label::Member(key) => Some(PathSegment::Key(key)),
+                Label::Key => None,
+            })
+            .collect()
+    }
+
+    /// The message along with the offending line of the input and a caret
+    /// under the column, for printing to a terminal.
+    pub fn render(&self, input: &str) -> String {
+        let (line, column) = self.line_column(input);
+        let text = input.lines().nth(line - 1).unwrap_or("");
+        let number = line.to_string();
+        let margin = " ".repeat(number.len());
+        format!(
+            "error: {self}\n{margin}--> line {line}, column {column}\n{margin} |\n{number} | {text}\n{margin} | {}^\n",
+            " ".repeat(column - 1)
+        )
+    }
+}
+
+/// The labels innermost first.
+fn context_message(labels: &[Label]) -> String {
+    let labels: Vec<_> = labels.iter().map(Label::to_string).collect();
+    labels.join(", in ")
 }
 
 trait Parser<'input, R> {
@@ -43,24 +426,66 @@ where
     }
 }
 
-fn pat_ws<'input, 'pattern>(p: &'pattern str) -> impl Parser<'input, &'input str>
+/// Whitespace, which in strict mode is limited to the four characters RFC 8259
+/// allows, any Unicode whitespace is accepted otherwise. Comments count as
+/// whitespace when enabled.
+fn whitespace<'input>(options: ParserOptions) -> impl Parser<'input, &'input str> {
+    let comments = options.comments && !options.strict;
+    move |input: &'input str, state: State| {
+        let mut end = state.current;
+        loop {
+            let rest = &input[end..];
+            let trimmed = match options.strict {
+                true => rest.trim_start_matches([' ', '\t', '\n', '\r']),
+                false => rest.trim_start(),
+            };
+            end += rest.len() - trimmed.len();
+            if !comments {
+                break;
+            }
+            if let Some(comment) = trimmed.strip_prefix("//") {
+                end += 2 + comment.find('\n').unwrap_or(comment.len());
+            } else if let Some(comment) = trimmed.strip_prefix("/*") {
+                // an unterminated comment is left for the next parser to fail on
+                match comment.find("*/") {
+                    Some(index) => end += 2 + index + 2,
+                    None => break,
+                }
+            } else {
+                break;
+            }
+        }
+        Ok((&input[state.current..end], State { current: end }))
+    }
+}
+
+fn pat_ws<'input, 'pattern>(
+    p: &'pattern str,
+    options: ParserOptions,
+) -> impl Parser<'input, &'input str>
 where
     'pattern: 'input,
 {
-    bind(take_while(|c| c.is_whitespace()), move |_: &str| {
+    bind(whitespace(options), move |_: &str| {
         bind(pat(p), move |s| {
-            bind(take_while(|c| c.is_whitespace()), move |_: &str| success(s))
+            bind(whitespace(options), move |_: &str| success(s))
         })
     })
 }
 
+/// Tries `second` where `first` doesn't match. When neither does, the error
+/// is the one that got further into the input, that is closer to whatever
+/// went wrong.
 fn or<'input, R: 'input>(
     first: impl Parser<'input, R> + 'input,
     second: impl Parser<'input, R> + 'input,
 ) -> impl Parser<'input, R> + 'input {
     move |input: &'input str, state| match first.parse(input, state) {
-        Ok(result) => Ok(result),
-        Err(_) => second.parse(input, state),
+        Err(ParserError::NoParse(first)) => match second.parse(input, state) {
+            Err(ParserError::NoParse(second)) => Err(ParserError::NoParse(first.max(second))),
+            result => result,
+        },
+        result => result,
     }
 }
 
@@ -72,7 +497,9 @@ fn take_while<'input>(
             .char_indices()
             .take_while(|(_, c)| pred(*c))
             .last()
-            .map_or(state.current, |(index, _)| state.current + index + 1);
+            .map_or(state.current, |(index, c)| {
+                state.current + index + c.len_utf8()
+            });
         Ok((&input[state.current..end], State { current: end }))
     }
 }
@@ -102,14 +529,114 @@ fn fail<'input, R: 'input>(unwind: Option<usize>) -> impl Parser<'input, R> {
     }
 }
 
-fn string<'input>() -> impl Parser<'input, JsonValue<'input>> {
-    bind(pat("\""), |_: &str| {
-        bind(take_while(|c| c != '"'), |s| {
-            bind(pat("\""), move |_: &str| success(JsonValue::String(s)))
+/// The raw text between the quotes, escaped quotes don't end it.
+fn string_span<'input>(
+    quote: &'static str,
+    options: ParserOptions,
+) -> impl Parser<'input, &'input str> {
+    let content = move |input: &'input str, state: State| {
+        let bytes = input.as_bytes();
+        let mut end = state.current;
+        // jump from one quote or backslash to the next, a backslash skips the
+        // byte after it, which can't start a multibyte character if it matters
+        loop {
+            match memchr::memchr2(quote.as_bytes()[0], b'\\', &bytes[end..]) {
+                Some(index) if bytes[end + index] == b'\\' => {
+                    end = (end + index + 2).min(bytes.len());
+                }
+                Some(index) => {
+                    end += index;
+                    break;
+                }
+                None => {
+                    end = bytes.len();
+                    break;
+                }
+            }
+        }
+        if end == bytes.len() {
+            return Err(ParserError::UnterminatedString(state.current - 1));
+        }
+        if options.strict {
+            if let Some(index) = bytes[state.current..end].iter().position(|&b| b <= 0x1f) {
+                return Err(ParserError::ControlCharacter(state.current + index));
+            }
+        }
+        Ok((&input[state.current..end], State { current: end }))
+    };
+    bind(pat(quote), move |_: &str| {
+        bind(content, move |s| {
+            bind(pat(quote), move |_: &str| success(s))
         })
     })
 }
 
+fn quoted_string<'input>(
+    quote: &'static str,
+    options: ParserOptions,
+) -> impl Parser<'input, std::borrow::Cow<'input, str>> {
+    bind(string_span(quote, options), move |raw| {
+        move |_: &'input str, state: State| {
+            match unescape::try_unescape(raw, options.replacement, quote.as_bytes()[0]) {
+                Ok(decoded) => Ok((decoded, state)),
+                // the state is past the closing quote
+                Err(offset) => Err(ParserError::InvalidEscape(
+                    state.current - 1 - raw.len() + offset,
+                )),
+            }
+        }
+    })
+}
+
+/// The text between the quotes with escapes decoded, borrowed from the input
+/// when there are none.
+fn decoded_string<'input>(
+    options: ParserOptions,
+) -> impl Parser<'input, std::borrow::Cow<'input, str>> {
+    let single_quotes = options.single_quotes && !options.strict;
+    or(
+        quoted_string("\"", options),
+        move |input: &'input str, state: State| match single_quotes {
+            true => quoted_string("'", options).parse(input, state),
+            false => Err(ParserError::NoParse(state.current)),
+        },
+    )
+}
+
+fn checked_quoted_string<'input>(
+    quote: &'static str,
+    options: ParserOptions,
+) -> impl Parser<'input, &'input str> {
+    bind(string_span(quote, options), move |raw| {
+        move |_: &'input str, state: State| {
+            match unescape::check_escapes(raw, options.replacement, quote.as_bytes()[0]) {
+                Ok(()) => Ok((raw, state)),
+                // the state is past the closing quote
+                Err(offset) => Err(ParserError::InvalidEscape(
+                    state.current - 1 - raw.len() + offset,
+                )),
+            }
+        }
+    })
+}
+
+/// The raw text between the quotes, failing where `decoded_string` would but
+/// with the escapes only checked rather than decoded.
+fn checked_string<'input>(options: ParserOptions) -> impl Parser<'input, &'input str> {
+    let single_quotes = options.single_quotes && !options.strict;
+    or(
+        checked_quoted_string("\"", options),
+        move |input: &'input str, state: State| match single_quotes {
+            true => checked_quoted_string("'", options).parse(input, state),
+            false => Err(ParserError::NoParse(state.current)),
+        },
+    )
+}
+
+fn string<'input>(options: ParserOptions) -> impl Parser<'input, JsonValue<'input>> {
+    bind(decoded_string(options), |s| success(JsonValue::String(s)))
+}
+
 fn merge_two_consecutive_strs<'input>(s1: &'input str, s2: &'input str) -> &'input str {
     unsafe {
         std::str::from_utf8_unchecked(std::slice::from_raw_parts(s1.as_ptr(), s1.len() + s2.len()))
@@ -143,12 +670,15 @@ where
 
 fn whole_part_number<'input>() -> impl Parser<'input, &'input str> {
     bind(or(pat("-"), pat("")), |sign| {
-        bind(take_while(|c| c.is_digit(10)), |digits| {
+        bind(take_while(|c| c.is_ascii_digit()), |digits| {
             match digits.len() {
                 0 => Either::A(fail(Some(sign.len()))),
                 1 => Either::B(success(merge_two_consecutive_strs(sign, digits))),
-                other if digits.chars().nth(0).unwrap() == '0' => {
-                    Either::C(fail(Some(other + sign.len())))
+                other if digits.starts_with('0') => {
+                    let length = sign.len() + other;
+                    Either::C(move |_: &'input str, state: State| {
+                        Err(ParserError::InvalidNumber(state.current - length))
+                    })
                 }
                 _ => Either::D(success(merge_two_consecutive_strs(sign, digits))),
             }
@@ -157,9 +687,33 @@ fn whole_part_number<'input>() -> impl Parser<'input, &'input str> {
 }
 
 fn decimal_part_number<'input>() -> impl Parser<'input, &'input str> {
-    bind(pat("."), |dot: &str| {
-        bind(take_while(|c| c.is_digit(10)), |digits| {
-            success(merge_two_consecutive_strs(dot, digits))
+    bind(pat("."), |dot: &'input str| {
+        bind(
+            take_while(|c| c.is_ascii_digit()),
+            move |digits: &'input str| {
+                move |_: &'input str, state: State| match digits.len() {
+                    0 => Err(ParserError::InvalidNumber(state.current - dot.len())),
+                    _ => Ok((merge_two_consecutive_strs(dot, digits), state)),
+                }
+            },
+        )
+    })
+}
+
+fn exponent_part_number<'input>() -> impl Parser<'input, &'input str> {
+    bind(or(pat("e"), pat("E")), |e: &'input str| {
+        bind(or(pat("+"), or(pat("-"), pat(""))), move |sign| {
+            bind(
+                take_while(|c| c.is_ascii_digit()),
+                move |digits: &'input str| {
+                    let exponent =
+                        merge_two_consecutive_strs(merge_two_consecutive_strs(e, sign), digits);
+                    move |_: &'input str, state: State| match digits.len() {
+                        0 => Err(ParserError::InvalidNumber(state.current - exponent.len())),
+                        _ => Ok((exponent, state)),
+                    }
+                },
+            )
         })
     })
 }
@@ -169,96 +723,444 @@ fn optional<'input, R: 'input>(
 ) -> impl Parser<'input, Option<R>> {
     move |input: &'input str, state| match parser.parse(input, state) {
         Ok((result, new_state)) => Ok((Some(result), new_state)),
-        Err(_) => Ok((None, state)),
+        Err(ParserError::NoParse(_)) => Ok((None, state)),
+        Err(error) => Err(error),
     }
 }
 
-fn spaced_by<'input, R: 'input, S: 'input>(
+/// Commits to the parser, failing to match it is an error naming what was
+/// expected rather than a cue to try something else.
+fn expect<'input, R: 'input>(
     parser: impl Parser<'input, R> + 'input,
-    spacer: impl Parser<'input, S> + 'input,
-) -> impl Parser<'input, Vec<R>> + 'input {
-    move |input: &'input str, state| {
-        let mut results = Vec::new();
-        let (first_result, mut state) = parser.parse(input, state)?;
-        results.push(first_result);
-
-        while let Ok((_, new_state)) = spacer.parse(input, state) {
-            match parser.parse(input, new_state) {
-                Ok((result, new_state)) => {
-                    results.push(result);
-                    state = new_state;
-                }
-                Err(_) => break,
+    expected: Expected,
+) -> impl Parser<'input, R> {
+    move |input: &'input str, state| match parser.parse(input, state) {
+        Err(ParserError::NoParse(position)) => Err(ParserError::Unexpected(position, expected)),
+        result => result,
+    }
+}
+
+/// Sequences like `bind`, except that once `first` has matched there is no
+/// going back, `then` not matching is an error naming what was expected rather
+/// than a cue for an enclosing `or` to try something else.
+fn cut<'input, R: 'input, RR: 'input, P>(
+    first: impl Parser<'input, R> + 'input,
+    expected: Expected,
+    then: impl Fn(R) -> P + 'input,
+) -> impl Parser<'input, RR>
+where
+    P: Parser<'input, RR> + 'input,
+{
+    bind(first, move |result| expect(then(result), expected))
+}
+
+/// Where the strings of parsed values end up, borrowed from the input as they
+/// are or taken from an `Interner`.
+pub(crate) trait Strings<'input, 'output>: Copy + 'input {
+    fn key(self, key: std::borrow::Cow<'input, str>) -> std::borrow::Cow<'output, str>;
+    fn string(self, s: std::borrow::Cow<'input, str>) -> std::borrow::Cow<'output, str>;
+    fn number(self, n: Number<'input>) -> Number<'output>;
+}
+
+/// The strings as the input has them.
+#[derive(Clone, Copy)]
+struct FromInput;
+
+impl<'input> Strings<'input, 'input> for FromInput {
+    fn key(self, key: std::borrow::Cow<'input, str>) -> std::borrow::Cow<'input, str> {
+        key
+    }
+
+    fn string(self, s: std::borrow::Cow<'input, str>) -> std::borrow::Cow<'input, str> {
+        s
+    }
+
+    fn number(self, n: Number<'input>) -> Number<'input> {
+        n
+    }
+}
+
+/// A container being filled in by `json_value`.
+enum Frame<'input> {
+    List(Vec<JsonValue<'input>>),
+    /// The members so far, along with the key of the member being parsed and
+    /// its position.
+    Object(Members<'input>, std::borrow::Cow<'input, str>, usize),
+}
+
+/// Objects with more members than this get their keys indexed for the
+/// duplicate key checks, fewer are quicker to scan.
+const INDEXED_MEMBERS: usize = 8;
+
+/// The members of an object being parsed, with the place of each key once
+/// there are enough of them.
+#[derive(Default)]
+struct Members<'input> {
+    pairs: Vec<Member<'input>>,
+    index: std::collections::HashMap<std::borrow::Cow<'input, str>, usize>,
+}
+
+impl<'input> Members<'input> {
+    /// Where the first member with the key is.
+    fn find(&mut self, key: &str) -> Option<usize> {
+        if self.pairs.len() <= INDEXED_MEMBERS {
+            return self.pairs.iter().position(|(k, _)| k == key);
+        }
+        if self.index.is_empty() {
+            for (position, (k, _)) in self.pairs.iter().enumerate() {
+                self.index.entry(k.clone()).or_insert(position);
             }
         }
+        self.index.get(key).copied()
+    }
 
-        Ok((results, state))
+    fn push(&mut self, (key, value): Member<'input>) {
+        if !self.index.is_empty() {
+            self.index.entry(key.clone()).or_insert(self.pairs.len());
+        }
+        self.pairs.push((key, value));
     }
 }
 
-fn json_value<'input>() -> impl Parser<'input, JsonValue<'input>> {
-    move |input: &'input str, state| {
-        or(
-            string(),
-            or(
-                number(),
-                or(object(), or(list(), or(boolean(), or(null(), fail(None))))),
-            ),
-        )
-        .parse(input, state)
+fn insert_member<'input>(
+    members: &mut Members<'input>,
+    (key, value): Member<'input>,
+    position: usize,
+    policy: DuplicateKeys,
+) -> Result<(), ParserError> {
+    let existing = match policy {
+        DuplicateKeys::Keep => None,
+        _ => members.find(&key),
+    };
+    match (existing, policy) {
+        (None, _) => members.push((key, value)),
+        (Some(_), DuplicateKeys::Keep | DuplicateKeys::First) => value.drop_iteratively(),
+        (Some(index), DuplicateKeys::Last) => {
+            members.pairs[index].1.replace(value).drop_iteratively()
+        }
+        (Some(_), DuplicateKeys::Error) => {
+            value.drop_iteratively();
+            return Err(ParserError::DuplicateKey(position));
+        }
     }
+    Ok(())
 }
 
-fn key_value_pair<'input>() -> impl Parser<'input, (&'input str, JsonValue<'input>)> {
-    bind(string(), move |key| {
-        bind(pat_ws(":"), move |_: &str| {
-            let JsonValue::String(key) = key else {
-                panic!("internal error in key_value_pair, key is not a string")
-            };
-            bind(json_value(), move |value| success((key, value)))
+fn scalar<'input>(options: ParserOptions) -> impl Parser<'input, JsonValue<'input>> {
+    or(
+        string(options),
+        or(number(options), or(boolean(), or(null(), fail(None)))),
+    )
+}
+
+/// An object key along with the colon after it, `expected` tells what else
+/// could have been there instead of the key.
+fn member_key<'input>(
+    options: ParserOptions,
+    expected: Expected,
+) -> impl Parser<'input, std::borrow::Cow<'input, str>> {
+    cut(
+        expect(decoded_string(options), expected),
+        Expected::COLON,
+        move |key| bind(pat_ws(":", options), move |_: &str| success(key.clone())),
+    )
+}
+
+fn json_value<'input>(options: ParserOptions) -> impl Parser<'input, JsonValue<'input>> {
+    json_value_with(options, FromInput)
+}
+
+/// A value with its strings passed through `strings`.
+fn json_value_with<'input, 'output>(
+    options: ParserOptions,
+    strings: impl Strings<'input, 'output>,
+) -> impl Parser<'input, JsonValue<'output>>
+where
+    'output: 'input,
+{
+    let value = container_value(options, strings);
+    // whatever was missing, the input ended before it
+    move |input: &'input str, state: State| {
+        value
+            .parse(input, state)
+            .map_err(|error| error.at_end(input.len()))
+    }
+}
+
+/// Containers are kept on an explicit stack rather than parsed recursively,
+/// so the nesting depth is bounded by the heap and not by the call stack.
+fn container_value<'input, 'output>(
+    options: ParserOptions,
+    strings: impl Strings<'input, 'output>,
+) -> impl Parser<'input, JsonValue<'output>>
+where
+    'output: 'input,
+{
+    let scalar = scalar(options);
+    let [list_start, list_end, object_start, object_end, comma] =
+        ["[", "]", "{", "}", ","].map(|p| pat_ws(p, options));
+    let trailing_commas = options.trailing_commas && !options.strict;
+    let first_key = member_key(options, Expected::STRING | Expected::CLOSE_BRACE);
+    let next_key = member_key(
+        options,
+        match trailing_commas {
+            true => Expected::STRING | Expected::CLOSE_BRACE,
+            false => Expected::STRING,
+        },
+    );
+    let list_next = expect(
+        pat_ws("]", options),
+        Expected::COMMA | Expected::CLOSE_BRACKET,
+    );
+    let object_next = expect(
+        pat_ws("}", options),
+        Expected::COMMA | Expected::CLOSE_BRACE,
+    );
+    move |input: &'input str, state: State| {
+        let mut stack: Vec<Frame<'output>> = Vec::new();
+        // whether an error is in the key of the innermost object rather than
+        // in its value
+        let mut in_key = false;
+        let mut parse = || {
+            let mut state = state;
+            'value: loop {
+                let start = state.current;
+                let too_deep = options.max_depth.is_some_and(|max| stack.len() >= max);
+                let mut value = match scalar.parse(input, state) {
+                    Ok((value, new_state)) => {
+                        state = new_state;
+                        match value {
+                            JsonValue::String(s) => JsonValue::String(strings.string(s)),
+                            JsonValue::Number(n) => JsonValue::Number(strings.number(n)),
+                            JsonValue::Boolean(b) => JsonValue::Boolean(b),
+                            _ => JsonValue::Null,
+                        }
+                    }
+                    Err(ParserError::NoParse(position)) => {
+                        if let Ok((_, new_state)) = list_start.parse(input, state) {
+                            if too_deep {
+                                return Err(ParserError::DepthLimit(start));
+                            }
+                            state = new_state;
+                            match list_end.parse(input, state) {
+                                Ok((_, new_state)) => {
+                                    state = new_state;
+                                    JsonValue::List(Shared::new(Vec::new()))
+                                }
+                                Err(_) => {
+                                    stack.push(Frame::List(Vec::new()));
+                                    continue 'value;
+                                }
+                            }
+                        } else if let Ok((_, new_state)) = object_start.parse(input, state) {
+                            if too_deep {
+                                return Err(ParserError::DepthLimit(start));
+                            }
+                            state = new_state;
+                            match object_end.parse(input, state) {
+                                Ok((_, new_state)) => {
+                                    state = new_state;
+                                    JsonValue::Object(Shared::new(Vec::new()))
+                                }
+                                Err(_) => {
+                                    let position = state.current;
+                                    stack.push(Frame::Object(
+                                        Members::default(),
+                                        "".into(),
+                                        position,
+                                    ));
+                                    in_key = true;
+                                    let (key, new_state) = first_key.parse(input, state)?;
+                                    in_key = false;
+                                    state = new_state;
+                                    if let Some(Frame::Object(_, frame_key, _)) = stack.last_mut() {
+                                        *frame_key = strings.key(key);
+                                    }
+                                    continue 'value;
+                                }
+                            }
+                        } else {
+                            let expected = match stack.last() {
+                                Some(Frame::List(values))
+                                    if values.is_empty() || trailing_commas =>
+                                {
+                                    Expected::VALUE | Expected::CLOSE_BRACKET
+                                }
+                                _ => Expected::VALUE,
+                            };
+                            return Err(ParserError::Unexpected(position, expected));
+                        }
+                    }
+                    Err(error) => return Err(error),
+                };
+                // hand the value over to the enclosing containers, closing those
+                // that end right after it
+                loop {
+                    match stack.last_mut() {
+                        None => return Ok((value, state)),
+                        Some(Frame::List(values)) => {
+                            values.push(value);
+                            if let Ok((_, new_state)) = comma.parse(input, state) {
+                                state = new_state;
+                                // a trailing comma falls through to the closing
+                                if !(trailing_commas && list_end.parse(input, state).is_ok()) {
+                                    continue 'value;
+                                }
+                            }
+                            (_, state) = list_next.parse(input, state)?;
+                            let Some(Frame::List(values)) = stack.pop() else {
+                                panic!("internal error in json_value, frame is not a list")
+                            };
+                            value = JsonValue::List(Shared::new(values));
+                        }
+                        Some(Frame::Object(members, key, position)) => {
+                            let member = (std::mem::take(key), value);
+                            // a repeated key is an error in the key
+                            in_key = true;
+                            insert_member(members, member, *position, options.duplicate_keys)?;
+                            in_key = false;
+                            if let Ok((_, new_state)) = comma.parse(input, state) {
+                                state = new_state;
+                                if !(trailing_commas && object_end.parse(input, state).is_ok()) {
+                                    *position = state.current;
+                                    in_key = true;
+                                    let (next, new_state) = next_key.parse(input, state)?;
+                                    in_key = false;
+                                    (*key, state) = (strings.key(next), new_state);
+                                    continue 'value;
+                                }
+                            }
+                            (_, state) = object_next.parse(input, state)?;
+                            let Some(Frame::Object(members, _, _)) = stack.pop() else {
+                                panic!("internal error in json_value, frame is not an object")
+                            };
+                            value = JsonValue::Object(Shared::new(members.pairs));
+                        }
+                    }
+                }
+            }
+        };
+        let result = parse();
+        let result = result.map_err(|error| match error {
+            ParserError::NoParse(_) => error,
+            error => stack
+                .iter()
+                .enumerate()
+                .rev()
+                .fold(error, |error, (depth, frame)| {
+                    error.within(match frame {
+                        Frame::List(values) => Label::Element(values.len()),
+                        Frame::Object(..) if in_key && depth == stack.len() - 1 => Label::Key,
+                        Frame::Object(_, key, _) => Label::Member(key.to_string()),
+                    })
+                }),
+        });
+        // the values of containers left open by an error
+        for frame in stack {
+            match frame {
+                Frame::List(values) => JsonValue::from(values).drop_iteratively(),
+                Frame::Object(members, ..) => {
+                    JsonValue::Object(Shared::new(members.pairs)).drop_iteratively()
+                }
+            }
+        }
+        result
+    }
+}
+
+/// A value with the whitespace around it.
+fn document<'input, 'output>(
+    options: ParserOptions,
+    strings: impl Strings<'input, 'output>,
+) -> impl Parser<'input, JsonValue<'output>>
+where
+    'output: 'input,
+{
+    bind(whitespace(options), move |_: &str| {
+        bind(json_value_with(options, strings), move |value| {
+            bind(whitespace(options), move |_: &str| success(value.clone()))
         })
     })
 }
 
-fn object<'input>() -> impl Parser<'input, JsonValue<'input>> {
-    bind(pat_ws("{"), |_: &str| {
-        bind(
-            spaced_by(key_value_pair(), pat_ws(",")),
-            move |key_value_pairs| {
-                let key_value_pairs = std::rc::Rc::new(key_value_pairs);
-                bind(pat_ws("}"), move |_: &str| {
-                    success(JsonValue::Object(key_value_pairs.clone()))
+fn number<'input>(options: ParserOptions) -> impl Parser<'input, JsonValue<'input>> {
+    let number = or(
+        radix_number(options),
+        or(
+            bind(whole_part_number(), move |whole_part| {
+                bind(optional(decimal_part_number()), move |decimal_part| {
+                    let number = merge_two_consecutive_strs(whole_part, decimal_part.unwrap_or(""));
+                    bind(optional(exponent_part_number()), move |exponent_part| {
+                        let text = merge_two_consecutive_strs(number, exponent_part.unwrap_or(""));
+                        number_value(text, options)
+                    })
                 })
-            },
-        )
-    })
+            }),
+            non_finite_number(options),
+        ),
+    );
+    // nothing but a number starts with a minus
+    move |input: &'input str, state: State| match number.parse(input, state) {
+        Err(ParserError::NoParse(_)) if input[state.current..].starts_with('-') => {
+            Err(ParserError::InvalidNumber(state.current))
+        }
+        result => result,
+    }
+}
+
+/// Converts the text of a number, failing at its start when it overflows and
+/// the options say so.
+fn number_value<'input>(
+    text: &'input str,
+    options: ParserOptions,
+) -> impl Parser<'input, JsonValue<'input>> {
+    move |_: &'input str, state: State| match Number::with_options(text, options) {
+        Some(number) => Ok((JsonValue::Number(number), state)),
+        None => Err(ParserError::InvalidNumber(state.current - text.len())),
+    }
 }
 
-fn list<'input>() -> impl Parser<'input, JsonValue<'input>> {
-    bind(pat_ws("["), |_: &str| {
-        bind(spaced_by(json_value(), pat_ws(",")), move |values| {
-            let values = std::rc::Rc::new(values);
-            bind(pat_ws("]"), move |_: &str| {
-                success(JsonValue::List(values.clone()))
+fn radix_number<'input>(options: ParserOptions) -> impl Parser<'input, JsonValue<'input>> {
+    move |input: &'input str, state| {
+        if !options.radix_literals || options.strict {
+            return fail(None).parse(input, state);
+        }
+        bind(or(pat("-"), pat("")), move |sign: &'input str| {
+            let prefix = or(pat("0x"), or(pat("0X"), or(pat("0b"), pat("0B"))));
+            bind(prefix, move |prefix: &'input str| {
+                let radix = if prefix.ends_with(['x', 'X']) { 16 } else { 2 };
+                bind(take_while(move |c| c.is_digit(radix)), move |digits| {
+                    move |_: &'input str, state: State| {
+                        let number = match (sign, u64::from_str_radix(digits, radix)) {
+                            ("", Ok(n)) => Some(Number::from(n)),
+                            (_, Ok(n)) => 0i64.checked_sub_unsigned(n).map(Number::from),
+                            (_, Err(_)) => None,
+                        };
+                        let start = state.current - sign.len() - prefix.len() - digits.len();
+                        match number {
+                            Some(number) => Ok((JsonValue::Number(number), state)),
+                            None => Err(ParserError::InvalidNumber(start)),
+                        }
+                    }
+                })
             })
         })
-    })
+        .parse(input, state)
+    }
 }
 
-fn number<'input>() -> impl Parser<'input, JsonValue<'input>> {
-    bind(whole_part_number(), |whole_part| {
-        bind(
-            optional(decimal_part_number()),
-            move |decimal_part| match decimal_part {
-                Some(decimal_part) => success(JsonValue::Number(
-                    merge_two_consecutive_strs(whole_part, decimal_part)
-                        .parse::<f64>()
-                        .unwrap(),
-                )),
-                None => success(JsonValue::Number(whole_part.parse::<f64>().unwrap())),
-            },
-        )
-    })
+fn non_finite_number<'input>(options: ParserOptions) -> impl Parser<'input, JsonValue<'input>> {
+    move |input: &'input str, state| {
+        if options.allow_nan && !options.strict {
+            bind(
+                or(pat("NaN"), or(pat("Infinity"), pat("-Infinity"))),
+                move |text| number_value(text, options),
+            )
+            .parse(input, state)
+        } else {
+            fail(None).parse(input, state)
+        }
+    }
 }
 
 fn boolean<'input>() -> impl Parser<'input, JsonValue<'input>> {
@@ -272,110 +1174,1993 @@ fn null<'input>() -> impl Parser<'input, JsonValue<'input>> {
     bind(pat("null"), |_| success(JsonValue::Null))
 }
 
+/// A JSON number. Integers are kept exactly as long as they fit in 64 bits,
+/// anything else, fractions and exponents included, is stored as `f64`.
+/// Numbers compare by their value, so `1` equals `1.0`, the source text of
+/// arbitrary precision ones exactly.
+#[derive(Debug, Clone)]
+pub struct Number<'input>(N<'input>);
+
 #[derive(Debug, Clone, PartialEq)]
-pub enum JsonValue<'input> {
-    String(&'input str),
-    Number(f64),
-    Object(std::rc::Rc<Vec<(&'input str, JsonValue<'input>)>>),
-    List(std::rc::Rc<Vec<JsonValue<'input>>>),
-    Boolean(bool),
-    Null,
+enum N<'input> {
+    PosInt(u64),
+    /// Always less than zero.
+    NegInt(i64),
+    Float(f64),
+    /// Source text of the number, see `ParserOptions::arbitrary_precision`.
+    Raw(std::borrow::Cow<'input, str>),
 }
 
-pub fn from_str<'input>(input: &'input str) -> Result<JsonValue<'input>, ParserError> {
-    let state = State { current: 0 };
-    let (result, state) = json_value().parse(input, state)?;
-    if state.current == input.len() {
-        Ok(result)
-    } else {
-        Err(ParserError::NoParse(state.current))
+impl<'input> Number<'input> {
+    /// Converts valid number syntax, `NaN` and `Infinity` included. Integer
+    /// literals too large for 64 bits fall back to `f64`, so does `-0` to keep
+    /// its sign.
+    fn from_text(text: &str) -> Self {
+        if text.contains(['.', 'e', 'E']) {
+            return Number(N::Float(float::parse_f64(text)));
+        }
+        match (text.parse::<u64>(), text.parse::<i64>()) {
+            (Ok(n), _) => Number(N::PosInt(n)),
+            (_, Ok(n)) if n < 0 => Number(N::NegInt(n)),
+            _ => Number(N::Float(float::parse_f64(text))),
+        }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    fn with_options(text: &'input str, options: ParserOptions) -> Option<Self> {
+        if options.arbitrary_precision {
+            return Some(Number(N::Raw(text.into())));
+        }
+        let number = Number::from_text(text);
+        let overflow = matches!(number.0, N::Float(n) if n.is_infinite());
+        if !overflow || text.ends_with("Infinity") {
+            return Some(number);
+        }
+        match options.overflow {
+            NumberOverflow::Infinity => Some(number),
+            NumberOverflow::Raw => Some(Number(N::Raw(text.into()))),
+            NumberOverflow::Error => None,
+        }
+    }
 
-    // test the pattern function
-    #[test]
-    fn test_pattern() {
-        let parser = pat("hello");
-        let input = "hello world";
-        let state = State { current: 0 };
-        let result = parser.parse(input, state).unwrap();
-        assert_eq!(result, ("hello", State { current: 5 }));
+    /// Converts the source text of raw numbers.
+    fn resolve(&self) -> N<'input> {
+        match &self.0 {
+            N::Raw(text) => Number::from_text(text).0,
+            n => n.clone(),
+        }
     }
 
-    // test the or function
-    #[test]
-    fn test_or() {
-        let parser = or(pat("hello"), pat("world"));
-        let input = "world";
-        let state = State { current: 0 };
-        let result = parser.parse(input, state).unwrap();
-        assert_eq!(result, ("world", State { current: 5 }));
+    pub(crate) fn map_str<'output>(
+        &self,
+        f: &impl Fn(&'input str) -> &'output str,
+    ) -> Number<'output> {
+        Number(match &self.0 {
+            N::Raw(std::borrow::Cow::Borrowed(text)) => N::Raw(f(text).into()),
+            N::Raw(std::borrow::Cow::Owned(text)) => N::Raw(text.clone().into()),
+            N::PosInt(n) => N::PosInt(*n),
+            N::NegInt(n) => N::NegInt(*n),
+            N::Float(n) => N::Float(*n),
+        })
     }
 
-    // test the take_while function
-    #[test]
-    fn test_take_while() {
-        let parser = take_while(|c| c.is_alphabetic());
-        let input = "hello world";
+    /// The number with its source text, if kept, copied out of the input.
+    pub fn into_owned(self) -> Number<'static> {
+        Number(match self.0 {
+            N::Raw(text) => N::Raw(text.into_owned().into()),
+            N::PosInt(n) => N::PosInt(n),
+            N::NegInt(n) => N::NegInt(n),
+            N::Float(n) => N::Float(n),
+        })
+    }
+
+    /// The source text of the number, only kept in arbitrary precision mode.
+    pub fn as_str(&self) -> Option<&str> {
+        match &self.0 {
+            N::Raw(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    pub fn is_f64(&self) -> bool {
+        matches!(self.resolve(), N::Float(_))
+    }
+
+    /// `Some` only when the number is an integer that fits, floats like `1e3`
+    /// included as long as they are small enough for `f64` to hold them
+    /// exactly.
+    pub fn as_u64(&self) -> Option<u64> {
+        match self.resolve() {
+            N::PosInt(n) => Some(n),
+            N::Float(n) if is_safe_integer(n) && n >= 0.0 => Some(n as u64),
+            _ => None,
+        }
+    }
+
+    /// Same as `as_u64`.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self.resolve() {
+            N::PosInt(n) => i64::try_from(n).ok(),
+            N::NegInt(n) => Some(n),
+            N::Float(n) if is_safe_integer(n) => Some(n as i64),
+            _ => None,
+        }
+    }
+
+    /// Integers beyond 64 bits only reach here intact in arbitrary precision
+    /// mode, otherwise this is `as_u64` widened.
+    pub fn as_u128(&self) -> Option<u128> {
+        match &self.0 {
+            N::Raw(text) => text.parse().ok().or_else(|| self.as_u64().map(u128::from)),
+            _ => self.as_u64().map(u128::from),
+        }
+    }
+
+    /// Same as `as_u128`.
+    pub fn as_i128(&self) -> Option<i128> {
+        match &self.0 {
+            N::Raw(text) => text.parse().ok().or_else(|| self.as_i64().map(i128::from)),
+            _ => self.as_i64().map(i128::from),
+        }
+    }
+
+    /// The number as a float, integers beyond 2^53 lose precision.
+    pub fn as_f64(&self) -> f64 {
+        match self.resolve() {
+            N::PosInt(n) => n as f64,
+            N::NegInt(n) => n as f64,
+            N::Float(n) => n,
+            N::Raw(_) => unreachable!(),
+        }
+    }
+}
+
+/// Whether the float is an integer in the range where every integer has an
+/// exact `f64`, past 2^53 it may well be a rounded larger literal.
+fn is_safe_integer(n: f64) -> bool {
+    n.fract() == 0.0 && n.abs() <= (1u64 << 53) as f64
+}
+
+/// The integer a float is equal to, if there is one that fits in 64 bits.
+/// Floats stand for their shortest digits, past 2^53 those are not always
+/// the integer of their bits, `2^60` reads as `1152921504606847000`.
+fn exact_integer<'input>(float: f64) -> Option<N<'input>> {
+    let integer = match float {
+        f if f.fract() != 0.0 => None,
+        f if (0.0..u64::MAX as f64).contains(&f) => Some(N::PosInt(f as u64)),
+        f if (i64::MIN as f64..0.0).contains(&f) => Some(N::NegInt(f as i64)),
+        _ => None,
+    };
+    match integer {
+        Some(integer) if !is_safe_integer(float) => {
+            let shortest = Number(N::Float(float)).exact();
+            (Number(integer.clone()).exact() == shortest).then_some(integer)
+        }
+        integer => integer,
+    }
+}
+
+/// The exact value of a number, what raw numbers compare and hash by since
+/// their text can hold more digits than `f64` does. Variants are in order.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Exact {
+    NegInfinity,
+    Finite(Decimal),
+    Infinity,
+    NaN,
+}
+
+/// A finite number as `0.digits * 10^exponent`, its digits without leading or
+/// trailing zeros and none at all for zero, which has no sign.
+#[derive(PartialEq, Eq, Hash)]
+struct Decimal {
+    negative: bool,
+    digits: Vec<u8>,
+    exponent: i64,
+}
+
+impl Decimal {
+    /// Converts valid number syntax, exponents too large for `i64` saturate.
+    fn from_text(text: &str) -> Self {
+        let (negative, text) = match text.strip_prefix('-') {
+            Some(text) => (true, text),
+            None => (false, text),
+        };
+        let limit = i64::MAX / 4;
+        let (mantissa, exponent) = match text.split_once(['e', 'E']) {
+            Some((mantissa, exponent)) => {
+                let saturated = if exponent.starts_with('-') {
+                    -limit
+                } else {
+                    limit
+                };
+                let exponent = exponent.parse().unwrap_or(saturated);
+                (mantissa, exponent.clamp(-limit, limit))
+            }
+            None => (text, 0),
+        };
+        let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        let digits = whole
+            .bytes()
+            .chain(fraction.bytes())
+            .map(|digit| digit - b'0');
+        let mut digits: Vec<u8> = digits.skip_while(|&digit| digit == 0).collect();
+        let leading = whole.len() + fraction.len() - digits.len();
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+        if digits.is_empty() {
+            return Decimal {
+                negative: false,
+                digits,
+                exponent: 0,
+            };
+        }
+        Decimal {
+            negative,
+            digits,
+            exponent: exponent + whole.len() as i64 - leading as i64,
+        }
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        let sign = |d: &Decimal| {
+            if d.negative {
+                -1
+            } else {
+                i8::from(!d.digits.is_empty())
+            }
+        };
+        sign(self).cmp(&sign(other)).then_with(|| {
+            let magnitude = (self.exponent, &self.digits).cmp(&(other.exponent, &other.digits));
+            if self.negative {
+                magnitude.reverse()
+            } else {
+                magnitude
+            }
+        })
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Number<'_> {
+    fn is_raw(&self) -> bool {
+        matches!(self.0, N::Raw(_))
+    }
+
+    /// Floats are taken as the fewest digits that read back as them, integral
+    /// or not, so that `1e23` is the float `1e23` rather than the
+    /// `99999999999999991611392` of its bits.
+    fn exact(&self) -> Exact {
+        let float = match &self.0 {
+            N::PosInt(n) => return Exact::Finite(Decimal::from_text(&n.to_string())),
+            N::NegInt(n) => return Exact::Finite(Decimal::from_text(&n.to_string())),
+            N::Raw(text) if text.ends_with("NaN") || text.ends_with("Infinity") => {
+                Number::from_text(text).as_f64()
+            }
+            N::Raw(text) => return Exact::Finite(Decimal::from_text(text)),
+            N::Float(n) => *n,
+        };
+        match float {
+            f if f.is_nan() => Exact::NaN,
+            f if f == f64::INFINITY => Exact::Infinity,
+            f if f == f64::NEG_INFINITY => Exact::NegInfinity,
+            f => Exact::Finite(Decimal::from_text(ryu::Buffer::new().format_finite(f))),
+        }
+    }
+}
+
+/// `NaN` equals `NaN`, unlike for `f64`, so that equality is an equivalence
+/// and values can be `Eq`. Numbers are compared by their decimal value, that
+/// of floats being the shortest digits that read back as them, so raw numbers
+/// equal the floats they read as only if they hold no more digits.
+impl PartialEq for Number<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        if self.is_raw() || other.is_raw() {
+            return self.exact() == other.exact();
+        }
+        match (self.resolve(), other.resolve()) {
+            (N::Float(a), N::Float(b)) => a == b || a.is_nan() && b.is_nan(),
+            // exact, unlike comparing the integer as a float
+            (N::Float(float), integer) | (integer, N::Float(float)) => {
+                exact_integer(float) == Some(integer)
+            }
+            (a, b) => a == b,
+        }
+    }
+}
+
+impl Eq for Number<'_> {}
+
+/// Consistent with equality, floats that stand for an integer hash as that
+/// integer, which takes care of `-0.0` too. Raw numbers hash as the number
+/// their text converts to if that is exactly them, by their digits otherwise.
+impl std::hash::Hash for Number<'_> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        if self.is_raw() {
+            let exact = self.exact();
+            if Number(self.resolve()).exact() != exact {
+                return exact.hash(state);
+            }
+        }
+        let n = match self.resolve() {
+            N::Float(f) => exact_integer(f).unwrap_or(N::Float(f)),
+            n => n,
+        };
+        match n {
+            N::PosInt(n) => i128::from(n).hash(state),
+            N::NegInt(n) => i128::from(n).hash(state),
+            N::Float(f) if f.is_nan() => f64::NAN.to_bits().hash(state),
+            N::Float(f) => f.to_bits().hash(state),
+            N::Raw(_) => unreachable!("resolved"),
+        }
+    }
+}
+
+/// Numbers in order of their value as for equality, exactly also between
+/// integers and floats, with `NaN` above everything else.
+impl Ord for Number<'_> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        use std::cmp::Ordering;
+
+        if self.is_raw() || other.is_raw() {
+            return self.exact().cmp(&other.exact());
+        }
+
+        let float_integer = |float: f64, n: &N| -> Ordering {
+            let integer = match *n {
+                N::PosInt(n) => i128::from(n),
+                N::NegInt(n) => i128::from(n),
+                _ => unreachable!("an integer"),
+            };
+            match float {
+                f if f.is_nan() || f >= u64::MAX as f64 => Ordering::Greater,
+                f if f < i64::MIN as f64 => Ordering::Less,
+                // by the shortest digits, as for equality
+                f if f.fract() == 0.0 && !is_safe_integer(f) => {
+                    Number(N::Float(f)).exact().cmp(&Number(n.clone()).exact())
+                }
+                f => (f.floor() as i128).cmp(&integer).then(if f.fract() != 0.0 {
+                    Ordering::Greater
+                } else {
+                    Ordering::Equal
+                }),
+            }
+        };
+        match (self.resolve(), other.resolve()) {
+            (N::Float(a), N::Float(b)) => a
+                .partial_cmp(&b)
+                .unwrap_or_else(|| a.is_nan().cmp(&b.is_nan())),
+            (N::Float(float), integer) => float_integer(float, &integer),
+            (integer, N::Float(float)) => float_integer(float, &integer).reverse(),
+            (N::PosInt(a), N::PosInt(b)) => a.cmp(&b),
+            (N::NegInt(a), N::NegInt(b)) => a.cmp(&b),
+            (N::PosInt(_), _) => Ordering::Greater,
+            _ => Ordering::Less,
+        }
+    }
+}
+
+impl PartialOrd for Number<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl From<u64> for Number<'_> {
+    fn from(n: u64) -> Self {
+        Number(N::PosInt(n))
+    }
+}
+
+impl From<i64> for Number<'_> {
+    fn from(n: i64) -> Self {
+        match u64::try_from(n) {
+            Ok(n) => Number(N::PosInt(n)),
+            Err(_) => Number(N::NegInt(n)),
+        }
+    }
+}
+
+impl From<f64> for Number<'_> {
+    fn from(n: f64) -> Self {
+        Number(N::Float(n))
+    }
+}
+
+impl std::fmt::Display for Number<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.0 {
+            N::PosInt(n) => n.fmt(f),
+            N::NegInt(n) => n.fmt(f),
+            N::Float(n) => n.fmt(f),
+            N::Raw(text) => text.fmt(f),
+        }
+    }
+}
+
+impl Number<'_> {
+    /// Writes the number as JSON. Integers are written as they are and floats
+    /// with the fewest digits that read back as the same double, keeping a
+    /// `.0` or an exponent so that they read back as floats. `NaN` and the
+    /// infinities have no JSON form and become `null`.
+    fn write_json<W: std::fmt::Write + ?Sized>(&self, f: &mut W) -> std::fmt::Result {
+        match &self.0 {
+            N::PosInt(n) => f.write_str(itoa::Buffer::new().format(*n)),
+            N::NegInt(n) => f.write_str(itoa::Buffer::new().format(*n)),
+            _ if self.non_finite_literal().is_some() => f.write_str("null"),
+            N::Float(n) => f.write_str(ryu::Buffer::new().format_finite(*n)),
+            N::Raw(text) => f.write_str(text),
+        }
+    }
+
+    /// How JavaScript writes the number if it is `NaN` or an infinity. Raw
+    /// numbers too large for `f64` are not, their text is valid JSON.
+    fn non_finite_literal(&self) -> Option<&'static str> {
+        let n = match &self.0 {
+            N::Float(n) => *n,
+            N::Raw(text) if text.ends_with("NaN") || text.ends_with("Infinity") => {
+                Number::from_text(text).as_f64()
+            }
+            _ => return None,
+        };
+        match n {
+            n if n.is_nan() => Some("NaN"),
+            n if n == f64::INFINITY => Some("Infinity"),
+            n if n == f64::NEG_INFINITY => Some("-Infinity"),
+            _ => None,
+        }
+    }
+}
+
+/// What containers are shared through, cloning a value doesn't copy them.
+/// It is `Arc` with the `sync` feature, so that values can be sent to and
+/// shared between threads, and the cheaper `Rc` otherwise.
+#[cfg(not(feature = "sync"))]
+pub type Shared<T> = std::rc::Rc<T>;
+#[cfg(feature = "sync")]
+pub type Shared<T> = std::sync::Arc<T>;
+
+#[derive(Debug, Clone)]
+pub enum JsonValue<'input> {
+    /// The decoded text, borrowed from the input unless it had escapes.
+    String(std::borrow::Cow<'input, str>),
+    Number(Number<'input>),
+    /// Members in document order, keys are decoded the same way as strings.
+    Object(Shared<Vec<(std::borrow::Cow<'input, str>, JsonValue<'input>)>>),
+    List(Shared<Vec<JsonValue<'input>>>),
+    Boolean(bool),
+    Null,
+}
+
+/// Values are compared with an explicit stack rather than recursively, so
+/// that any value the parser gives can be compared however deep it is.
+impl PartialEq for JsonValue<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        let mut pending = vec![(self, other)];
+        while let Some(pair) = pending.pop() {
+            match pair {
+                (JsonValue::String(a), JsonValue::String(b)) if a == b => {}
+                (JsonValue::Number(a), JsonValue::Number(b)) if a == b => {}
+                (JsonValue::Boolean(a), JsonValue::Boolean(b)) if a == b => {}
+                (JsonValue::Null, JsonValue::Null) => {}
+                (JsonValue::List(a), JsonValue::List(b)) if Shared::ptr_eq(a, b) => {}
+                (JsonValue::List(a), JsonValue::List(b)) if a.len() == b.len() => {
+                    pending.extend(a.iter().zip(b.iter()));
+                }
+                (JsonValue::Object(a), JsonValue::Object(b)) if Shared::ptr_eq(a, b) => {}
+                (JsonValue::Object(a), JsonValue::Object(b)) if a.len() == b.len() => {
+                    for ((a_key, a), (b_key, b)) in a.iter().zip(b.iter()) {
+                        if a_key != b_key {
+                            return false;
+                        }
+                        pending.push((a, b));
+                    }
+                }
+                _ => return false,
+            }
+        }
+        true
+    }
+}
+
+impl Eq for JsonValue<'_> {}
+
+/// Same as equality, hashes the value with an explicit stack.
+impl std::hash::Hash for JsonValue<'_> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        let mut pending = vec![self];
+        while let Some(value) = pending.pop() {
+            std::mem::discriminant(value).hash(state);
+            match value {
+                JsonValue::String(s) => s.hash(state),
+                JsonValue::Number(n) => n.hash(state),
+                JsonValue::Boolean(b) => b.hash(state),
+                JsonValue::Null => {}
+                JsonValue::List(values) => {
+                    values.len().hash(state);
+                    pending.extend(values.iter().rev());
+                }
+                JsonValue::Object(pairs) => {
+                    pairs.len().hash(state);
+                    pairs.iter().for_each(|(key, _)| key.hash(state));
+                    pending.extend(pairs.iter().rev().map(|(_, value)| value));
+                }
+            }
+        }
+    }
+}
+
+/// A member of an object.
+pub(crate) type Member<'input> = (std::borrow::Cow<'input, str>, JsonValue<'input>);
+
+/// A container being folded by `JsonValue::fold`, along with what its values
+/// folded into so far.
+enum Folding<'value, 'input, T> {
+    List(&'value [JsonValue<'input>], Vec<T>),
+    Object(&'value [Member<'input>], Vec<T>),
+}
+
+/// A container being copied by `JsonValue::into_owned`, with the values left
+/// to copy and those copied, objects along with the key of the value being
+/// copied.
+enum Owning<'input> {
+    List(std::vec::IntoIter<JsonValue<'input>>, Vec<Value>),
+    Object(
+        std::vec::IntoIter<Member<'input>>,
+        Vec<Member<'static>>,
+        std::borrow::Cow<'static, str>,
+    ),
+}
+
+/// A value that owns all of its text, as `JsonValue::into_owned` gives.
+pub type Value = JsonValue<'static>;
+
+impl<'input> JsonValue<'input> {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            JsonValue::Number(n) => n.as_u64(),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            JsonValue::Number(n) => n.as_i64(),
+            _ => None,
+        }
+    }
+
+    pub fn as_u128(&self) -> Option<u128> {
+        match self {
+            JsonValue::Number(n) => n.as_u128(),
+            _ => None,
+        }
+    }
+
+    pub fn as_i128(&self) -> Option<i128> {
+        match self {
+            JsonValue::Number(n) => n.as_i128(),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            JsonValue::Number(n) => Some(n.as_f64()),
+            _ => None,
+        }
+    }
+
+    pub fn as_number(&self) -> Option<&Number<'input>> {
+        match self {
+            JsonValue::Number(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            JsonValue::Boolean(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// The members of an object in document order.
+    pub fn as_object(&self) -> Option<&[(std::borrow::Cow<'input, str>, JsonValue<'input>)]> {
+        match self {
+            JsonValue::Object(pairs) => Some(pairs),
+            _ => None,
+        }
+    }
+
+    /// The values of a `List`.
+    pub fn as_array(&self) -> Option<&[JsonValue<'input>]> {
+        match self {
+            JsonValue::List(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    /// The value at the key of an object or the index of a list, `None` when
+    /// there is none or the value is of the other kind.
+    pub fn get<'key>(&self, key: impl Into<Key<'key>>) -> Option<&JsonValue<'input>> {
+        match key.into() {
+            Key::Name(name) => self
+                .as_object()?
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value),
+            Key::Index(index) => self.get_index(index),
+        }
+    }
+
+    pub fn get_index(&self, index: usize) -> Option<&JsonValue<'input>> {
+        self.as_array()?.get(index)
+    }
+
+    /// The value a JSON pointer (RFC 6901) such as `/users/0/name` refers to,
+    /// the empty pointer being the value itself. The first member wins when a
+    /// key is repeated.
+    pub fn pointer(&self, pointer: &str) -> Option<&JsonValue<'input>> {
+        let mut value = self;
+        for token in pointer_tokens(pointer)? {
+            value = match value {
+                JsonValue::Object(_) => value.get(&*token)?,
+                JsonValue::List(_) => value.get_index(pointer_index(&token)?)?,
+                _ => return None,
+            };
+        }
+        Some(value)
+    }
+
+    /// Same as `pointer`, containers along the way are copied first if they
+    /// are shared with other values.
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut JsonValue<'input>> {
+        let mut value = self;
+        for token in pointer_tokens(pointer)? {
+            value = match value {
+                JsonValue::Object(pairs) => Shared::make_mut(pairs)
+                    .iter_mut()
+                    .find(|(key, _)| *key == token)
+                    .map(|(_, value)| value)?,
+                JsonValue::List(values) => {
+                    Shared::make_mut(values).get_mut(pointer_index(&token)?)?
+                }
+                _ => return None,
+            };
+        }
+        Some(value)
+    }
+
+    /// The members of an object to edit, copied first if the object is shared
+    /// with other values.
+    pub fn as_object_mut(
+        &mut self,
+    ) -> Option<&mut Vec<(std::borrow::Cow<'input, str>, JsonValue<'input>)>> {
+        match self {
+            JsonValue::Object(pairs) => Some(Shared::make_mut(pairs)),
+            _ => None,
+        }
+    }
+
+    /// Same as `as_object_mut` for the values of a list.
+    pub fn as_array_mut(&mut self) -> Option<&mut Vec<JsonValue<'input>>> {
+        match self {
+            JsonValue::List(values) => Some(Shared::make_mut(values)),
+            _ => None,
+        }
+    }
+
+    /// Sets the member of an object, in place when the key is there already,
+    /// in which case the previous value is returned. Panics when the value
+    /// isn't an object.
+    pub fn insert(
+        &mut self,
+        key: impl Into<std::borrow::Cow<'input, str>>,
+        value: JsonValue<'input>,
+    ) -> Option<JsonValue<'input>> {
+        let pairs = self.as_object_mut().expect("not an object");
+        let key = key.into();
+        match pairs.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, existing)) => Some(std::mem::replace(existing, value)),
+            None => {
+                pairs.push((key, value));
+                None
+            }
+        }
+    }
+
+    /// The member of an object with the key, to look at, change or insert it
+    /// without searching the members twice. Panics when the value isn't an
+    /// object.
+    pub fn entry<'value>(
+        &'value mut self,
+        key: impl Into<std::borrow::Cow<'input, str>>,
+    ) -> Entry<'value, 'input> {
+        Entry::new(self.as_object_mut().expect("not an object"), key.into())
+    }
+
+    /// Removes every member with the key from an object and returns the value
+    /// of the first one, if any.
+    pub fn remove(&mut self, key: &str) -> Option<JsonValue<'input>> {
+        let pairs = self.as_object_mut()?;
+        let index = pairs.iter().position(|(k, _)| k == key)?;
+        let (_, value) = pairs.remove(index);
+        pairs.retain(|(k, _)| k != key);
+        Some(value)
+    }
+
+    /// Appends to a list, panics when the value isn't one.
+    pub fn push(&mut self, value: JsonValue<'input>) {
+        self.as_array_mut().expect("not a list").push(value);
+    }
+
+    /// Removes the last value of a list.
+    pub fn pop(&mut self) -> Option<JsonValue<'input>> {
+        self.as_array_mut()?.pop()
+    }
+
+    /// The members of an object collected into a map, a `BTreeMap<String,
+    /// JsonValue>`, `HashMap` or `IndexMap` for instance. The first member
+    /// wins when a key is repeated, as with `get`.
+    pub fn into_map<M>(mut self) -> Option<M>
+    where
+        M: FromIterator<(String, JsonValue<'input>)>,
+    {
+        let JsonValue::Object(pairs) = &mut self else {
+            return None;
+        };
+        let mut pairs = match Shared::get_mut(pairs) {
+            Some(pairs) => std::mem::take(pairs),
+            None => pairs.to_vec(),
+        };
+        let mut seen = std::collections::HashSet::new();
+        pairs.retain(|(key, _)| seen.insert(key.clone()));
+        let pairs = pairs.into_iter();
+        Some(
+            pairs
+                .map(|(key, value)| (key.into_owned(), value))
+                .collect(),
+        )
+    }
+
+    /// An object with the entries of a map, or any other pairs, as members in
+    /// iteration order.
+    pub fn from_map<K>(map: impl IntoIterator<Item = (K, JsonValue<'input>)>) -> Self
+    where
+        K: Into<std::borrow::Cow<'input, str>>,
+    {
+        let pairs = map.into_iter().map(|(key, value)| (key.into(), value));
+        JsonValue::Object(Shared::new(pairs.collect()))
+    }
+
+    /// Drops the value with its containers emptied into a local stack.
+    /// Dropping a value the usual way recurses once per level of nesting and
+    /// overflows the stack on deep values the parser gives, which should go
+    /// through here instead.
+    pub fn drop_iteratively(self) {
+        let mut stack = vec![self];
+        while let Some(mut value) = stack.pop() {
+            match &mut value {
+                JsonValue::List(values) => {
+                    if let Some(values) = Shared::get_mut(values) {
+                        stack.append(values);
+                    }
+                }
+                JsonValue::Object(pairs) => {
+                    if let Some(pairs) = Shared::get_mut(pairs) {
+                        stack.extend(pairs.drain(..).map(|(_, value)| value));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Moves the value out, leaving `Null` in its place.
+    pub fn take(&mut self) -> JsonValue<'input> {
+        std::mem::replace(self, JsonValue::Null)
+    }
+
+    /// Puts `value` in place of the value, which is returned.
+    pub fn replace(&mut self, value: JsonValue<'input>) -> JsonValue<'input> {
+        std::mem::replace(self, value)
+    }
+
+    /// Merges `other` into the value, recursively for objects. Members of
+    /// `other` are merged into the member with the same key or added after the
+    /// others, arrays are combined as `strategy` says and anything else,
+    /// `null` included, replaces what was there.
+    pub fn merge(&mut self, other: &JsonValue<'input>, strategy: MergeStrategy) {
+        let mergeable = |this: &JsonValue, other: &JsonValue| match (this, other) {
+            (JsonValue::Object(_), JsonValue::Object(_)) => true,
+            (JsonValue::List(_), JsonValue::List(_)) => strategy != MergeStrategy::Replace,
+            _ => false,
+        };
+        // values along with what gets merged into them in order, a key that
+        // repeats in an object merges more than one value into the member.
+        // Kept on an explicit stack, so that values of any depth get merged.
+        let mut pending = vec![(self, vec![other])];
+        while let Some((this, mut others)) = pending.pop() {
+            // a merge leaves a value of the kind merged in, whatever comes
+            // before the last value that doesn't merge gets replaced
+            let mut kind: &JsonValue = this;
+            let mut replacing = None;
+            for (index, other) in others.iter().enumerate() {
+                if !mergeable(kind, other) {
+                    replacing = Some(index);
+                }
+                kind = other;
+            }
+            if let Some(index) = replacing {
+                this.replace(others[index].clone()).drop_iteratively();
+                others.drain(..=index);
+            }
+            match this {
+                JsonValue::Object(pairs) => {
+                    let pairs = Shared::make_mut(pairs);
+                    let mut positions = std::collections::HashMap::new();
+                    for (position, (key, _)) in pairs.iter().enumerate() {
+                        positions.entry(key.clone()).or_insert(position);
+                    }
+                    let mut merged = std::collections::HashMap::<_, Vec<_>>::new();
+                    for other in others {
+                        let JsonValue::Object(others) = other else {
+                            unreachable!("only objects merge into an object")
+                        };
+                        for (key, value) in others.iter() {
+                            match positions.get(key) {
+                                Some(&position) => merged.entry(position).or_default().push(value),
+                                None => {
+                                    positions.insert(key.clone(), pairs.len());
+                                    pairs.push((key.clone(), value.clone()));
+                                }
+                            }
+                        }
+                    }
+                    for (position, (_, value)) in pairs.iter_mut().enumerate() {
+                        if let Some(others) = merged.remove(&position) {
+                            pending.push((value, others));
+                        }
+                    }
+                }
+                JsonValue::List(values) => {
+                    let values = Shared::make_mut(values);
+                    let others = others.into_iter().flat_map(|other| match other {
+                        JsonValue::List(others) => others.iter(),
+                        _ => unreachable!("only lists merge into a list"),
+                    });
+                    if strategy == MergeStrategy::Concatenate {
+                        values.extend(others.cloned());
+                        continue;
+                    }
+                    let mut seen: std::collections::HashSet<_> = values.iter().collect();
+                    let added: Vec<_> =
+                        others.filter(|value| seen.insert(value)).cloned().collect();
+                    values.extend(added);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Number of values of a list or members of an object, scalars have none.
+    pub fn len(&self) -> usize {
+        match self {
+            JsonValue::List(values) => values.len(),
+            JsonValue::Object(pairs) => pairs.len(),
+            _ => 0,
+        }
+    }
+
+    /// Whether `len` is zero, which includes every scalar.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_string(&self) -> bool {
+        matches!(self, JsonValue::String(_))
+    }
+
+    pub fn is_number(&self) -> bool {
+        matches!(self, JsonValue::Number(_))
+    }
+
+    pub fn is_bool(&self) -> bool {
+        matches!(self, JsonValue::Boolean(_))
+    }
+
+    pub fn is_object(&self) -> bool {
+        matches!(self, JsonValue::Object(_))
+    }
+
+    pub fn is_array(&self) -> bool {
+        matches!(self, JsonValue::List(_))
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, JsonValue::Null)
+    }
+
+    /// What the value is, as in "string" or "object", for error messages.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            JsonValue::String(_) => "string",
+            JsonValue::Number(_) => "number",
+            JsonValue::Object(_) => "object",
+            JsonValue::List(_) => "array",
+            JsonValue::Boolean(_) => "boolean",
+            JsonValue::Null => "null",
+        }
+    }
+
+    /// The value as a `T`, `value.try_as::<Option<i64>>()` for instance, same
+    /// as `T::try_from(&value)`.
+    pub fn try_as<'value, T>(&'value self) -> Result<T, TypeError>
+    where
+        T: TryFrom<&'value JsonValue<'input>, Error = TypeError>,
+    {
+        T::try_from(self)
+    }
+
+    /// Folds the value bottom up, scalars through `scalar`, lists and objects
+    /// through `list` and `object` once their values are folded. Containers
+    /// are kept on an explicit stack, so that any value the parser gives can
+    /// be folded however deep it is.
+    pub(crate) fn fold<'value, T, E>(
+        &'value self,
+        mut scalar: impl FnMut(&'value JsonValue<'input>) -> Result<T, E>,
+        mut list: impl FnMut(Vec<T>) -> Result<T, E>,
+        mut object: impl FnMut(&'value [Member<'input>], Vec<T>) -> Result<T, E>,
+    ) -> Result<T, E> {
+        let mut stack: Vec<Folding<'value, 'input, T>> = Vec::new();
+        let mut value = self;
+        loop {
+            let mut folded = match value {
+                JsonValue::List(values) => {
+                    stack.push(Folding::List(values, Vec::with_capacity(values.len())));
+                    None
+                }
+                JsonValue::Object(pairs) => {
+                    stack.push(Folding::Object(pairs, Vec::with_capacity(pairs.len())));
+                    None
+                }
+                value => Some(scalar(value)?),
+            };
+            // hand the folded value over to its container, folding those
+            // that have all of their values
+            loop {
+                match stack.last_mut() {
+                    None => return Ok(folded.expect("a folded value")),
+                    Some(Folding::List(values, folded_values)) => {
+                        let values: &'value [JsonValue<'input>] = values;
+                        folded_values.extend(folded.take());
+                        if let Some(next) = values.get(folded_values.len()) {
+                            value = next;
+                            break;
+                        }
+                    }
+                    Some(Folding::Object(pairs, folded_values)) => {
+                        let pairs: &'value [Member<'input>] = pairs;
+                        folded_values.extend(folded.take());
+                        if let Some((_, next)) = pairs.get(folded_values.len()) {
+                            value = next;
+                            break;
+                        }
+                    }
+                }
+                folded = Some(match stack.pop() {
+                    Some(Folding::List(_, values)) => list(values)?,
+                    Some(Folding::Object(pairs, values)) => object(pairs, values)?,
+                    None => unreachable!("a container was on the stack"),
+                });
+            }
+        }
+    }
+
+    /// Rebuilds the value passing every string and object key borrowed from the
+    /// input through `f`.
+    pub(crate) fn map_strs<'output>(
+        &self,
+        f: &impl Fn(&'input str) -> &'output str,
+    ) -> JsonValue<'output> {
+        let map_cow = |s: &std::borrow::Cow<'input, str>| match s {
+            std::borrow::Cow::Borrowed(s) => std::borrow::Cow::Borrowed(f(s)),
+            std::borrow::Cow::Owned(s) => std::borrow::Cow::Owned(s.clone()),
+        };
+        let Ok(value) = self.fold::<_, std::convert::Infallible>(
+            |value| {
+                Ok(match value {
+                    JsonValue::String(s) => JsonValue::String(map_cow(s)),
+                    JsonValue::Number(n) => JsonValue::Number(n.map_str(f)),
+                    JsonValue::Boolean(b) => JsonValue::Boolean(*b),
+                    _ => JsonValue::Null,
+                })
+            },
+            |values| Ok(JsonValue::List(Shared::new(values))),
+            |pairs, values| {
+                let keys = pairs.iter().map(|(key, _)| map_cow(key));
+                Ok(JsonValue::Object(Shared::new(keys.zip(values).collect())))
+            },
+        );
+        value
+    }
+
+    /// Copies whatever the value borrows from the input, so that it can
+    /// outlive the input. Strings that are already owned are moved over where
+    /// the value is the only one holding them. Containers are kept on an
+    /// explicit stack, as deep values would overflow the call stack.
+    pub fn into_owned(self) -> JsonValue<'static> {
+        let own = |s: std::borrow::Cow<'input, str>| std::borrow::Cow::Owned(s.into_owned());
+        let mut stack: Vec<Owning<'input>> = Vec::new();
+        let mut value = self;
+        loop {
+            let mut owned = match &mut value {
+                JsonValue::List(values) => {
+                    let values = match Shared::get_mut(values) {
+                        Some(values) => std::mem::take(values),
+                        None => values.to_vec(),
+                    };
+                    let owned_values = Vec::with_capacity(values.len());
+                    stack.push(Owning::List(values.into_iter(), owned_values));
+                    None
+                }
+                JsonValue::Object(pairs) => {
+                    let pairs = match Shared::get_mut(pairs) {
+                        Some(pairs) => std::mem::take(pairs),
+                        None => pairs.to_vec(),
+                    };
+                    let owned_pairs = Vec::with_capacity(pairs.len());
+                    stack.push(Owning::Object(
+                        pairs.into_iter(),
+                        owned_pairs,
+                        Default::default(),
+                    ));
+                    None
+                }
+                JsonValue::String(s) => Some(JsonValue::String(own(std::mem::take(s)))),
+                JsonValue::Number(n) => Some(JsonValue::Number(n.clone().into_owned())),
+                JsonValue::Boolean(b) => Some(JsonValue::Boolean(*b)),
+                JsonValue::Null => Some(JsonValue::Null),
+            };
+            // hand the owned value over to its container, completing those
+            // that have all of their values
+            loop {
+                match stack.last_mut() {
+                    None => return owned.expect("an owned value"),
+                    Some(Owning::List(values, owned_values)) => {
+                        owned_values.extend(owned.take());
+                        if let Some(next) = values.next() {
+                            value = next;
+                            break;
+                        }
+                    }
+                    Some(Owning::Object(pairs, owned_pairs, key)) => {
+                        if let Some(owned) = owned.take() {
+                            owned_pairs.push((std::mem::take(key), owned));
+                        }
+                        if let Some((next_key, next)) = pairs.next() {
+                            *key = own(next_key);
+                            value = next;
+                            break;
+                        }
+                    }
+                }
+                owned = Some(match stack.pop() {
+                    Some(Owning::List(_, values)) => JsonValue::List(Shared::new(values)),
+                    Some(Owning::Object(_, pairs, _)) => JsonValue::Object(Shared::new(pairs)),
+                    None => unreachable!("a container was on the stack"),
+                });
+            }
+        }
+    }
+}
+
+impl<'input> From<&'input str> for JsonValue<'input> {
+    fn from(s: &'input str) -> Self {
+        JsonValue::String(s.into())
+    }
+}
+
+impl From<String> for JsonValue<'_> {
+    fn from(s: String) -> Self {
+        JsonValue::String(s.into())
+    }
+}
+
+impl<'input> From<std::borrow::Cow<'input, str>> for JsonValue<'input> {
+    fn from(s: std::borrow::Cow<'input, str>) -> Self {
+        JsonValue::String(s)
+    }
+}
+
+impl<'input> From<Number<'input>> for JsonValue<'input> {
+    fn from(n: Number<'input>) -> Self {
+        JsonValue::Number(n)
+    }
+}
+
+impl From<u64> for JsonValue<'_> {
+    fn from(n: u64) -> Self {
+        JsonValue::Number(n.into())
+    }
+}
+
+impl From<i64> for JsonValue<'_> {
+    fn from(n: i64) -> Self {
+        JsonValue::Number(n.into())
+    }
+}
+
+/// So that integer literals convert without a suffix.
+impl From<i32> for JsonValue<'_> {
+    fn from(n: i32) -> Self {
+        JsonValue::Number(i64::from(n).into())
+    }
+}
+
+impl From<f64> for JsonValue<'_> {
+    fn from(n: f64) -> Self {
+        JsonValue::Number(n.into())
+    }
+}
+
+impl From<bool> for JsonValue<'_> {
+    fn from(b: bool) -> Self {
+        JsonValue::Boolean(b)
+    }
+}
+
+/// `None` is `Null`.
+impl<'input, T: Into<JsonValue<'input>>> From<Option<T>> for JsonValue<'input> {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(JsonValue::Null, Into::into)
+    }
+}
+
+impl<'input> From<Vec<JsonValue<'input>>> for JsonValue<'input> {
+    fn from(values: Vec<JsonValue<'input>>) -> Self {
+        JsonValue::List(Shared::new(values))
+    }
+}
+
+/// An object with the members in the given order.
+impl<'input, K> From<Vec<(K, JsonValue<'input>)>> for JsonValue<'input>
+where
+    K: Into<std::borrow::Cow<'input, str>>,
+{
+    fn from(pairs: Vec<(K, JsonValue<'input>)>) -> Self {
+        JsonValue::from_map(pairs)
+    }
+}
+
+/// A value that isn't of the type asked for, `found` is its `type_name`.
+#[derive(Debug, Clone, Copy, thiserror::Error, PartialEq, Eq)]
+#[error("Expected {expected}, found {found}")]
+pub struct TypeError {
+    pub expected: &'static str,
+    pub found: &'static str,
+}
+
+impl TypeError {
+    fn new(expected: &'static str, value: &JsonValue) -> Self {
+        TypeError {
+            expected,
+            found: value.type_name(),
+        }
+    }
+}
+
+impl<'value> TryFrom<&'value JsonValue<'_>> for &'value str {
+    type Error = TypeError;
+
+    fn try_from(value: &'value JsonValue<'_>) -> Result<Self, TypeError> {
+        value.as_str().ok_or(TypeError::new("string", value))
+    }
+}
+
+/// Integers only, and only those in range.
+impl TryFrom<&JsonValue<'_>> for i64 {
+    type Error = TypeError;
+
+    fn try_from(value: &JsonValue<'_>) -> Result<Self, TypeError> {
+        value.as_i64().ok_or(TypeError::new("i64", value))
+    }
+}
+
+/// Integers only, and only those in range.
+impl TryFrom<&JsonValue<'_>> for u64 {
+    type Error = TypeError;
+
+    fn try_from(value: &JsonValue<'_>) -> Result<Self, TypeError> {
+        value.as_u64().ok_or(TypeError::new("u64", value))
+    }
+}
+
+impl TryFrom<&JsonValue<'_>> for f64 {
+    type Error = TypeError;
+
+    fn try_from(value: &JsonValue<'_>) -> Result<Self, TypeError> {
+        value.as_f64().ok_or(TypeError::new("number", value))
+    }
+}
+
+impl TryFrom<&JsonValue<'_>> for bool {
+    type Error = TypeError;
+
+    fn try_from(value: &JsonValue<'_>) -> Result<Self, TypeError> {
+        value.as_bool().ok_or(TypeError::new("boolean", value))
+    }
+}
+
+/// `Null` is `None`, anything else has to convert to `T`.
+impl<'value, 'input, T> TryFrom<&'value JsonValue<'input>> for Option<T>
+where
+    T: TryFrom<&'value JsonValue<'input>, Error = TypeError>,
+{
+    type Error = TypeError;
+
+    fn try_from(value: &'value JsonValue<'input>) -> Result<Self, TypeError> {
+        match value {
+            JsonValue::Null => Ok(None),
+            value => T::try_from(value).map(Some),
+        }
+    }
+}
+
+/// `Null`, so that `std::mem::take` works as `JsonValue::take` does.
+impl Default for JsonValue<'_> {
+    fn default() -> Self {
+        JsonValue::Null
+    }
+}
+
+/// Compact JSON, same as `to_string`, or pretty with `{:#}`.
+impl std::fmt::Display for JsonValue<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let options = SerializerOptions {
+            pretty: f.alternate().then(PrettyConfig::default),
+            ..Default::default()
+        };
+        ser::write_value(f, self, options)
+    }
+}
+
+/// Parses with the default options into a value that doesn't borrow from
+/// `s`, `from_str` avoids the copies when the input outlives the value.
+impl std::str::FromStr for Value {
+    type Err = ParserError;
+
+    fn from_str(s: &str) -> Result<Self, ParserError> {
+        from_str(s).map(JsonValue::into_owned)
+    }
+}
+
+/// What is left to compare of two values, pushed in reverse so that they
+/// come off the stack in the order the values are compared.
+enum Comparing<'a, 'input> {
+    Values(&'a JsonValue<'input>, &'a JsonValue<'input>),
+    Keys(&'a str, &'a str),
+    Lengths(usize, usize),
+}
+
+/// A total order to sort by, values of different types rank as null, booleans,
+/// numbers, strings, arrays then objects. Numbers compare as `Number` does,
+/// strings by code point, arrays element by element and objects member by
+/// member in document order, key first. Same as equality, containers are
+/// compared with an explicit stack.
+impl Ord for JsonValue<'_> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        let rank = |value: &JsonValue| match value {
+            JsonValue::Null => 0,
+            JsonValue::Boolean(_) => 1,
+            JsonValue::Number(_) => 2,
+            JsonValue::String(_) => 3,
+            JsonValue::List(_) => 4,
+            JsonValue::Object(_) => 5,
+        };
+        let mut pending = vec![Comparing::Values(self, other)];
+        while let Some(comparing) = pending.pop() {
+            let ordering = match comparing {
+                Comparing::Keys(a, b) => a.cmp(b),
+                Comparing::Lengths(a, b) => a.cmp(&b),
+                Comparing::Values(a, b) => match (a, b) {
+                    (JsonValue::Boolean(a), JsonValue::Boolean(b)) => a.cmp(b),
+                    (JsonValue::Number(a), JsonValue::Number(b)) => a.cmp(b),
+                    (JsonValue::String(a), JsonValue::String(b)) => a.cmp(b),
+                    (JsonValue::List(a), JsonValue::List(b)) => {
+                        if !Shared::ptr_eq(a, b) {
+                            pending.push(Comparing::Lengths(a.len(), b.len()));
+                            let values = a.iter().zip(b.iter()).rev();
+                            pending.extend(values.map(|(a, b)| Comparing::Values(a, b)));
+                        }
+                        std::cmp::Ordering::Equal
+                    }
+                    (JsonValue::Object(a), JsonValue::Object(b)) => {
+                        if !Shared::ptr_eq(a, b) {
+                            pending.push(Comparing::Lengths(a.len(), b.len()));
+                            for ((a_key, a), (b_key, b)) in a.iter().zip(b.iter()).rev() {
+                                pending.push(Comparing::Values(a, b));
+                                pending.push(Comparing::Keys(a_key, b_key));
+                            }
+                        }
+                        std::cmp::Ordering::Equal
+                    }
+                    (a, b) => rank(a).cmp(&rank(b)),
+                },
+            };
+            if ordering.is_ne() {
+                return ordering;
+            }
+        }
+        std::cmp::Ordering::Equal
+    }
+}
+
+impl PartialOrd for JsonValue<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// The reference tokens of a JSON pointer with `~1` and `~0` decoded, `None`
+/// when it doesn't start with `/`.
+fn pointer_tokens(pointer: &str) -> Option<Vec<String>> {
+    if pointer.is_empty() {
+        return Some(Vec::new());
+    }
+    let tokens = pointer.strip_prefix('/')?.split('/');
+    Some(
+        tokens
+            .map(|token| token.replace("~1", "/").replace("~0", "~"))
+            .collect(),
+    )
+}
+
+/// Array indices are digits without leading zeros.
+fn pointer_index(token: &str) -> Option<usize> {
+    let digits = !token.is_empty() && token.bytes().all(|b| b.is_ascii_digit());
+    let leading_zero = token.starts_with('0') && token != "0";
+    (digits && !leading_zero).then(|| token.parse().ok())?
+}
+
+/// What `JsonValue::get` looks up, the member of an object or the value of a
+/// list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Key<'key> {
+    Name(&'key str),
+    Index(usize),
+}
+
+impl<'key> From<&'key str> for Key<'key> {
+    fn from(name: &'key str) -> Self {
+        Key::Name(name)
+    }
+}
+
+impl From<usize> for Key<'_> {
+    fn from(index: usize) -> Self {
+        Key::Index(index)
+    }
+}
+
+/// What indexing gives for missing keys, `Rc` keeps `JsonValue` from being
+/// `Sync` without the `sync` feature but there is nothing to share in a
+/// `Null`.
+struct StaticNull(JsonValue<'static>);
+
+// SAFETY: the only value is the `Null` of `NULL`, which holds no `Rc` and
+// can't be changed through the shared references handed out, so no thread can
+// reach a reference count through it. The wrapper is private, so no other
+// value can be put in one.
+unsafe impl Sync for StaticNull {}
+
+static NULL: StaticNull = StaticNull(JsonValue::Null);
+
+/// `value["key"]` is `Null` rather than a panic when the key is missing or the
+/// value isn't an object, so that lookups can be chained.
+impl<'input> std::ops::Index<&str> for JsonValue<'input> {
+    type Output = JsonValue<'input>;
+
+    fn index(&self, name: &str) -> &Self::Output {
+        self.get(name).unwrap_or(&NULL.0)
+    }
+}
+
+/// Same as `Index<&str>` for lists.
+impl<'input> std::ops::Index<usize> for JsonValue<'input> {
+    type Output = JsonValue<'input>;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        self.get_index(index).unwrap_or(&NULL.0)
+    }
+}
+
+pub fn from_str<'input>(input: &'input str) -> Result<JsonValue<'input>, ParserError> {
+    from_str_with_options(input, ParserOptions::default())
+}
+
+pub fn from_str_with_options<'input>(
+    input: &'input str,
+    options: ParserOptions,
+) -> Result<JsonValue<'input>, ParserError> {
+    parse_with(input, options, FromInput)
+}
+
+/// Parses the document with its strings passed through `strings`.
+pub(crate) fn parse_with<'input, 'output>(
+    input: &'input str,
+    options: ParserOptions,
+    strings: impl Strings<'input, 'output>,
+) -> Result<JsonValue<'output>, ParserError>
+where
+    'output: 'input,
+{
+    let state = State { current: 0 };
+    let (result, state) = document(options, strings).parse(input, state)?;
+    if state.current == input.len() {
+        Ok(result)
+    } else {
+        result.drop_iteratively();
+        Err(ParserError::TrailingCharacters(state.current))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // test the pattern function
+    #[test]
+    fn test_pattern() {
+        let parser = pat("hello");
+        let input = "hello world";
+        let state = State { current: 0 };
+        let result = parser.parse(input, state).unwrap();
+        assert_eq!(result, ("hello", State { current: 5 }));
+    }
+
+    // test the or function
+    #[test]
+    fn test_or() {
+        let parser = or(pat("hello"), pat("world"));
+        let input = "world";
+        let state = State { current: 0 };
+        let result = parser.parse(input, state).unwrap();
+        assert_eq!(result, ("world", State { current: 5 }));
+    }
+
+    // test the farthest failure of the or function
+    #[test]
+    fn test_or_farthest() {
+        let state = State { current: 0 };
+        let deep = || bind(pat("ab"), |_| pat("c"));
+        let result = or(deep(), pat("x")).parse("abd", state).unwrap_err();
+        assert_eq!(result, ParserError::NoParse(2));
+        let result = or(pat("x"), deep()).parse("abd", state).unwrap_err();
+        assert_eq!(result, ParserError::NoParse(2));
+    }
+
+    // test the cut function
+    #[test]
+    fn test_cut() {
+        let state = State { current: 0 };
+        let parser = or(
+            cut(pat("{"), Expected::CLOSE_BRACE, |_| pat("}")),
+            pat("{x"),
+        );
+        assert_eq!(parser.parse("{}", state).unwrap().0, "}");
+        assert_eq!(
+            parser.parse("{x", state).unwrap_err(),
+            ParserError::Unexpected(1, Expected::CLOSE_BRACE)
+        );
+        assert_eq!(
+            parser.parse("x", state).unwrap_err(),
+            ParserError::NoParse(0)
+        );
+    }
+
+    // test the labels of errors within containers
+    #[test]
+    fn test_error_labels() {
+        let error = from_str("[0, 1, 2, {\"a\": 1, 2: 3}]").unwrap_err();
+        assert_eq!(error.labels(), [Label::Key, Label::Element(3)]);
+        assert_eq!(error.root(), &ParserError::Unexpected(19, Expected::STRING));
+        assert_eq!(error.position(), 19);
+        assert_eq!(
+            error.to_string(),
+            "in object key, in array element 3: Unexpected character at position 19, expected a string"
+        );
+        let error = from_str("{\"a\\\"\": [{}, {\"b\": tru}]}").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "in object member \"b\", in array element 1, in object member \"a\\\"\": Unexpected character at position 19, expected a value"
+        );
+        assert_eq!(from_str("[1 2]").unwrap_err().labels(), [Label::Element(1)]);
+        assert_eq!(from_str("{\"a\": 1,").unwrap_err().labels(), [Label::Key]);
+        let options = ParserOptions {
+            duplicate_keys: DuplicateKeys::Error,
+            ..Default::default()
+        };
+        let error = from_str_with_options("[{\"a\": 1, \"a\": 2}]", options).unwrap_err();
+        assert_eq!(error.labels(), [Label::Key, Label::Element(0)]);
+        assert_eq!(error.root(), &ParserError::DuplicateKey(10));
+        assert_eq!(from_str("tru").unwrap_err().labels(), []);
+    }
+
+    // test the take_while function
+    #[test]
+    fn test_take_while() {
+        let parser = take_while(|c| c.is_alphabetic());
+        let input = "hello world";
         let state = State { current: 0 };
         let result = parser.parse(input, state).unwrap();
         assert_eq!(result, ("hello", State { current: 5 }));
     }
 
-    // test the pure function
+    // test the pure function
+    #[test]
+    fn test_pure() {
+        let parser = success("hello");
+        let input = "world";
+        let state = State { current: 0 };
+        let result = parser.parse(input, state).unwrap();
+        assert_eq!(result, ("hello", State { current: 0 }));
+    }
+
+    // test the bind parser using the pure parser
+    #[test]
+    fn test_bind() {
+        let parser = bind(pat("hello"), |s| success(s.to_uppercase()));
+        let input = "hello world";
+        let state = State { current: 0 };
+        let result = parser.parse(input, state).unwrap();
+        assert_eq!(result, ("HELLO".to_string(), State { current: 5 }));
+    }
+
+    // test the string parser
+    #[test]
+    fn test_string() {
+        let parser = string(ParserOptions::default());
+        let input = "\"hello\"";
+        let state = State { current: 0 };
+        let result = parser.parse(input, state).unwrap();
+        assert_eq!(
+            result,
+            (JsonValue::String("hello".into()), State { current: 7 })
+        );
+    }
+
+    // test the string parser in strict mode
+    #[test]
+    fn test_string_strict() {
+        let options = ParserOptions {
+            strict: true,
+            ..Default::default()
+        };
+        let parser = string(options);
+        let input = "\"line\nbreak\"";
+        let state = State { current: 0 };
+        let result = parser.parse(input, state).unwrap_err();
+        assert_eq!(result, ParserError::ControlCharacter(5));
+        assert_eq!(
+            from_str_with_options("[\"tab\there\"]", options)
+                .unwrap_err()
+                .root(),
+            &ParserError::ControlCharacter(5)
+        );
+        let value = from_str_with_options("\"tab\\there\"", options).unwrap();
+        assert_eq!(value.as_str(), Some("tab\there"));
+
+        let parser = string(ParserOptions::default());
+        let state = State { current: 0 };
+        let result = parser.parse(input, state).unwrap();
+        assert_eq!(
+            result,
+            (
+                JsonValue::String("line\nbreak".into()),
+                State { current: 12 }
+            )
+        );
+    }
+
+    // test the escaped quotes and backslashes
+    #[test]
+    fn test_string_escaped_quotes() {
+        let value = from_str("[\"say \\\"hi\\\"\", \"\\\\\", \"end\"]").unwrap();
+        let JsonValue::List(values) = &value else {
+            panic!("not a list")
+        };
+        assert_eq!(values[0].as_str(), Some("say \"hi\""));
+        assert_eq!(values[1].as_str(), Some("\\"));
+        assert_eq!(values[2].as_str(), Some("end"));
+        assert!(from_str("\"open \\\"").is_err());
+
+        let value = from_str("\"é\\\"ü\"").unwrap();
+        assert_eq!(value.as_str(), Some("é\"ü"));
+        assert_eq!(from_str("\"\\é\""), Err(ParserError::InvalidEscape(1)));
+    }
+
+    // test the unicode escapes
+    #[test]
+    fn test_string_unicode_escapes() {
+        let value = from_str("\"\\u00e9\"").unwrap();
+        assert_eq!(value.as_str(), Some("é"));
+        let value = from_str("\"\\ud83d\\ude00\"").unwrap();
+        assert_eq!(value.as_str(), Some("😀"));
+
+        assert_eq!(
+            from_str("[\"ok\", \"\\ud83d\"]").unwrap_err().root(),
+            &ParserError::InvalidEscape(8)
+        );
+        assert_eq!(
+            from_str("{\"a\": \"\\u12x4\"}").unwrap_err().root(),
+            &ParserError::InvalidEscape(7)
+        );
+        assert_eq!(from_str("\"\\q\""), Err(ParserError::InvalidEscape(1)));
+
+        let options = ParserOptions {
+            replacement: Some(Replacement::default()),
+            ..Default::default()
+        };
+        let value = from_str_with_options("\"\\ude00!\"", options).unwrap();
+        assert_eq!(value.as_str(), Some("\u{fffd}!"));
+    }
+
+    // test the single quoted strings
+    #[test]
+    fn test_string_single_quotes() {
+        let options = ParserOptions {
+            single_quotes: true,
+            ..Default::default()
+        };
+        let value = from_str_with_options("{'say': 'it\\'s \"fine\"'}", options).unwrap();
+        assert_eq!(
+            value,
+            JsonValue::Object(Shared::new(vec![(
+                "say".into(),
+                JsonValue::String("it's \"fine\"".into())
+            )]))
+        );
+        assert!(from_str("['nope']").is_err());
+        assert!(from_str_with_options("\"\\'\"", options).is_err());
+        let strict = ParserOptions {
+            strict: true,
+            ..options
+        };
+        assert!(from_str_with_options("'nope'", strict).is_err());
+    }
+
+    // test the borrowed and owned strings and keys
+    #[test]
+    fn test_string_cow() {
+        use std::borrow::Cow;
+
+        let value = from_str("{\"plain\": \"a\\nb\", \"tab\\t\": \"plain\"}").unwrap();
+        let JsonValue::Object(pairs) = &value else {
+            panic!("not an object")
+        };
+        assert!(matches!(pairs[0].0, Cow::Borrowed("plain")));
+        assert!(matches!(&pairs[0].1, JsonValue::String(Cow::Owned(s)) if s == "a\nb"));
+        assert!(matches!(&pairs[1].0, Cow::Owned(key) if key == "tab\t"));
+        assert!(matches!(
+            pairs[1].1,
+            JsonValue::String(Cow::Borrowed("plain"))
+        ));
+    }
+
+    // test the number parser
+    #[test]
+    fn test_number() {
+        let parser = number(ParserOptions::default());
+        let input = "123";
+        let state = State { current: 0 };
+        let result = parser.parse(input, state).unwrap();
+        assert_eq!(
+            result,
+            (JsonValue::Number(123u64.into()), State { current: 3 })
+        );
+
+        let parser = number(ParserOptions::default());
+        let input = "-123";
+        let state = State { current: 0 };
+        let result = parser.parse(input, state).unwrap();
+        assert_eq!(
+            result,
+            (JsonValue::Number((-123i64).into()), State { current: 4 })
+        );
+
+        let parser = number(ParserOptions::default());
+        let input = "-00000000000001";
+        let state = State { current: 0 };
+        let result = parser.parse(input, state).unwrap_err();
+        assert_eq!(result, ParserError::InvalidNumber(0));
+    }
+
+    // test the integer numbers
+    #[test]
+    fn test_number_integer() {
+        let value = from_str("9007199254740993").unwrap();
+        assert_eq!(value, JsonValue::Number(9007199254740993u64.into()));
+        let value = from_str("-9223372036854775808").unwrap();
+        assert_eq!(value, JsonValue::Number(i64::MIN.into()));
+        let JsonValue::Number(n) = &from_str("18446744073709551616").unwrap() else {
+            panic!("not a number")
+        };
+        assert_eq!((n.as_f64(), n.is_f64()), (18446744073709551616.0, true));
+        let JsonValue::Number(n) = &from_str("-0").unwrap() else {
+            panic!("not a number")
+        };
+        assert!(n.as_f64().is_sign_negative());
+        assert_eq!(from_str("1.0").unwrap(), JsonValue::Number(1u64.into()));
+        assert_ne!(
+            Number::from(9007199254740993u64),
+            Number::from(9007199254740992.0)
+        );
+    }
+
+    // test the arbitrary precision numbers
+    #[test]
+    fn test_number_arbitrary_precision() {
+        let options = ParserOptions {
+            arbitrary_precision: true,
+            ..Default::default()
+        };
+        let input = "[0.1000000000000000000000001, 1234567890123456789012345678901234567890]";
+        let JsonValue::List(values) = &from_str_with_options(input, options).unwrap() else {
+            panic!("not a list")
+        };
+        let JsonValue::Number(n) = &values[0] else {
+            panic!("not a number")
+        };
+        assert_eq!(n.as_str(), Some("0.1000000000000000000000001"));
+        assert_eq!(n.to_string(), "0.1000000000000000000000001");
+        assert_ne!(n, &Number::from(0.1));
+        assert!(n > &Number::from(0.1));
+        let JsonValue::Number(n) = &values[1] else {
+            panic!("not a number")
+        };
+        assert_eq!(n.to_string(), "1234567890123456789012345678901234567890");
+        assert_eq!(n.as_u64(), None);
+        assert_eq!(Number::from(1u64).as_str(), None);
+    }
+
+    // test the exact comparison of arbitrary precision numbers
+    #[test]
+    fn test_number_arbitrary_precision_eq() {
+        use std::collections::HashSet;
+
+        let options = ParserOptions {
+            arbitrary_precision: true,
+            allow_nan: true,
+            ..Default::default()
+        };
+        let input = "[18446744073709551616, 18446744073709551617, 1.0e0, 100e-2, 0.1, -0.0, 1e99999999999999999999, -Infinity, NaN, 1e23, 123456789012345678901234, 18446744073709552000]";
+        let value = from_str_with_options(input, options).unwrap();
+        let n = |index: usize| match &value[index] {
+            JsonValue::Number(n) => n.clone(),
+            _ => panic!("not a number"),
+        };
+        assert_ne!(n(0), n(1));
+        assert!(n(0) < n(1));
+        // the float reads as 18446744073709552000
+        assert_ne!(n(0), Number::from(18446744073709551616.0));
+        assert!(n(0) < Number::from(18446744073709551616.0));
+        assert_eq!(n(11), Number::from(18446744073709551616.0));
+        assert!(n(0) > Number::from(u64::MAX));
+        assert_eq!(n(2), n(3));
+        assert_eq!(n(2), Number::from(1u64));
+        assert_eq!(n(4), Number::from(0.1));
+        assert_eq!(n(5), Number::from(0u64));
+        assert!(n(5) > Number::from(-1e-300));
+        assert!(n(6) > n(1) && n(6) < Number::from(f64::INFINITY));
+        assert!(n(7) < Number::from(i64::MIN));
+        assert_eq!(n(8), Number::from(f64::NAN));
+        assert_eq!(n(9), Number::from(1e23));
+        assert_ne!(n(10), Number::from(1.2345678901234568e23));
+        assert!(n(10) < Number::from(1.2345678901234568e23));
+        // past 2^53 floats stand for their shortest digits as well
+        let float = Number::from((1u64 << 60) as f64);
+        assert_ne!(float, Number::from(1u64 << 60));
+        assert!(float > Number::from(1u64 << 60));
+        assert_eq!(
+            Number::from(1e19),
+            Number::from(10_000_000_000_000_000_000u64)
+        );
+
+        let unique: HashSet<_> = (0..12)
+            .map(n)
+            .chain([1u64.into(), 0.1.into(), 0u64.into(), f64::NAN.into()])
+            .chain([
+                1e23.into(),
+                1.2345678901234568e23.into(),
+                18446744073709551616.0.into(),
+            ])
+            .collect();
+        assert_eq!(unique.len(), 12);
+        let unique: HashSet<Number> = [1e19.into(), 10_000_000_000_000_000_000u64.into()]
+            .into_iter()
+            .chain([float, Number::from(1u64 << 60)])
+            .collect();
+        assert_eq!(unique.len(), 3);
+    }
+
+    // test the number accessors converting on demand
+    #[test]
+    fn test_number_accessors() {
+        let options = ParserOptions {
+            arbitrary_precision: true,
+            ..Default::default()
+        };
+        let JsonValue::List(values) =
+            &from_str_with_options("[-7, 2.5e-1, true]", options).unwrap()
+        else {
+            panic!("not a list")
+        };
+        assert_eq!(values[0].as_i64(), Some(-7));
+        assert_eq!(values[0].as_u64(), None);
+        assert_eq!(values[1].as_f64(), Some(0.25));
+        assert_eq!(values[1].as_i64(), None);
+        assert_eq!(values[2].as_f64(), None);
+    }
+
+    // test the non-finite numbers
+    #[test]
+    fn test_number_non_finite() {
+        let mut options = ParserOptions {
+            allow_nan: true,
+            ..Default::default()
+        };
+        let input = "[NaN, Infinity, -Infinity]";
+        let value = from_str_with_options(input, options).unwrap();
+        let JsonValue::List(values) = &value else {
+            panic!("not a list")
+        };
+        assert!(values[0].as_f64().unwrap().is_nan());
+        assert_eq!(values[1].as_f64(), Some(f64::INFINITY));
+        assert_eq!(values[2].as_f64(), Some(f64::NEG_INFINITY));
+
+        assert!(from_str(input).is_err());
+        options.strict = true;
+        assert!(from_str_with_options(input, options).is_err());
+    }
+
+    // test the number overflow handling
+    #[test]
+    fn test_number_overflow() {
+        let input = "[1, -1e400]";
+        let JsonValue::List(values) = &from_str(input).unwrap() else {
+            panic!("not a list")
+        };
+        assert_eq!(values[1].as_f64(), Some(f64::NEG_INFINITY));
+
+        let mut options = ParserOptions {
+            overflow: NumberOverflow::Error,
+            ..Default::default()
+        };
+        let result = from_str_with_options(input, options);
+        assert!(result.is_err());
+        let parser = number(options);
+        let state = State { current: 4 };
+        let result = parser.parse(input, state).unwrap_err();
+        assert_eq!(result, ParserError::InvalidNumber(4));
+
+        options.overflow = NumberOverflow::Raw;
+        let JsonValue::List(values) = &from_str_with_options(input, options).unwrap() else {
+            panic!("not a list")
+        };
+        let JsonValue::Number(n) = &values[1] else {
+            panic!("not a number")
+        };
+        assert_eq!(n.as_str(), Some("-1e400"));
+        assert_eq!(values[0], JsonValue::Number(1u64.into()));
+    }
+
+    // test the hexadecimal and binary numbers
     #[test]
-    fn test_pure() {
-        let parser = success("hello");
-        let input = "world";
+    fn test_number_radix() {
+        let mut options = ParserOptions {
+            radix_literals: true,
+            ..Default::default()
+        };
+        let input = "[0xFF, -0b1010, 0XffFFffFFffFFffFF, 12]";
+        let value = from_str_with_options(input, options).unwrap();
+        let JsonValue::List(values) = &value else {
+            panic!("not a list")
+        };
+        assert_eq!(values[0].as_u64(), Some(255));
+        assert_eq!(values[1].as_i64(), Some(-10));
+        assert_eq!(values[2].as_u64(), Some(u64::MAX));
+        assert_eq!(values[3].as_u64(), Some(12));
+
+        let parser = radix_number(options);
         let state = State { current: 0 };
-        let result = parser.parse(input, state).unwrap();
-        assert_eq!(result, ("hello", State { current: 0 }));
+        let result = parser.parse("-0x", state).unwrap_err();
+        assert_eq!(result, ParserError::InvalidNumber(0));
+        assert!(from_str_with_options("-0b2", options).is_err());
+        assert!(from_str(input).is_err());
+        options.strict = true;
+        assert!(from_str_with_options(input, options).is_err());
     }
 
-    // test the bind parser using the pure parser
+    // test the lossless integer accessors
     #[test]
-    fn test_bind() {
-        let parser = bind(pat("hello"), |s| success(s.to_uppercase()));
-        let input = "hello world";
-        let state = State { current: 0 };
-        let result = parser.parse(input, state).unwrap();
-        assert_eq!(result, ("HELLO".to_string(), State { current: 5 }));
+    fn test_number_integer_accessors() {
+        let value = from_str("[1e3, -2.0, 0.5, 1e300, 18446744073709551615, -1]").unwrap();
+        let JsonValue::List(values) = &value else {
+            panic!("not a list")
+        };
+        assert_eq!(
+            (values[0].as_u64(), values[0].as_i64()),
+            (Some(1000), Some(1000))
+        );
+        assert_eq!((values[1].as_u64(), values[1].as_i64()), (None, Some(-2)));
+        assert_eq!((values[2].as_u64(), values[2].as_i64()), (None, None));
+        assert_eq!((values[3].as_u64(), values[3].as_i64()), (None, None));
+        assert_eq!(
+            (values[4].as_u64(), values[4].as_i64()),
+            (Some(u64::MAX), None)
+        );
+        assert_eq!((values[5].as_u64(), values[5].as_i64()), (None, Some(-1)));
+        assert_eq!(JsonValue::Null.as_i64(), None);
     }
 
-    // test the string parser
+    // test the 128 bit integer accessors
     #[test]
-    fn test_string() {
-        let parser = string();
-        let input = "\"hello\"";
-        let state = State { current: 0 };
-        let result = parser.parse(input, state).unwrap();
-        assert_eq!(result, (JsonValue::String("hello"), State { current: 7 }));
+    fn test_number_128_bit_accessors() {
+        let input = "[340282366920938463463374607431768211455, -170141183460469231731687303715884105728, 1e3]";
+        let options = ParserOptions {
+            arbitrary_precision: true,
+            ..Default::default()
+        };
+        let JsonValue::List(values) = &from_str_with_options(input, options).unwrap() else {
+            panic!("not a list")
+        };
+        assert_eq!(values[0].as_u128(), Some(u128::MAX));
+        assert_eq!(values[1].as_i128(), Some(i128::MIN));
+        assert_eq!(values[1].as_u128(), None);
+        assert_eq!(values[2].as_i128(), Some(1000));
+
+        let JsonValue::List(values) = &from_str(input).unwrap() else {
+            panic!("not a list")
+        };
+        assert_eq!(values[0].as_u128(), None);
+        assert_eq!(values[2].as_u128(), Some(1000));
     }
 
-    // test the number parser
+    // test the number parser with exponents
     #[test]
-    fn test_number() {
-        let parser = number();
-        let input = "123";
-        let state = State { current: 0 };
-        let result = parser.parse(input, state).unwrap();
-        assert_eq!(result, (JsonValue::Number(123.0), State { current: 3 }));
-
-        let parser = number();
-        let input = "-123";
-        let state = State { current: 0 };
-        let result = parser.parse(input, state).unwrap();
-        assert_eq!(result, (JsonValue::Number(-123.0), State { current: 4 }));
+    fn test_number_exponent() {
+        for (input, expected) in [("1e10", 1e10), ("2.5E-3", 2.5e-3), ("6.02e+23", 6.02e23)] {
+            let parser = number(ParserOptions::default());
+            let state = State { current: 0 };
+            let result = parser.parse(input, state).unwrap();
+            let state = State {
+                current: input.len(),
+            };
+            assert_eq!(result, (JsonValue::Number(expected.into()), state));
+        }
 
-        let parser = number();
-        let input = "-00000000000001";
+        let parser = exponent_part_number();
+        let input = "e+";
         let state = State { current: 0 };
         let result = parser.parse(input, state).unwrap_err();
-        assert_eq!(result, ParserError::NoParse(0));
+        assert_eq!(result, ParserError::InvalidNumber(0));
+        assert_eq!(from_str("1e"), Err(ParserError::InvalidNumber(1)));
     }
 
     // test the pure fail parser
@@ -419,7 +3204,7 @@ mod tests {
         let input = "-00000000000001";
         let state = State { current: 0 };
         let result = parser.parse(input, state).unwrap_err();
-        assert_eq!(result, ParserError::NoParse(0));
+        assert_eq!(result, ParserError::InvalidNumber(0));
     }
 
     // test the decimal part number parser
@@ -436,6 +3221,12 @@ mod tests {
         let state = State { current: 0 };
         let result = parser.parse(input, state).unwrap();
         assert_eq!(result, (".00000000000001", State { current: 15 }));
+
+        let parser = decimal_part_number();
+        let input = ".e1";
+        let state = State { current: 0 };
+        let result = parser.parse(input, state).unwrap_err();
+        assert_eq!(result, ParserError::InvalidNumber(0));
     }
 
     // test the boolean parser
@@ -467,41 +3258,47 @@ mod tests {
     // test the json value parser
     #[test]
     fn test_json_value() {
-        let parser = json_value();
+        let parser = json_value(ParserOptions::default());
         let input = "\"hello\"";
         let state = State { current: 0 };
         let result = parser.parse(input, state).unwrap();
-        assert_eq!(result, (JsonValue::String("hello"), State { current: 7 }));
+        assert_eq!(
+            result,
+            (JsonValue::String("hello".into()), State { current: 7 })
+        );
 
-        let parser = json_value();
+        let parser = json_value(ParserOptions::default());
         let input = "123";
         let state = State { current: 0 };
         let result = parser.parse(input, state).unwrap();
-        assert_eq!(result, (JsonValue::Number(123.0), State { current: 3 }));
+        assert_eq!(
+            result,
+            (JsonValue::Number(123u64.into()), State { current: 3 })
+        );
 
-        let parser = json_value();
+        let parser = json_value(ParserOptions::default());
         let input = "true";
         let state = State { current: 0 };
         let result = parser.parse(input, state).unwrap();
         assert_eq!(result, (JsonValue::Boolean(true), State { current: 4 }));
 
-        let parser = json_value();
+        let parser = json_value(ParserOptions::default());
         let input = "null";
         let state = State { current: 0 };
         let result = parser.parse(input, state).unwrap();
         assert_eq!(result, (JsonValue::Null, State { current: 4 }));
 
-        let parser = json_value();
+        let parser = json_value(ParserOptions::default());
         let input = "[1, 2, 3]";
         let state = State { current: 0 };
         let result = parser.parse(input, state).unwrap();
         assert_eq!(
             result,
             (
-                JsonValue::List(std::rc::Rc::new(vec![
-                    JsonValue::Number(1.0),
-                    JsonValue::Number(2.0),
-                    JsonValue::Number(3.0)
+                JsonValue::List(Shared::new(vec![
+                    JsonValue::Number(1u64.into()),
+                    JsonValue::Number(2u64.into()),
+                    JsonValue::Number(3u64.into())
                 ])),
                 State {
                     current: input.len()
@@ -509,18 +3306,758 @@ mod tests {
             )
         );
 
-        let parser = json_value();
+        let parser = json_value(ParserOptions::default());
         let input = "{\"key\": \"value\"}";
         let state = State { current: 0 };
         let result = parser.parse(input, state).unwrap();
         assert_eq!(
             result,
             (
-                JsonValue::Object(std::rc::Rc::new(vec![("key", JsonValue::String("value"))])),
+                JsonValue::Object(Shared::new(vec![(
+                    "key".into(),
+                    JsonValue::String("value".into())
+                )])),
                 State {
                     current: input.len()
                 }
             )
         );
     }
+
+    // test the whitespace around the top-level value
+    #[test]
+    fn test_document_whitespace() {
+        assert_eq!(
+            from_str("  {\"a\": 1}\n"),
+            Ok(JsonValue::Object(Shared::new(vec![(
+                "a".into(),
+                JsonValue::Number(1u64.into())
+            )])))
+        );
+        assert_eq!(
+            from_str("\t\"text\" \r\n"),
+            Ok(JsonValue::String("text".into()))
+        );
+        assert_eq!(from_str(" 1 2 "), Err(ParserError::TrailingCharacters(3)));
+    }
+
+    // test the empty containers
+    #[test]
+    fn test_empty_containers() {
+        assert_eq!(from_str("{}"), Ok(JsonValue::Object(Shared::new(vec![]))));
+        assert_eq!(
+            from_str("[ { }, [\n] ]"),
+            Ok(JsonValue::List(Shared::new(vec![
+                JsonValue::Object(Shared::new(vec![])),
+                JsonValue::List(Shared::new(vec![])),
+            ])))
+        );
+        assert!(from_str("[1,]").is_err());
+        assert!(from_str("{,}").is_err());
+    }
+
+    // test the whitespace in strict mode
+    #[test]
+    fn test_whitespace_strict() {
+        let input = "\u{a0}[1,\u{3000}2]";
+        assert!(from_str(input).is_ok());
+        let options = ParserOptions {
+            strict: true,
+            ..Default::default()
+        };
+        assert!(from_str_with_options(input, options).is_err());
+        assert!(from_str_with_options("[1,\u{3000}2]", options).is_err());
+        assert!(from_str_with_options(" \t[1,\r\n2] \n", options).is_ok());
+    }
+
+    // test the deeply nested documents
+    #[test]
+    fn test_deep_nesting() {
+        let depth = 20_000;
+        let input = format!("{}null{}", "[{\"a\":".repeat(depth), "}]".repeat(depth));
+        let value = from_str(&input).unwrap();
+        let mut current = &value;
+        for _ in 0..depth {
+            let JsonValue::List(values) = current else {
+                panic!("not a list")
+            };
+            let JsonValue::Object(pairs) = &values[0] else {
+                panic!("not an object")
+            };
+            current = &pairs[0].1;
+        }
+        assert_eq!(current, &JsonValue::Null);
+        value.drop_iteratively();
+    }
+
+    // test the lenient extensions
+    #[test]
+    fn test_options_lenient() {
+        let input = "{ // comment\n 'a': [1, 0x2, NaN, /* more */ ], \"b\": \"\\x\", }";
+        let value = from_str_with_options(input, ParserOptions::lenient()).unwrap();
+        let JsonValue::Object(pairs) = &value else {
+            panic!("not an object")
+        };
+        assert_eq!(pairs.len(), 2);
+        assert!(matches!(&pairs[0].1, JsonValue::List(values) if values.len() == 3));
+        assert_eq!(pairs[1].1.as_str(), Some("\u{fffd}"));
+        assert!(from_str(input).is_err());
+        assert!(from_str_with_options(input, ParserOptions::strict()).is_err());
+        assert!(from_str_with_options("[1,,]", ParserOptions::lenient()).is_err());
+        assert!(from_str_with_options("[/* open", ParserOptions::lenient()).is_err());
+    }
+
+    // test the depth limit
+    #[test]
+    fn test_options_max_depth() {
+        let options = ParserOptions {
+            max_depth: Some(2),
+            ..Default::default()
+        };
+        assert!(from_str_with_options("[{\"a\": 1}, []]", options).is_ok());
+        assert_eq!(
+            from_str_with_options("[{\"a\": []}]", options),
+            Err(ParserError::Context(
+                vec![Label::Member("a".into()), Label::Element(0)],
+                Box::new(ParserError::DepthLimit(7))
+            ))
+        );
+    }
+
+    // test the duplicate keys policies
+    #[test]
+    fn test_options_duplicate_keys() {
+        let input = "{\"a\": 1, \"b\": 2, \"a\": 3}";
+        let members = |duplicate_keys| {
+            let options = ParserOptions {
+                duplicate_keys,
+                ..Default::default()
+            };
+            from_str_with_options(input, options).map(|value| match &value {
+                JsonValue::Object(pairs) => pairs
+                    .iter()
+                    .map(|(key, value)| (key.to_string(), value.as_u64().unwrap()))
+                    .collect::<Vec<_>>(),
+                _ => panic!("not an object"),
+            })
+        };
+        let owned = |pairs: &[(&str, u64)]| {
+            pairs
+                .iter()
+                .map(|&(key, value)| (key.to_string(), value))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            members(DuplicateKeys::Keep),
+            Ok(owned(&[("a", 1), ("b", 2), ("a", 3)]))
+        );
+        assert_eq!(
+            members(DuplicateKeys::First),
+            Ok(owned(&[("a", 1), ("b", 2)]))
+        );
+        assert_eq!(
+            members(DuplicateKeys::Last),
+            Ok(owned(&[("a", 3), ("b", 2)]))
+        );
+        assert_eq!(
+            members(DuplicateKeys::Error).unwrap_err().root(),
+            &ParserError::DuplicateKey(17)
+        );
+
+        // past the size where keys get indexed
+        let keys: Vec<_> = (0..20).map(|n| format!("\"{}\": {n}", n % 15)).collect();
+        let input = format!("{{{}}}", keys.join(", "));
+        let parse = |duplicate_keys| {
+            let options = ParserOptions {
+                duplicate_keys,
+                ..Default::default()
+            };
+            from_str_with_options(&input, options).map(|value| value.to_string())
+        };
+        let first: Vec<_> = (0..15).map(|n| format!("\"{n}\":{n}")).collect();
+        assert_eq!(
+            parse(DuplicateKeys::First),
+            Ok(format!("{{{}}}", first.join(",")))
+        );
+        let last: Vec<_> = (0..15)
+            .map(|n| format!("\"{n}\":{}", if n < 5 { n + 15 } else { n }))
+            .collect();
+        assert_eq!(
+            parse(DuplicateKeys::Last),
+            Ok(format!("{{{}}}", last.join(",")))
+        );
+        let position = input.find("\"0\": 15").unwrap();
+        assert_eq!(
+            parse(DuplicateKeys::Error).unwrap_err().root(),
+            &ParserError::DuplicateKey(position)
+        );
+    }
+
+    // test the error line and column
+    #[test]
+    fn test_error_line_column() {
+        let input = "{\n  \"a\": [1, 2],\n  \"é\": tru\n}";
+        let error = from_str(input).unwrap_err();
+        assert_eq!(error.line_column(input), (3, 8));
+        assert_eq!(ParserError::NoParse(0).line_column(""), (1, 1));
+        assert_eq!(ParserError::NoParse(3).line_column("[1,"), (1, 4));
+    }
+
+    // test the error variants
+    #[test]
+    fn test_error_variants() {
+        assert_eq!(
+            from_str(""),
+            Err(ParserError::UnexpectedEof(0, Expected::VALUE))
+        );
+        let root = |input| from_str(input).unwrap_err().root().clone();
+        assert_eq!(
+            root("[1, 2"),
+            ParserError::UnexpectedEof(5, Expected::COMMA | Expected::CLOSE_BRACKET)
+        );
+        assert_eq!(root("{\"a\": \"b"), ParserError::UnterminatedString(6));
+        assert_eq!(root("[01]"), ParserError::InvalidNumber(1));
+        assert_eq!(root("[-]"), ParserError::InvalidNumber(1));
+        assert_eq!(from_str("[1] x"), Err(ParserError::TrailingCharacters(4)));
+    }
+
+    // test the expected tokens
+    #[test]
+    fn test_error_expected() {
+        let error = |input| from_str(input).unwrap_err().root().to_string();
+        assert_eq!(
+            error("[tru]"),
+            "Unexpected character at position 1, expected a value or `]`"
+        );
+        assert_eq!(
+            error("[1, ]"),
+            "Unexpected character at position 4, expected a value"
+        );
+        assert_eq!(
+            error("{\"a\" 1}"),
+            "Unexpected character at position 5, expected `:`"
+        );
+        assert_eq!(
+            error("{\"a\": 1 \"b\": 2}"),
+            "Unexpected character at position 8, expected `,` or `}`"
+        );
+        assert_eq!(
+            error("{1: 2}"),
+            "Unexpected character at position 1, expected a string or `}`"
+        );
+        assert_eq!(
+            error("{\"a\": 1,"),
+            "Unexpected end of input at position 8, expected a string"
+        );
+    }
+
+    // test the error path
+    #[test]
+    fn test_error_path() {
+        let path = |input| format_path(&from_str(input).unwrap_err().path());
+        assert_eq!(
+            path("{\"items\": [{}, {\"price\": 1.}]}"),
+            "$.items[1].price"
+        );
+        assert_eq!(path("[1, 2, [3, 4"), "$[2][2]");
+        assert_eq!(path("[{\"a b\": {\"c\": 1, 2}}]"), "$[0][\"a b\"]");
+        assert_eq!(path("[1 2]"), "$[1]");
+        assert_eq!(path(""), "$");
+    }
+
+    // test the error rendering
+    #[test]
+    fn test_error_render() {
+        let input = "{\n  \"a\": [1, 2],\n  \"é\": tru\n}";
+        assert_eq!(
+            from_str(input).unwrap_err().render(input),
+            "error: in object member \"é\": Unexpected character at position 25, expected a value\n \
+             --> line 3, column 8\n  \
+             |\n\
+             3 |   \"é\": tru\n  \
+             |        ^\n"
+        );
+        assert_eq!(
+            ParserError::UnexpectedEof(3, Expected::VALUE).render("[1,"),
+            "error: Unexpected end of input at position 3, expected a value\n \
+             --> line 1, column 4\n  |\n1 | [1,\n  |    ^\n"
+        );
+    }
+
+    // test the error codes and values
+    #[test]
+    fn test_error_to_value() {
+        assert_eq!(
+            from_str("\"open").unwrap_err().code(),
+            "E_UNTERMINATED_STRING"
+        );
+        let error = from_str("[1 2]").unwrap_err();
+        assert_eq!(error.code(), "E_UNEXPECTED_CHARACTER");
+        let value = error.to_value();
+        let JsonValue::Object(pairs) = &value else {
+            panic!("not an object")
+        };
+        let keys: Vec<_> = pairs.iter().map(|(key, _)| key.as_ref()).collect();
+        assert_eq!(
+            keys,
+            vec!["code", "message", "position", "expected", "labels"]
+        );
+        assert_eq!(pairs[0].1.as_str(), Some("E_UNEXPECTED_CHARACTER"));
+        assert_eq!(pairs[1].1.as_str(), Some(error.to_string().as_str()));
+        assert_eq!(pairs[2].1.as_u64(), Some(3));
+        assert_eq!(pairs[3].1, from_str("[\",\", \"]\"]").unwrap());
+        assert_eq!(pairs[4].1, from_str("[\"array element 1\"]").unwrap());
+
+        let error = error.root().clone();
+        assert_eq!(error.code(), "E_UNEXPECTED_CHARACTER");
+        let JsonValue::Object(pairs) = &error.to_value() else {
+            panic!("not an object")
+        };
+        assert_eq!(pairs.len(), 4);
+    }
+
+    // test the value accessors
+    #[test]
+    fn test_accessors() {
+        let value = from_str("{\"a\": [true, null, 1.5, \"s\"]}").unwrap();
+        let pairs = value.as_object().unwrap();
+        assert_eq!(pairs[0].0, "a");
+        assert!(value.is_object() && !value.is_array());
+        let values = pairs[0].1.as_array().unwrap();
+        assert_eq!(values[0].as_bool(), Some(true));
+        assert!(values[1].is_null());
+        assert!(values[2].is_number());
+        assert_eq!(values[2].as_number(), Some(&Number::from(1.5)));
+        assert!(values[3].is_string() && !values[3].is_bool());
+        assert_eq!(values[3].as_bool(), None);
+        assert_eq!(values[3].as_array(), None);
+        assert_eq!(value.as_str(), None);
+    }
+
+    // test the value lookups
+    #[test]
+    fn test_get() {
+        let value = from_str("{\"a\": [1, {\"b\": 2}], \"a\": 3, \"c\\u0064\": 4}").unwrap();
+        let list = value.get("a").unwrap();
+        assert_eq!(list.get_index(0).and_then(JsonValue::as_u64), Some(1));
+        let b = list.get(1).and_then(|value| value.get("b"));
+        assert_eq!(b.and_then(JsonValue::as_u64), Some(2));
+        assert_eq!(value.get("cd").and_then(JsonValue::as_u64), Some(4));
+        assert_eq!(value.get(0), None);
+        assert_eq!(list.get("b"), None);
+        assert_eq!(list.get_index(2), None);
+        assert_eq!(JsonValue::Null.get("a"), None);
+    }
+
+    // test the value indexing
+    #[test]
+    fn test_index() {
+        let value = from_str("{\"user\": {\"emails\": [\"a@b.c\"]}}").unwrap();
+        assert_eq!(value["user"]["emails"][0].as_str(), Some("a@b.c"));
+        assert!(value["user"]["emails"][1].is_null());
+        assert!(value["user"]["phone"][0]["x"].is_null());
+        assert!(value[0].is_null());
+    }
+
+    // test the container lengths
+    #[test]
+    fn test_len() {
+        let value = from_str("{\"a\": [1, 2, 3], \"b\": {}, \"c\": \"text\"}").unwrap();
+        assert_eq!(value.len(), 3);
+        assert_eq!(value["a"].len(), 3);
+        assert!(value["b"].is_empty());
+        assert_eq!(value["c"].len(), 0);
+        assert!(value["c"].is_empty());
+    }
+
+    // test the owned values
+    #[test]
+    fn test_into_owned() {
+        fn parse(input: String) -> JsonValue<'static> {
+            let options = ParserOptions {
+                arbitrary_precision: true,
+                ..Default::default()
+            };
+            from_str_with_options(&input, options).unwrap().into_owned()
+        }
+        let value = parse("{\"a\": [\"b\", 12345678901234567890123], \"c\\n\": null}".to_string());
+        assert_eq!(value["a"][0].as_str(), Some("b"));
+        assert_eq!(
+            value["a"][1].as_number().and_then(Number::as_str),
+            Some("12345678901234567890123")
+        );
+        assert!(value.get("c\n").is_some_and(JsonValue::is_null));
+
+        let input = "[[\"shared\"]]".to_string();
+        let value = from_str(&input).unwrap();
+        let shared = value.clone();
+        assert_eq!(value.into_owned(), shared);
+
+        let depth = 100_000;
+        let input = format!("{}{{\"k\": \"v\"}}{}", "[".repeat(depth), "]".repeat(depth));
+        let value: Value = input.parse().unwrap();
+        drop(input);
+        assert_eq!(
+            value.pointer(&"/0".repeat(depth)).unwrap()["k"].as_str(),
+            Some("v")
+        );
+        value.drop_iteratively();
+    }
+
+    // test the values crossing threads
+    #[cfg(feature = "sync")]
+    #[test]
+    fn test_sync() {
+        let value = from_str("{\"a\": [1, 2]}").unwrap().into_owned();
+        let shared = value.clone();
+        let len = std::thread::spawn(move || shared["a"].len())
+            .join()
+            .unwrap();
+        assert_eq!(len, value["a"].len());
+    }
+
+    // test the value editing
+    #[test]
+    fn test_edit() {
+        let original = from_str("{\"a\": [1], \"b\": 2, \"b\": 3}").unwrap();
+        let mut value = original.clone();
+        assert_eq!(value.insert("c", JsonValue::Null), None);
+        assert_eq!(
+            value.insert("b", JsonValue::Boolean(true)),
+            Some(from_str("2").unwrap())
+        );
+        assert_eq!(value.remove("b"), Some(JsonValue::Boolean(true)));
+        assert_eq!(value.get("b"), None);
+        assert_eq!(value.remove("b"), None);
+
+        let list = value.as_object_mut().unwrap()[0].1.as_array_mut().unwrap();
+        list.push(JsonValue::Null);
+        assert_eq!(value["a"].len(), 2);
+        let mut list = value.remove("a").unwrap();
+        assert_eq!(list.pop(), Some(JsonValue::Null));
+        list.push(JsonValue::Boolean(false));
+        assert_eq!(list, from_str("[1, false]").unwrap());
+        assert_eq!(value, from_str("{\"c\": null}").unwrap());
+        // the original is left alone
+        assert_eq!(original.len(), 3);
+        assert_eq!(original["a"].len(), 1);
+        assert_eq!(JsonValue::Null.pop(), None);
+    }
+
+    // test the conversions into values
+    #[test]
+    fn test_from() {
+        let value = JsonValue::from(vec![
+            (
+                "a",
+                JsonValue::from(vec![
+                    JsonValue::from(1),
+                    JsonValue::from(2.5),
+                    JsonValue::from("x"),
+                ]),
+            ),
+            ("b", true.into()),
+            ("c", None::<bool>.into()),
+            ("d", Some(u64::MAX).into()),
+            ("e", String::from("y").into()),
+        ]);
+        assert_eq!(
+            value,
+            from_str("{\"a\": [1, 2.5, \"x\"], \"b\": true, \"c\": null, \"d\": 18446744073709551615, \"e\": \"y\"}")
+                .unwrap()
+        );
+        assert_eq!(JsonValue::from(-1i64).as_i64(), Some(-1));
+    }
+
+    // test the conversions out of values
+    #[test]
+    fn test_try_from() {
+        let value =
+            from_str("{\"a\": 1, \"b\": -2.5, \"c\": \"x\", \"d\": true, \"e\": null}").unwrap();
+        assert_eq!(value["a"].try_as::<i64>(), Ok(1));
+        assert_eq!(u64::try_from(&value["a"]), Ok(1));
+        assert_eq!(value["b"].try_as::<f64>(), Ok(-2.5));
+        assert_eq!(value["c"].try_as::<&str>(), Ok("x"));
+        assert_eq!(value["d"].try_as::<bool>(), Ok(true));
+        assert_eq!(value["e"].try_as::<Option<bool>>(), Ok(None));
+        assert_eq!(value["a"].try_as::<Option<i64>>(), Ok(Some(1)));
+        let error = value["b"].try_as::<i64>().unwrap_err();
+        assert_eq!(error.to_string(), "Expected i64, found number");
+        assert_eq!(
+            value["c"].try_as::<Option<bool>>(),
+            Err(TypeError {
+                expected: "boolean",
+                found: "string"
+            })
+        );
+        assert_eq!(value.try_as::<f64>().unwrap_err().found, "object");
+    }
+    // test the compact JSON display
+    #[test]
+    fn test_display() {
+        let input = "{\"a\": [1, -2, 2.5, 1e300, \"x\\\"\\n\"], \"b\": {}, \"c\": [[]], \"d\": true, \"e\": null}";
+        let value = from_str(input).unwrap();
+        let text = value.to_string();
+        assert_eq!(
+            text,
+            "{\"a\":[1,-2,2.5,1e300,\"x\\\"\\n\"],\"b\":{},\"c\":[[]],\"d\":true,\"e\":null}"
+        );
+        assert_eq!(from_str(&text).unwrap(), value);
+        assert_eq!(JsonValue::from(1.0).to_string(), "1.0");
+        assert_eq!(JsonValue::from(f64::NAN).to_string(), "null");
+        let options = ParserOptions {
+            arbitrary_precision: true,
+            ..Default::default()
+        };
+        let value = from_str_with_options("[1.50, 123456789012345678901234567890]", options);
+        assert_eq!(
+            value.unwrap().to_string(),
+            "[1.50,123456789012345678901234567890]"
+        );
+        let deep = "[".repeat(100_000) + &"]".repeat(100_000);
+        let value = from_str(&deep).unwrap();
+        assert_eq!(value.to_string(), deep);
+        value.drop_iteratively();
+    }
+
+    // test the parsing through str::parse
+    #[test]
+    fn test_parse() {
+        let value: Value = String::from("{\"a\": [1, \"x\"]}").parse().unwrap();
+        assert_eq!(value, from_str("{\"a\": [1, \"x\"]}").unwrap());
+        assert_eq!(
+            "[1,".parse::<Value>(),
+            Err(ParserError::Context(
+                vec![Label::Element(1)],
+                Box::new(ParserError::UnexpectedEof(3, Expected::VALUE))
+            ))
+        );
+    }
+
+    // test the values as hash keys
+    #[test]
+    fn test_hash() {
+        use std::collections::HashSet;
+
+        let options = ParserOptions {
+            allow_nan: true,
+            ..Default::default()
+        };
+        let values = from_str_with_options(
+            "[1, 1.0, 1e0, -0, 0, 0.5, NaN, NaN, \"1\", {\"a\": [1]}, {\"a\": [1.0]}]",
+            options,
+        )
+        .unwrap();
+        let unique: HashSet<_> = values.as_array().unwrap().iter().collect();
+        assert_eq!(unique.len(), 6);
+        assert!(unique.contains(&JsonValue::from(0.0)));
+        assert!(unique.contains(&from_str("{\"a\": [1]}").unwrap()));
+        assert!(!unique.contains(&JsonValue::from(2)));
+
+        let deep = |depth: usize, last: &str| {
+            let input = format!("{}{last}{}", "[".repeat(depth), "]".repeat(depth));
+            from_str(&input).unwrap().into_owned()
+        };
+        let (a, b) = (deep(100_000, "1"), deep(100_000, "1.0"));
+        assert_eq!(a, b);
+        for other in [deep(100_000, "2"), deep(99_999, "1")] {
+            assert_ne!(a, other);
+            other.drop_iteratively();
+        }
+        let unique: HashSet<_> = [&a, &b].into_iter().collect();
+        assert_eq!(unique.len(), 1);
+        a.drop_iteratively();
+        b.drop_iteratively();
+    }
+
+    // test the total order of values
+    #[test]
+    fn test_ord() {
+        let options = ParserOptions {
+            allow_nan: true,
+            ..Default::default()
+        };
+        let input = "[{\"a\": 1}, [1, 2], \"b\", NaN, 1e300, 18446744073709551615, 2.5, 2, 1.0, -0, -1.5, -9223372036854775808, -Infinity, true, false, null, [1], \"a\", {}]";
+        let mut values = from_str_with_options(input, options)
+            .unwrap()
+            .as_array()
+            .unwrap()
+            .to_vec();
+        values.sort();
+        assert_eq!(
+            JsonValue::from(values).to_string(),
+            "[null,false,true,null,-9223372036854775808,-1.5,-0.0,1.0,2,2.5,18446744073709551615,1e300,null,\"a\",\"b\",[1],[1,2],{},{\"a\":1}]"
+        );
+        assert!(JsonValue::from(18446744073709551615u64) < JsonValue::from(18446744073709551616.0));
+        assert!(JsonValue::from(0.5) > JsonValue::from(0));
+        assert_eq!(
+            JsonValue::from(-0.0).cmp(&JsonValue::from(0)),
+            std::cmp::Ordering::Equal
+        );
+    }
+
+    // test the values too deep to recurse into
+    #[test]
+    fn test_deep_values() {
+        let depth = 100_000;
+        let input = format!(
+            "{}{{\"a\": [1]}}{}",
+            "[{\"a\": ".repeat(depth),
+            "}]".repeat(depth)
+        );
+        let value = from_str(&input).unwrap();
+        let owned = value.clone().into_owned();
+        assert_eq!(owned, value);
+        let parsed = input.parse::<Value>().unwrap();
+        assert_eq!(parsed, value);
+        assert_eq!(owned.cmp(&value), std::cmp::Ordering::Equal);
+        let greater = input.replacen("[1]", "[2]", 1);
+        let greater = from_str(&greater).unwrap();
+        assert!(value < greater);
+
+        let document = Document::from_value(&value).unwrap();
+        let from_document = document.to_value();
+        assert_eq!(from_document, value);
+        assert_eq!(value.to_string().len(), input.len() - depth - 1);
+
+        #[cfg(feature = "serde_json")]
+        {
+            let converted = Value::from(::serde_json::Value::try_from(&value).unwrap());
+            assert_eq!(converted, value);
+            converted.drop_iteratively();
+        }
+
+        let invalid = input.replacen("[1]", "[1,]", 1);
+        let error = from_str(&invalid).unwrap_err();
+        assert_eq!(error.position(), input.find("[1]").unwrap() + 3);
+        assert!(!parse_cst(&invalid).errors().is_empty());
+        assert_eq!(error.path().len(), 2 * depth + 2);
+
+        let repeated = format!("{{\"a\": {input}, \"a\": 1}}");
+        for duplicate_keys in [
+            DuplicateKeys::First,
+            DuplicateKeys::Last,
+            DuplicateKeys::Error,
+        ] {
+            let options = ParserOptions {
+                duplicate_keys,
+                ..Default::default()
+            };
+            if let Ok(value) = from_str_with_options(&repeated, options) {
+                value.drop_iteratively();
+            }
+        }
+
+        for value in [value, owned, parsed, greater, from_document] {
+            value.drop_iteratively();
+        }
+    }
+
+    // test the merging of values
+    #[test]
+    fn test_merge() {
+        let base = from_str("{\"a\": {\"b\": 1, \"c\": [1, 2]}, \"d\": [1], \"e\": 1}").unwrap();
+        let overlay =
+            from_str("{\"a\": {\"c\": [2, 3], \"f\": true}, \"d\": null, \"g\": {}}").unwrap();
+        for (strategy, expected) in [
+            (
+                MergeStrategy::Replace,
+                "{\"a\":{\"b\":1,\"c\":[2,3],\"f\":true},\"d\":null,\"e\":1,\"g\":{}}",
+            ),
+            (
+                MergeStrategy::Concatenate,
+                "{\"a\":{\"b\":1,\"c\":[1,2,2,3],\"f\":true},\"d\":null,\"e\":1,\"g\":{}}",
+            ),
+            (
+                MergeStrategy::Union,
+                "{\"a\":{\"b\":1,\"c\":[1,2,3],\"f\":true},\"d\":null,\"e\":1,\"g\":{}}",
+            ),
+        ] {
+            let mut value = base.clone();
+            value.merge(&overlay, strategy);
+            assert_eq!(value.to_string(), expected);
+        }
+        assert_eq!(base["a"]["c"].len(), 2);
+
+        // a repeated key merges into the same member in turn
+        let mut value = from_str("{\"a\": {\"b\": [1]}}").unwrap();
+        let overlay =
+            from_str("{\"a\": {\"b\": [1, 2, 2]}, \"c\": 1, \"a\": {\"b\": [3, 1]}, \"c\": {}}")
+                .unwrap();
+        value.merge(&overlay, MergeStrategy::Union);
+        assert_eq!(value.to_string(), "{\"a\":{\"b\":[1,2,3]},\"c\":{}}");
+
+        let depth = 100_000;
+        let deep = |inner: &str| {
+            let input = format!("{}{inner}{}", "{\"a\": ".repeat(depth), "}".repeat(depth));
+            from_str(&input).unwrap().into_owned()
+        };
+        let mut value = deep("{\"b\": [1]}");
+        let overlay = deep("{\"b\": [2, 1], \"c\": 3}");
+        value.merge(&overlay, MergeStrategy::Union);
+        let expected = deep("{\"b\": [1, 2], \"c\": 3}");
+        assert_eq!(value, expected);
+        for value in [value, overlay, expected] {
+            value.drop_iteratively();
+        }
+    }
+
+    // test the JSON pointer lookups
+    #[test]
+    fn test_pointer() {
+        let mut value = from_str(
+            "{\"users\": [{\"name\": \"a\"}, {\"name\": \"b\"}], \"a/b\": 1, \"m~n\": 2, \"\": 3}",
+        )
+        .unwrap();
+        assert_eq!(value.pointer(""), Some(&value));
+        assert_eq!(value.pointer("/users/1/name"), Some(&JsonValue::from("b")));
+        assert_eq!(value.pointer("/a~1b"), Some(&JsonValue::from(1)));
+        assert_eq!(value.pointer("/m~0n"), Some(&JsonValue::from(2)));
+        assert_eq!(value.pointer("/"), Some(&JsonValue::from(3)));
+        for missing in [
+            "users",
+            "/users/01",
+            "/users/+1",
+            "/users/2",
+            "/users/0/name/x",
+        ] {
+            assert_eq!(value.pointer(missing), None, "{missing}");
+        }
+        let copy = value.clone();
+        *value.pointer_mut("/users/0/name").unwrap() = "c".into();
+        assert_eq!(value["users"][0]["name"], "c".into());
+        assert_eq!(copy["users"][0]["name"], "a".into());
+        assert_eq!(value.pointer_mut("/users/x"), None);
+    }
+
+    // test the moving of values out of others
+    #[test]
+    fn test_take() {
+        let mut value = from_str("{\"a\": [1, 2], \"b\": true}").unwrap();
+        let a = value.pointer_mut("/a").unwrap().take();
+        assert_eq!(a, from_str("[1, 2]").unwrap());
+        let b = value["b"].clone();
+        assert_eq!(value.pointer_mut("/b").unwrap().replace(a), b);
+        assert_eq!(value.to_string(), "{\"a\":null,\"b\":[1,2]}");
+        assert_eq!(
+            std::mem::take(&mut value),
+            from_str("{\"a\": null, \"b\": [1, 2]}").unwrap()
+        );
+        assert!(value.is_null());
+    }
+
+    // test the conversions between objects and maps
+    #[test]
+    fn test_map() {
+        use std::collections::{BTreeMap, HashMap};
+
+        let value = from_str("{\"b\": 1, \"a\": [2], \"b\": 3}").unwrap();
+        let map: BTreeMap<String, JsonValue> = value.clone().into_map().unwrap();
+        assert_eq!(map.keys().collect::<Vec<_>>(), ["a", "b"]);
+        assert_eq!(map["b"], JsonValue::from(1));
+        assert_eq!(JsonValue::from_map(map).to_string(), "{\"a\":[2],\"b\":1}");
+        let map: HashMap<String, JsonValue> = value.into_map().unwrap();
+        assert_eq!(map["a"], from_str("[2]").unwrap());
+        assert_eq!(JsonValue::from(1).into_map::<HashMap<_, _>>(), None);
+    }
 }
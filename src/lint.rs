@@ -0,0 +1,520 @@
+use std::borrow::Cow;
+
+use crate::{
+    bind, boolean, checked_string, cut, expect, fail, from_str_with_options, member_key, null,
+    number, or, pat_ws, scalar, string_span, success, whitespace, DuplicateKeys, Expected,
+    JsonValue, Parser, ParserError, ParserOptions, State,
+};
+
+enum Container<'input> {
+    List,
+    /// The keys so far, kept only to find duplicates.
+    Object(Vec<Cow<'input, str>>),
+}
+
+enum Next {
+    Value,
+    Key,
+    /// A comma, a closing or the end of the input.
+    AfterValue,
+}
+
+/// Checks the document the way `from_str_with_options` parses it, but rather
+/// than stopping at the first problem, skips ahead to the next `,`, `]` or `}`
+/// and carries on, so that a single pass reports every error there is. A
+/// valid document gives no errors. Errors past the first one are a best guess,
+/// the skipping can't always tell where a broken value was meant to end.
+pub fn collect_errors(input: &str, options: ParserOptions) -> Vec<ParserError> {
+    let scalar = scalar(options);
+    let trailing_commas = options.trailing_commas && !options.strict;
+    let first_key = member_key(options, Expected::STRING | Expected::CLOSE_BRACE);
+    let next_key = member_key(
+        options,
+        match trailing_commas {
+            true => Expected::STRING | Expected::CLOSE_BRACE,
+            false => Expected::STRING,
+        },
+    );
+    let mut errors = Vec::new();
+    let mut error = |error| {
+        errors.push(match error {
+            ParserError::Unexpected(position, expected) if position == input.len() => {
+                ParserError::UnexpectedEof(position, expected)
+            }
+            error => error,
+        })
+    };
+    let mut stack: Vec<Container> = Vec::new();
+    let mut next = Next::Value;
+    // whether the container was just opened, where it may as well close
+    let mut first = false;
+    let mut position = 0;
+    loop {
+        position = skip_whitespace(input, position, options);
+        let rest = &input[position..];
+        let state = State { current: position };
+        match next {
+            Next::Value => {
+                let closing = match stack.last() {
+                    Some(Container::List) if first || trailing_commas => Expected::CLOSE_BRACKET,
+                    _ => Expected::default(),
+                };
+                let opening = rest.starts_with(['[', '{']);
+                if opening && options.max_depth.is_some_and(|max| stack.len() >= max) {
+                    error(ParserError::DepthLimit(position));
+                }
+                if rest.starts_with('[') {
+                    stack.push(Container::List);
+                    first = true;
+                    position += 1;
+                    next = match skip_whitespace(input, position, options) {
+                        end if input[end..].starts_with(']') => Next::AfterValue,
+                        _ => Next::Value,
+                    };
+                } else if rest.starts_with('{') {
+                    stack.push(Container::Object(Vec::new()));
+                    first = true;
+                    position += 1;
+                    next = match skip_whitespace(input, position, options) {
+                        end if input[end..].starts_with('}') => Next::AfterValue,
+                        _ => Next::Key,
+                    };
+                } else {
+                    first = false;
+                    match scalar.parse(input, state) {
+                        Ok((_, state)) => position = state.current,
+                        Err(ParserError::NoParse(at)) => {
+                            error(ParserError::Unexpected(at, Expected::VALUE | closing));
+                            position = resync(input, position, options);
+                            if position == input.len() {
+                                return errors;
+                            }
+                        }
+                        Err(other) => {
+                            error(other);
+                            position = resync(input, position, options);
+                            if position == input.len() {
+                                return errors;
+                            }
+                        }
+                    }
+                    next = Next::AfterValue;
+                }
+            }
+            Next::Key => {
+                let key = match first {
+                    true => first_key.parse(input, state),
+                    false => next_key.parse(input, state),
+                };
+                first = false;
+                match key {
+                    Ok((key, state)) => {
+                        if let Some(Container::Object(keys)) = stack.last_mut() {
+                            if options.duplicate_keys == DuplicateKeys::Error && keys.contains(&key)
+                            {
+                                error(ParserError::DuplicateKey(position));
+                            }
+                            keys.push(key);
+                        }
+                        position = state.current;
+                        next = Next::Value;
+                    }
+                    Err(other) => {
+                        error(other);
+                        position = resync(input, position, options);
+                        if position == input.len() {
+                            return errors;
+                        }
+                        next = Next::AfterValue;
+                    }
+                }
+            }
+            Next::AfterValue => {
+                let (closer, expected) = match stack.last() {
+                    None if rest.is_empty() => return errors,
+                    None => {
+                        error(ParserError::TrailingCharacters(position));
+                        return errors;
+                    }
+                    Some(Container::List) => (']', Expected::COMMA | Expected::CLOSE_BRACKET),
+                    Some(Container::Object(_)) => ('}', Expected::COMMA | Expected::CLOSE_BRACE),
+                };
+                if rest.starts_with(',') {
+                    position += 1;
+                    let after = skip_whitespace(input, position, options);
+                    next = match stack.last() {
+                        _ if trailing_commas && input[after..].starts_with(closer) => {
+                            Next::AfterValue
+                        }
+                        Some(Container::Object(_)) => Next::Key,
+                        _ => Next::Value,
+                    };
+                } else if rest.starts_with([']', '}']) {
+                    // a mismatched closing still closes, the most likely fix
+                    // is to correct it
+                    if !rest.starts_with(closer) {
+                        error(ParserError::Unexpected(position, expected));
+                    }
+                    stack.pop();
+                    position += 1;
+                } else if rest.is_empty() {
+                    error(ParserError::Unexpected(position, expected));
+                    return errors;
+                } else {
+                    error(ParserError::Unexpected(position, expected));
+                    position = resync(input, position, options);
+                    if position == input.len() {
+                        return errors;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The kinds of the open containers, a bit each that is set for objects, so
+/// that nesting up to 128 deep takes no allocation.
+#[derive(Default)]
+struct Nesting {
+    bits: u128,
+    /// The bits of the outer containers, 128 at a time.
+    spilled: Vec<u128>,
+    depth: usize,
+}
+
+impl Nesting {
+    fn push(&mut self, object: bool) {
+        if self.depth > 0 && self.depth.is_multiple_of(128) {
+            self.spilled.push(self.bits);
+        }
+        let bit = 1 << (self.depth % 128);
+        self.bits = match object {
+            true => self.bits | bit,
+            false => self.bits & !bit,
+        };
+        self.depth += 1;
+    }
+
+    fn pop(&mut self) {
+        self.depth -= 1;
+        if self.depth > 0 && self.depth.is_multiple_of(128) {
+            self.bits = self.spilled.pop().expect("spilled bits");
+        }
+    }
+
+    /// Whether the innermost container is an object, `None` outside of any.
+    fn last(&self) -> Option<bool> {
+        let depth = self.depth.checked_sub(1)?;
+        Some(self.bits >> (depth % 128) & 1 == 1)
+    }
+}
+
+/// Checks the document the way `from_str_with_options` parses it, without
+/// building any value. Strings have their escape sequences checked but not
+/// decoded, and the open containers take a bit each, so a valid document
+/// takes no allocation unless it nests more than 128 deep or repeated keys
+/// are errors, which keeps the keys of the open objects. The error of an
+/// invalid document is the one `from_str_with_options` gives.
+pub fn validate(input: &str, options: ParserOptions) -> Result<(), ParserError> {
+    check(input, options).map_err(|error| match from_str_with_options(input, options) {
+        Err(error) => error,
+        // can't happen, the error is as good as any
+        Ok(value) => {
+            value.drop_iteratively();
+            error
+        }
+    })
+}
+
+fn check<'input>(input: &'input str, options: ParserOptions) -> Result<(), ParserError> {
+    let scalar = or(
+        bind(checked_string(options), |_| success(JsonValue::Null)),
+        or(number(options), or(boolean(), or(null(), fail(None)))),
+    );
+    let [list_start, list_end, object_start, object_end, comma] =
+        ["[", "]", "{", "}", ","].map(|p| pat_ws(p, options));
+    let trailing_commas = options.trailing_commas && !options.strict;
+    let key = |expected| {
+        cut(
+            expect(checked_string(options), expected),
+            Expected::COLON,
+            move |key| bind(pat_ws(":", options), move |_: &str| success(key)),
+        )
+    };
+    let first_key = key(Expected::STRING | Expected::CLOSE_BRACE);
+    let next_key = key(match trailing_commas {
+        true => Expected::STRING | Expected::CLOSE_BRACE,
+        false => Expected::STRING,
+    });
+    // decoded only to find repeated keys
+    let decoded_key = member_key(options, Expected::STRING);
+    let duplicates = options.duplicate_keys == DuplicateKeys::Error;
+    let list_next = expect(
+        pat_ws("]", options),
+        Expected::COMMA | Expected::CLOSE_BRACKET,
+    );
+    let object_next = expect(
+        pat_ws("}", options),
+        Expected::COMMA | Expected::CLOSE_BRACE,
+    );
+    let mut nesting = Nesting::default();
+    let mut keys: Vec<Vec<Cow<'input, str>>> = Vec::new();
+    let object_key = |state: State, first: bool, keys: &mut Vec<Vec<Cow<'input, str>>>| {
+        let (_, new_state) = match first {
+            true => first_key.parse(input, state)?,
+            false => next_key.parse(input, state)?,
+        };
+        if duplicates {
+            let (key, _) = decoded_key.parse(input, state)?;
+            let keys = keys.last_mut().expect("the keys of the object");
+            if keys.contains(&key) {
+                return Err(ParserError::DuplicateKey(state.current));
+            }
+            keys.push(key);
+        }
+        Ok(new_state)
+    };
+    // whether the value would be the first of a list, which could be closed
+    // instead
+    let mut first_element = false;
+    let (_, mut state) = whitespace(options).parse(input, State { current: 0 })?;
+    'value: loop {
+        let first = std::mem::take(&mut first_element);
+        let too_deep = options.max_depth.is_some_and(|max| nesting.depth >= max);
+        match scalar.parse(input, state) {
+            Ok((_, new_state)) => state = new_state,
+            Err(ParserError::NoParse(position)) => {
+                if let Ok((_, new_state)) = list_start.parse(input, state) {
+                    if too_deep {
+                        return Err(ParserError::DepthLimit(state.current));
+                    }
+                    state = new_state;
+                    match list_end.parse(input, state) {
+                        Ok((_, new_state)) => state = new_state,
+                        Err(_) => {
+                            nesting.push(false);
+                            first_element = true;
+                            continue 'value;
+                        }
+                    }
+                } else if let Ok((_, new_state)) = object_start.parse(input, state) {
+                    if too_deep {
+                        return Err(ParserError::DepthLimit(state.current));
+                    }
+                    state = new_state;
+                    match object_end.parse(input, state) {
+                        Ok((_, new_state)) => state = new_state,
+                        Err(_) => {
+                            nesting.push(true);
+                            if duplicates {
+                                keys.push(Vec::new());
+                            }
+                            state = object_key(state, true, &mut keys)?;
+                            continue 'value;
+                        }
+                    }
+                } else {
+                    let expected = match first || trailing_commas && nesting.last() == Some(false) {
+                        true => Expected::VALUE | Expected::CLOSE_BRACKET,
+                        false => Expected::VALUE,
+                    };
+                    return Err(ParserError::Unexpected(position, expected));
+                }
+            }
+            Err(error) => return Err(error),
+        }
+        // move on to the next value, closing the containers that end here
+        while let Some(object) = nesting.last() {
+            if let Ok((_, new_state)) = comma.parse(input, state) {
+                state = new_state;
+                let closer = match object {
+                    true => &object_end,
+                    false => &list_end,
+                };
+                // a trailing comma falls through to the closing
+                if !(trailing_commas && closer.parse(input, state).is_ok()) {
+                    if object {
+                        state = object_key(state, false, &mut keys)?;
+                    }
+                    continue 'value;
+                }
+            }
+            (_, state) = match object {
+                true => object_next.parse(input, state)?,
+                false => list_next.parse(input, state)?,
+            };
+            if object && duplicates {
+                keys.pop();
+            }
+            nesting.pop();
+        }
+        break;
+    }
+    let (_, state) = whitespace(options).parse(input, state)?;
+    if state.current != input.len() {
+        return Err(ParserError::TrailingCharacters(state.current));
+    }
+    Ok(())
+}
+
+fn skip_whitespace(input: &str, position: usize, options: ParserOptions) -> usize {
+    let state = State { current: position };
+    whitespace(options)
+        .parse(input, state)
+        .map_or(position, |(_, state)| state.current)
+}
+
+/// Offset of the next `,`, `]` or `}` from `position` on, strings skipped, or
+/// the end of the input, past which there is nothing more worth reporting.
+fn resync(input: &str, position: usize, options: ParserOptions) -> usize {
+    let single_quotes = options.single_quotes && !options.strict;
+    let mut position = position;
+    while let Some(c) = input[position..].chars().next() {
+        match c {
+            ',' | ']' | '}' => return position,
+            '"' | '\'' if c == '"' || single_quotes => {
+                let quote = if c == '"' { "\"" } else { "'" };
+                let state = State { current: position };
+                position = string_span(quote, options)
+                    .parse(input, state)
+                    .map_or(input.len(), |(_, state)| state.current);
+            }
+            _ => position += c.len_utf8(),
+        }
+    }
+    input.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Replacement;
+
+    // test the errors of valid and singly broken documents
+    #[test]
+    fn test_collect_errors_single() {
+        let options = ParserOptions::default();
+        for input in [
+            "{\"a\": [1, 2.5, \"x\"], \"b\": {}, \"c\": []}",
+            " null ",
+            "[1, 2",
+            "{\"a\" 1}",
+            "[01]",
+            "[1] 2",
+            "",
+            "[\"open]",
+        ] {
+            let expected: Vec<_> = from_str_with_options(input, options)
+                .err()
+                .map(|error| error.root().clone())
+                .into_iter()
+                .collect();
+            assert_eq!(collect_errors(input, options), expected, "{input}");
+        }
+    }
+
+    // test that validation agrees with parsing
+    #[test]
+    fn test_validate() {
+        let lenient = ParserOptions {
+            single_quotes: true,
+            trailing_commas: true,
+            comments: true,
+            allow_nan: true,
+            ..Default::default()
+        };
+        let duplicates = ParserOptions {
+            duplicate_keys: DuplicateKeys::Error,
+            max_depth: Some(2),
+            ..Default::default()
+        };
+        let replacement = ParserOptions {
+            replacement: Some(Replacement::default()),
+            ..Default::default()
+        };
+        let strict = ParserOptions {
+            strict: true,
+            ..lenient
+        };
+        for options in [
+            ParserOptions::default(),
+            lenient,
+            duplicates,
+            replacement,
+            strict,
+        ] {
+            for input in [
+                "{\"a\": [1, 2.5, \"x\\n\"], \"b\": {}, \"c\": []}",
+                " null ",
+                "\"\\u00e9\\uD83D\\uDE00\\/\"",
+                "\"\\q\"",
+                "[\"\\u12\"]",
+                "{\"\\uD800\": 1}",
+                "[\"\\uDE00\", \"\\\"\"]",
+                "['a\\'b', NaN] // c",
+                "[1, {\"a\": 2,},]",
+                "{\"a\": 1, \"\\u0061\": 2}",
+                "[[[1]]]",
+                "[1, 2",
+                "{\"a\" 1}",
+                "[01]",
+                "[1] 2",
+                "",
+                "[\"open]",
+                "[,]",
+                "{\"a\": 1,, \"b\": 2}",
+            ] {
+                let expected = from_str_with_options(input, options).map(|value| {
+                    value.drop_iteratively();
+                });
+                assert_eq!(validate(input, options), expected, "{input}");
+            }
+        }
+    }
+
+    // test the validation of documents nested past the inline bits
+    #[test]
+    fn test_validate_deep() {
+        let options = ParserOptions::default();
+        for depth in [127, 128, 129, 256, 100_000] {
+            let input = format!("{}{}", "[{\"a\": ".repeat(depth), "}]".repeat(depth));
+            assert!(validate(&input, options).is_err(), "{depth}");
+            let input = format!("{}1{}", "[{\"a\": ".repeat(depth), "}, 2]".repeat(depth));
+            assert_eq!(validate(&input, options), Ok(()), "{depth}");
+            let mut nesting = Nesting::default();
+            for level in 0..depth {
+                nesting.push(level % 3 == 0);
+            }
+            for level in (0..depth).rev() {
+                assert_eq!(nesting.last(), Some(level % 3 == 0));
+                nesting.pop();
+            }
+            assert_eq!(nesting.last(), None);
+        }
+    }
+
+    // test the errors of a document with several problems
+    #[test]
+    fn test_collect_errors_several() {
+        let input = "{\"a\": [1, tru, 3], \"b\" 2, \"c\": 01, \"d\": [4 5]]";
+        assert_eq!(
+            collect_errors(input, ParserOptions::default()),
+            vec![
+                ParserError::Unexpected(10, Expected::VALUE),
+                ParserError::Unexpected(23, Expected::COLON),
+                ParserError::InvalidNumber(31),
+                ParserError::Unexpected(43, Expected::COMMA | Expected::CLOSE_BRACKET),
+                ParserError::Unexpected(45, Expected::COMMA | Expected::CLOSE_BRACE),
+            ]
+        );
+        let options = ParserOptions {
+            duplicate_keys: DuplicateKeys::Error,
+            max_depth: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(
+            collect_errors("{\"a\": [], \"a\": 1}", options),
+            vec![ParserError::DepthLimit(6), ParserError::DuplicateKey(10)]
+        );
+    }
+}
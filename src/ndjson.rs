@@ -0,0 +1,406 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, Write};
+
+use crate::{
+    expect, member_key, pat_ws, scalar, to_writer_with_options, whitespace, Expected, JsonValue,
+    Parser, ParserError, ParserOptions, SerializerOptions, State, Value,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum AggregateError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("Line {line}: {error}")]
+    Parse { line: usize, error: ParserError },
+}
+
+/// Fields are addressed by the chain of object keys leading to them.
+pub type Field<'spec> = &'spec [&'spec str];
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct AggregateSpec<'spec> {
+    /// Documents get counted and summed per distinct value of this field.
+    pub group_by: Option<Field<'spec>>,
+    pub sums: Vec<Field<'spec>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GroupStats {
+    pub count: u64,
+    /// Sums in the order of `AggregateSpec::sums`, non-numeric values are
+    /// skipped.
+    pub sums: Vec<f64>,
+}
+
+/// A container `scan` is in. Objects come with the key of the member being
+/// scanned, whether no member before it had that key, and the keys seen so
+/// far that some field goes through.
+enum Scanning<'input> {
+    List,
+    Object {
+        key: Cow<'input, str>,
+        first: bool,
+        seen: Vec<Cow<'input, str>>,
+    },
+}
+
+impl<'input> Scanning<'input> {
+    fn object(key: Cow<'input, str>, depth: usize, fields: &[Field]) -> Self {
+        let mut object = Scanning::Object {
+            key: Cow::Borrowed(""),
+            first: true,
+            seen: Vec::new(),
+        };
+        object.member(key, depth, fields);
+        object
+    }
+
+    /// Moves on to the member with `key` of the object at `depth`.
+    fn member(&mut self, key: Cow<'input, str>, depth: usize, fields: &[Field]) {
+        let Scanning::Object {
+            key: current,
+            first,
+            seen,
+        } = self
+        else {
+            panic!("internal error in scan, frame is not an object")
+        };
+        *first = !seen.contains(&key);
+        if *first && fields.iter().any(|field| field.get(depth) == Some(&&*key)) {
+            seen.push(key.clone());
+        }
+        *current = key;
+    }
+}
+
+/// Whether the value being scanned is the one `field` leads to, the first
+/// member with each key as lookups in a parsed value find.
+fn is_at(stack: &[Scanning], field: Field) -> bool {
+    stack.len() == field.len()
+        && stack.iter().zip(field).all(|(level, name)| {
+            matches!(level, Scanning::Object { key, first: true, .. } if key == name)
+        })
+}
+
+/// Looks up the values at `fields` in a document, checking its syntax as
+/// `from_str` does without building any of its containers. Only scalars are
+/// kept, fields holding containers are `None` as missing ones are.
+fn scan<'input>(
+    input: &'input str,
+    fields: &[Field],
+) -> Result<Vec<Option<JsonValue<'input>>>, ParserError> {
+    // whatever was missing, the input ended before it
+    scan_value(input, fields).map_err(|error| match error {
+        ParserError::Unexpected(position, expected) if position == input.len() => {
+            ParserError::UnexpectedEof(position, expected)
+        }
+        error => error,
+    })
+}
+
+fn scan_value<'input>(
+    input: &'input str,
+    fields: &[Field],
+) -> Result<Vec<Option<JsonValue<'input>>>, ParserError> {
+    let options = ParserOptions::default();
+    let scalar = scalar(options);
+    let [list_start, list_end, object_start, object_end, comma] =
+        ["[", "]", "{", "}", ","].map(|p| pat_ws(p, options));
+    let first_key = member_key(options, Expected::STRING | Expected::CLOSE_BRACE);
+    let next_key = member_key(options, Expected::STRING);
+    let list_next = expect(
+        pat_ws("]", options),
+        Expected::COMMA | Expected::CLOSE_BRACKET,
+    );
+    let object_next = expect(
+        pat_ws("}", options),
+        Expected::COMMA | Expected::CLOSE_BRACE,
+    );
+    let mut found = vec![None; fields.len()];
+    let mut stack: Vec<Scanning<'input>> = Vec::new();
+    // whether the value would be the first of a list, which could be closed
+    // instead
+    let mut first_element = false;
+    let (_, mut state) = whitespace(options).parse(input, State { current: 0 })?;
+    'value: loop {
+        let first = std::mem::take(&mut first_element);
+        match scalar.parse(input, state) {
+            Ok((value, new_state)) => {
+                state = new_state;
+                for (found, field) in found.iter_mut().zip(fields) {
+                    if is_at(&stack, field) {
+                        *found = Some(value.clone());
+                    }
+                }
+            }
+            Err(ParserError::NoParse(position)) => {
+                if let Ok((_, new_state)) = list_start.parse(input, state) {
+                    state = new_state;
+                    match list_end.parse(input, state) {
+                        Ok((_, new_state)) => state = new_state,
+                        Err(_) => {
+                            stack.push(Scanning::List);
+                            first_element = true;
+                            continue 'value;
+                        }
+                    }
+                } else if let Ok((_, new_state)) = object_start.parse(input, state) {
+                    state = new_state;
+                    match object_end.parse(input, state) {
+                        Ok((_, new_state)) => state = new_state,
+                        Err(_) => {
+                            let (key, new_state) = first_key.parse(input, state)?;
+                            state = new_state;
+                            stack.push(Scanning::object(key, stack.len(), fields));
+                            continue 'value;
+                        }
+                    }
+                } else {
+                    let expected = match first {
+                        true => Expected::VALUE | Expected::CLOSE_BRACKET,
+                        false => Expected::VALUE,
+                    };
+                    return Err(ParserError::Unexpected(position, expected));
+                }
+            }
+            Err(error) => return Err(error),
+        }
+        // move on to the next value, leaving the containers that end here
+        loop {
+            let depth = stack.len().saturating_sub(1);
+            match stack.last_mut() {
+                None => break 'value,
+                Some(Scanning::List) => {
+                    if let Ok((_, new_state)) = comma.parse(input, state) {
+                        state = new_state;
+                        continue 'value;
+                    }
+                    (_, state) = list_next.parse(input, state)?;
+                }
+                Some(object) => {
+                    if let Ok((_, new_state)) = comma.parse(input, state) {
+                        let (key, new_state) = next_key.parse(input, new_state)?;
+                        state = new_state;
+                        object.member(key, depth, fields);
+                        continue 'value;
+                    }
+                    (_, state) = object_next.parse(input, state)?;
+                }
+            }
+            stack.pop();
+        }
+    }
+    let (_, state) = whitespace(options).parse(input, state)?;
+    if state.current != input.len() {
+        return Err(ParserError::TrailingCharacters(state.current));
+    }
+    Ok(found)
+}
+
+/// Computes counts and sums over newline delimited documents. Lines are
+/// scanned one at a time for the fields the spec names, no document gets
+/// built, so memory stays bounded by the longest line and the number of
+/// groups. Groups are told apart by value, `1` and `1.0` are the same group
+/// while `200` and `"200"` are not. Blank lines are skipped, documents
+/// missing the grouping field or with a container there end up in the `None`
+/// group.
+pub fn aggregate(
+    reader: impl BufRead,
+    spec: &AggregateSpec,
+) -> Result<BTreeMap<Option<Value>, GroupStats>, AggregateError> {
+    let mut groups = BTreeMap::<Option<Value>, GroupStats>::new();
+    let fields: Vec<Field> = spec.group_by.iter().chain(&spec.sums).copied().collect();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut found = scan(&line, &fields)
+            .map_err(|error| AggregateError::Parse {
+                line: index + 1,
+                error,
+            })?
+            .into_iter();
+        let key = match spec.group_by {
+            Some(_) => found.next().flatten().map(JsonValue::into_owned),
+            None => None,
+        };
+        let stats = groups.entry(key).or_insert_with(|| GroupStats {
+            count: 0,
+            sums: vec![0.0; spec.sums.len()],
+        });
+        stats.count += 1;
+        for (sum, value) in stats.sums.iter_mut().zip(found) {
+            if let Some(JsonValue::Number(n)) = &value {
+                *sum += n.as_f64();
+            }
+        }
+    }
+    Ok(groups)
+}
+
+/// Writes documents one per line in compact form, which `aggregate` and other
+/// NDJSON readers take in. Strings can't break a line, their newlines being
+/// escaped.
+pub struct JsonLinesWriter<W> {
+    writer: W,
+    options: SerializerOptions,
+}
+
+impl<W: Write> JsonLinesWriter<W> {
+    pub fn new(writer: W) -> Self {
+        JsonLinesWriter::with_options(writer, SerializerOptions::default())
+    }
+
+    /// `options.pretty` is ignored, as a document has to fit on its line.
+    pub fn with_options(writer: W, options: SerializerOptions) -> Self {
+        let options = SerializerOptions {
+            pretty: None,
+            ..options
+        };
+        JsonLinesWriter { writer, options }
+    }
+
+    pub fn write(&mut self, value: &JsonValue) -> io::Result<()> {
+        to_writer_with_options(&mut self.writer, value, self.options)?;
+        self.writer.write_all(b"\n")
+    }
+
+    pub fn write_all<'value, 'input: 'value>(
+        &mut self,
+        values: impl IntoIterator<Item = &'value JsonValue<'input>>,
+    ) -> io::Result<()> {
+        values.into_iter().try_for_each(|value| self.write(value))
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::from_str;
+
+    const LOG: &str = "{\"status\": 200, \"response\": {\"bytes\": 100}}\n{\"status\": 404, \"response\": {\"bytes\": 10}}\n\n{\"status\": 200, \"response\": {\"bytes\": 50}}\n{\"response\": {\"bytes\": \"n/a\"}}\n";
+
+    // test the grouped aggregation
+    #[test]
+    fn test_aggregate() {
+        let spec = AggregateSpec {
+            group_by: Some(&["status"]),
+            sums: vec![&["response", "bytes"]],
+        };
+        let groups = aggregate(LOG.as_bytes(), &spec).unwrap();
+        assert_eq!(
+            groups.into_iter().collect::<Vec<_>>(),
+            vec![
+                (
+                    None,
+                    GroupStats {
+                        count: 1,
+                        sums: vec![0.0]
+                    }
+                ),
+                (
+                    Some(JsonValue::from(200u64)),
+                    GroupStats {
+                        count: 2,
+                        sums: vec![150.0]
+                    }
+                ),
+                (
+                    Some(JsonValue::from(404u64)),
+                    GroupStats {
+                        count: 1,
+                        sums: vec![10.0]
+                    }
+                ),
+            ]
+        );
+    }
+
+    // test the grouping by value, of the first member with a key
+    #[test]
+    fn test_aggregate_group_values() {
+        let log = "{\"a\": 1, \"n\": 1}\n{\"a\": 1.0, \"n\": 2}\n{\"a\": \"1\", \"n\": 4}\n{\"a\": [1], \"n\": 8}\n{\"a\": {\"b\": 1}, \"n\": 16, \"n\": 32}\n{\"b\": [{\"n\": 1}], \"n\": {\"n\": 64}}";
+        let spec = AggregateSpec {
+            group_by: Some(&["a"]),
+            sums: vec![&["n"], &["a", "b"]],
+        };
+        let groups = aggregate(log.as_bytes(), &spec).unwrap();
+        let sums: Vec<_> = groups
+            .into_iter()
+            .map(|(key, stats)| (key.map(|key| key.to_string()), stats.count, stats.sums))
+            .collect();
+        assert_eq!(
+            sums,
+            vec![
+                (None, 3, vec![24.0, 1.0]),
+                (Some("1".to_string()), 2, vec![3.0, 0.0]),
+                (Some("\"1\"".to_string()), 1, vec![4.0, 0.0]),
+            ]
+        );
+    }
+
+    // test the aggregation errors
+    #[test]
+    fn test_aggregate_error() {
+        let result = aggregate(
+            "{\"a\": 0}\n{\"a\": 1}\n{\"a\"".as_bytes(),
+            &AggregateSpec::default(),
+        );
+        assert!(matches!(result, Err(AggregateError::Parse { line: 3, .. })));
+        for line in [
+            "[1,",
+            "{\"a\"",
+            "[1,]",
+            "[{\"a\": ]}]",
+            "{\"a\": 1} 2",
+            "{\"a\" 1}",
+            "[1 2]",
+            "-",
+        ] {
+            let result = aggregate(line.as_bytes(), &AggregateSpec::default());
+            let Err(AggregateError::Parse { error, .. }) = result else {
+                panic!("{line} aggregated")
+            };
+            assert_eq!(&error, from_str(line).unwrap_err().root(), "{line}");
+        }
+    }
+
+    // test the writing of documents a line each
+    #[test]
+    fn test_json_lines_writer() {
+        let values: Vec<_> = LOG
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| from_str(line).unwrap())
+            .collect();
+        let mut writer = JsonLinesWriter::new(Vec::new());
+        writer.write_all(&values).unwrap();
+        let out = writer.into_inner();
+        assert_eq!(
+            String::from_utf8(out.clone()).unwrap(),
+            "{\"status\":200,\"response\":{\"bytes\":100}}\n{\"status\":404,\"response\":{\"bytes\":10}}\n{\"status\":200,\"response\":{\"bytes\":50}}\n{\"response\":{\"bytes\":\"n/a\"}}\n"
+        );
+        let spec = AggregateSpec {
+            group_by: Some(&["status"]),
+            sums: vec![],
+        };
+        assert_eq!(
+            aggregate(&out[..], &spec).unwrap(),
+            aggregate(LOG.as_bytes(), &spec).unwrap()
+        );
+
+        let mut writer = JsonLinesWriter::new(Vec::new());
+        writer.write(&from_str("\"a\\nb\"").unwrap()).unwrap();
+        assert_eq!(writer.into_inner(), b"\"a\\nb\"\n");
+    }
+}
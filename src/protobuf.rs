@@ -0,0 +1,169 @@
+use std::collections::btree_map;
+
+use prost_types::value::Kind;
+use prost_types::{ListValue, Struct, Value};
+
+use crate::{JsonValue, Member, Shared};
+
+/// A list or struct being converted by `from_protobuf`, with the values left
+/// to convert and those converted, structs along with the key of the value
+/// being converted.
+enum Converting<'message> {
+    List(std::slice::Iter<'message, Value>, Vec<JsonValue<'message>>),
+    Struct(
+        btree_map::Iter<'message, String, Value>,
+        Vec<Member<'message>>,
+        &'message str,
+    ),
+}
+
+/// Converts values of any depth, mind that `prost` drops its messages
+/// recursively.
+pub fn to_protobuf(value: &JsonValue) -> Value {
+    let kind = |kind| Value { kind: Some(kind) };
+    let Ok(converted) = value.fold::<_, std::convert::Infallible>(
+        |value| {
+            Ok(kind(match value {
+                JsonValue::String(s) => Kind::StringValue(s.to_string()),
+                JsonValue::Number(n) => Kind::NumberValue(n.as_f64()),
+                JsonValue::Boolean(b) => Kind::BoolValue(*b),
+                _ => Kind::NullValue(0),
+            }))
+        },
+        |values| Ok(kind(Kind::ListValue(ListValue { values }))),
+        |pairs, values| {
+            let keys = pairs.iter().map(|(key, _)| key.to_string());
+            let fields = keys.zip(values).collect();
+            Ok(kind(Kind::StructValue(Struct { fields })))
+        },
+    );
+    converted
+}
+
+/// `None` unless the value is an object, the only thing a `Struct` can hold.
+pub fn to_protobuf_struct(value: &JsonValue) -> Option<Struct> {
+    match to_protobuf(value).kind {
+        Some(Kind::StructValue(s)) => Some(s),
+        _ => None,
+    }
+}
+
+/// Converts a `Value` message, strings are borrowed from the message. A value
+/// with no kind set is taken as null. Lists and structs are kept on an
+/// explicit stack, so that values of any depth get converted.
+pub fn from_protobuf(value: &Value) -> JsonValue<'_> {
+    let mut stack: Vec<Converting> = Vec::new();
+    let mut value = value;
+    loop {
+        let mut converted = match &value.kind {
+            None | Some(Kind::NullValue(_)) => Some(JsonValue::Null),
+            Some(Kind::NumberValue(n)) => Some(JsonValue::Number((*n).into())),
+            Some(Kind::StringValue(s)) => Some(JsonValue::String(s.into())),
+            Some(Kind::BoolValue(b)) => Some(JsonValue::Boolean(*b)),
+            Some(Kind::StructValue(s)) => {
+                let converted = Vec::with_capacity(s.fields.len());
+                stack.push(Converting::Struct(s.fields.iter(), converted, ""));
+                None
+            }
+            Some(Kind::ListValue(list)) => {
+                let converted = Vec::with_capacity(list.values.len());
+                stack.push(Converting::List(list.values.iter(), converted));
+                None
+            }
+        };
+        // hand the converted value over to its container, completing those
+        // that have all of their values
+        loop {
+            match stack.last_mut() {
+                None => return converted.expect("a converted value"),
+                Some(Converting::List(values, converted_values)) => {
+                    converted_values.extend(converted.take());
+                    if let Some(next) = values.next() {
+                        value = next;
+                        break;
+                    }
+                }
+                Some(Converting::Struct(fields, converted_pairs, key)) => {
+                    if let Some(converted) = converted.take() {
+                        converted_pairs.push(((*key).into(), converted));
+                    }
+                    if let Some((next_key, next)) = fields.next() {
+                        *key = next_key;
+                        value = next;
+                        break;
+                    }
+                }
+            }
+            converted = Some(match stack.pop() {
+                Some(Converting::List(_, values)) => JsonValue::List(Shared::new(values)),
+                Some(Converting::Struct(_, pairs, _)) => JsonValue::Object(Shared::new(pairs)),
+                None => unreachable!("a container was on the stack"),
+            });
+        }
+    }
+}
+
+pub fn from_protobuf_struct(s: &Struct) -> JsonValue<'_> {
+    JsonValue::Object(Shared::new(
+        s.fields
+            .iter()
+            .map(|(key, value)| (key.into(), from_protobuf(value)))
+            .collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // test the protobuf round trip
+    #[test]
+    fn test_protobuf_round_trip() {
+        let value =
+            crate::from_str("{\"a\": [1, \"two\", false], \"b\": null, \"c\": {\"d\": 4.5}}")
+                .unwrap();
+        let message = to_protobuf_struct(&value).unwrap();
+        assert_eq!(
+            message.fields["a"],
+            Value {
+                kind: Some(Kind::ListValue(ListValue {
+                    values: vec![
+                        Value {
+                            kind: Some(Kind::NumberValue(1.0))
+                        },
+                        Value {
+                            kind: Some(Kind::StringValue("two".to_string()))
+                        },
+                        Value {
+                            kind: Some(Kind::BoolValue(false))
+                        },
+                    ]
+                }))
+            }
+        );
+        assert_eq!(from_protobuf_struct(&message), value);
+        assert_eq!(to_protobuf_struct(&JsonValue::Null), None);
+    }
+
+    // test the conversions of values too deep to recurse into
+    #[test]
+    fn test_protobuf_deep() {
+        let depth = 100_000;
+        let input = format!("{}{{\"a\": 1}}{}", "[".repeat(depth), "]".repeat(depth));
+        let value = crate::from_str(&input).unwrap();
+        let message = to_protobuf(&value);
+        let converted = from_protobuf(&message);
+        assert_eq!(converted, value);
+        converted.drop_iteratively();
+        value.drop_iteratively();
+        // `prost` would drop the deep message recursively
+        let mut pending = vec![message];
+        while let Some(message) = pending.pop() {
+            match message.kind {
+                Some(Kind::ListValue(list)) => pending.extend(list.values),
+                Some(Kind::StructValue(s)) => pending.extend(s.fields.into_values()),
+                _ => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use std::io::Read;
+
+use crate::{from_str, JsonValue, ParserError};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ReadError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Parse(#[from] ParserError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    pub processed: u64,
+    pub total: Option<u64>,
+}
+
+/// Wraps a reader and reports the amount of bytes pulled through it,
+/// once every `granularity` bytes and once more at the end of the stream.
+pub struct ProgressReader<R, F> {
+    inner: R,
+    callback: F,
+    granularity: u64,
+    progress: Progress,
+    reported: u64,
+}
+
+impl<R: Read, F: FnMut(Progress)> ProgressReader<R, F> {
+    pub fn new(inner: R, total: Option<u64>, granularity: u64, callback: F) -> Self {
+        Self {
+            inner,
+            callback,
+            granularity: granularity.max(1),
+            progress: Progress {
+                processed: 0,
+                total,
+            },
+            reported: 0,
+        }
+    }
+}
+
+impl<R: Read, F: FnMut(Progress)> Read for ProgressReader<R, F> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.progress.processed += read as u64;
+        let is_done = read == 0 && !buf.is_empty();
+        if self.progress.processed - self.reported >= self.granularity
+            || (is_done && self.reported != self.progress.processed)
+        {
+            self.reported = self.progress.processed;
+            (self.callback)(self.progress);
+        }
+        Ok(read)
+    }
+}
+
+/// Reads the whole `reader` into `buffer` and parses it. The buffer is provided
+/// by the caller, since the resulting value borrows from it.
+pub fn from_reader<R: Read>(
+    mut reader: R,
+    buffer: &mut String,
+) -> Result<JsonValue<'_>, ReadError> {
+    buffer.clear();
+    reader.read_to_string(buffer)?;
+    Ok(from_str(buffer)?)
+}
+
+pub fn from_reader_with_progress<R: Read>(
+    reader: R,
+    buffer: &mut String,
+    total: Option<u64>,
+    granularity: u64,
+    callback: impl FnMut(Progress),
+) -> Result<JsonValue<'_>, ReadError> {
+    from_reader(
+        ProgressReader::new(reader, total, granularity, callback),
+        buffer,
+    )
+}
+
+pub fn from_file(
+    path: impl AsRef<std::path::Path>,
+    buffer: &mut String,
+) -> Result<JsonValue<'_>, ReadError> {
+    from_reader(std::fs::File::open(path)?, buffer)
+}
+
+/// Same as `from_file`, the total is taken from the file metadata.
+pub fn from_file_with_progress(
+    path: impl AsRef<std::path::Path>,
+    buffer: &mut String,
+    granularity: u64,
+    callback: impl FnMut(Progress),
+) -> Result<JsonValue<'_>, ReadError> {
+    let file = std::fs::File::open(path)?;
+    let total = file.metadata()?.len();
+    from_reader_with_progress(file, buffer, Some(total), granularity, callback)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // test the progress reader reporting
+    #[test]
+    fn test_progress_reader() {
+        let input = "[1, 2, 3, 4, 5]";
+        let mut reports = Vec::new();
+        let mut buffer = String::new();
+        let mut reader = ProgressReader::new(input.as_bytes(), Some(15), 4, |progress| {
+            reports.push(progress.processed)
+        });
+        let mut chunk = [0u8; 4];
+        loop {
+            let read = reader.read(&mut chunk).unwrap();
+            if read == 0 {
+                break;
+            }
+            buffer.push_str(std::str::from_utf8(&chunk[..read]).unwrap());
+        }
+        assert_eq!(buffer, input);
+        assert_eq!(reports, vec![4, 8, 12, 15]);
+    }
+
+    // test the reader entry point
+    #[test]
+    fn test_from_reader_with_progress() {
+        let input = "{\"key\": \"value\"}";
+        let mut last = None;
+        let mut buffer = String::new();
+        let result =
+            from_reader_with_progress(input.as_bytes(), &mut buffer, None, 1024, |progress| {
+                last = Some(progress)
+            })
+            .unwrap();
+        assert_eq!(
+            result,
+            JsonValue::Object(crate::Shared::new(vec![(
+                "key".into(),
+                JsonValue::String("value".into())
+            )]))
+        );
+        assert_eq!(
+            last,
+            Some(Progress {
+                processed: input.len() as u64,
+                total: None
+            })
+        );
+    }
+}
@@ -0,0 +1,930 @@
+use std::borrow::Cow;
+use std::fmt::{self, Write};
+use std::io;
+
+use crate::{escape_str, EscapeOptions, JsonValue, Number};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct SerializerOptions {
+    /// Put every value of a container on its own line, indented, `None` for
+    /// compact output.
+    pub pretty: Option<PrettyConfig>,
+    /// What gets escaped within strings beyond what has to be.
+    pub escape: EscapeOptions,
+    pub non_finite: NonFinite,
+}
+
+/// What becomes of `NaN` and the infinities, which JSON has no way to write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum NonFinite {
+    #[default]
+    Null,
+    /// Fail with `SerializeError::NonFinite`.
+    Error,
+    /// Write `NaN`, `Infinity` and `-Infinity`, which JSON5 and JavaScript
+    /// read, as does the parser with `ParserOptions::allow_nan`.
+    Literal,
+}
+
+/// How pretty output is laid out, two spaces per level by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PrettyConfig {
+    /// Number of `indent_char` per level of nesting.
+    pub indent_width: usize,
+    pub indent_char: char,
+    pub newline: Newline,
+}
+
+impl Default for PrettyConfig {
+    fn default() -> Self {
+        PrettyConfig {
+            indent_width: 2,
+            indent_char: ' ',
+            newline: Newline::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Newline {
+    #[default]
+    Lf,
+    CrLf,
+}
+
+impl Newline {
+    fn as_str(self) -> &'static str {
+        match self {
+            Newline::Lf => "\n",
+            Newline::CrLf => "\r\n",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, thiserror::Error, PartialEq, Eq)]
+pub enum SerializeError {
+    #[error("NaN and the infinities have no JSON form")]
+    NonFinite,
+}
+
+/// The value as compact JSON, no whitespace between tokens and only the
+/// escapes strings need. Numbers keep their source text in arbitrary
+/// precision mode, `NaN` and the infinities have no JSON form and become
+/// `null`.
+pub fn to_string(value: &JsonValue) -> String {
+    let options = SerializerOptions::default();
+    to_string_with_formatter(value, &mut StandardFormatter::new(options))
+}
+
+/// The value as JSON indented by two spaces, see `PrettyConfig`.
+pub fn to_string_pretty(value: &JsonValue) -> String {
+    let options = SerializerOptions {
+        pretty: Some(PrettyConfig::default()),
+        ..Default::default()
+    };
+    to_string_with_formatter(value, &mut StandardFormatter::new(options))
+}
+
+/// Fails only as `options.non_finite` says.
+pub fn to_string_with_options(
+    value: &JsonValue,
+    options: SerializerOptions,
+) -> Result<String, SerializeError> {
+    let mut out = String::new();
+    let mut formatter = StandardFormatter::new(options);
+    match write_tokens(&mut out, value, &mut formatter, false) {
+        Ok(()) => Ok(out),
+        Err(_) if formatter.non_finite => Err(SerializeError::NonFinite),
+        Err(_) => unreachable!("writing to a String doesn't fail"),
+    }
+}
+
+/// The value as pretty JSON colored for a terminal, keys, strings, numbers
+/// and literals each wrapped in the ANSI escapes of the theme.
+pub fn to_string_colored(value: &JsonValue, theme: &ColorTheme) -> String {
+    let options = SerializerOptions {
+        pretty: Some(PrettyConfig::default()),
+        ..Default::default()
+    };
+    to_string_with_formatter(value, &mut ColorFormatter::new(options, theme.clone()))
+}
+
+/// The canonical form of the value after RFC 8785 (JCS), the same text for
+/// equal documents so that it can be hashed or signed. Members are sorted by
+/// the UTF-16 code units of their keys, strings have only the escapes they
+/// need and every number is written the way JavaScript writes a double, which
+/// loses digits of integers beyond 2^53. Fails on `NaN` and the infinities.
+pub fn to_string_canonical(value: &JsonValue) -> Result<String, SerializeError> {
+    let mut out = String::new();
+    let mut formatter = CanonicalFormatter { non_finite: false };
+    match write_tokens(&mut out, value, &mut formatter, true) {
+        Ok(()) => Ok(out),
+        Err(_) if formatter.non_finite => Err(SerializeError::NonFinite),
+        Err(_) => unreachable!("writing to a String doesn't fail"),
+    }
+}
+
+/// The value as JSON laid out by `formatter`.
+pub fn to_string_with_formatter(value: &JsonValue, formatter: &mut impl Formatter) -> String {
+    let mut out = String::new();
+    write_tokens(&mut out, value, formatter, false).expect("writing to a String doesn't fail");
+    out
+}
+
+/// The bytes of `to_string`, written straight into the vector.
+pub fn to_vec(value: &JsonValue) -> Vec<u8> {
+    let mut out = Vec::new();
+    to_writer(&mut out, value).expect("writing to a Vec doesn't fail");
+    out
+}
+
+pub fn to_vec_pretty(value: &JsonValue) -> Vec<u8> {
+    let mut out = Vec::new();
+    to_writer_pretty(&mut out, value).expect("writing to a Vec doesn't fail");
+    out
+}
+
+/// Fails only as `options.non_finite` says.
+pub fn to_vec_with_options(
+    value: &JsonValue,
+    options: SerializerOptions,
+) -> Result<Vec<u8>, SerializeError> {
+    let mut out = Vec::new();
+    // writing to a Vec doesn't fail otherwise
+    match to_writer_with_options(&mut out, value, options) {
+        Ok(()) => Ok(out),
+        Err(_) => Err(SerializeError::NonFinite),
+    }
+}
+
+/// Writes the value as `to_string` does, token by token rather than building
+/// the whole text first. Small writes go straight through, wrap the writer in
+/// a `BufWriter` if those are costly.
+pub fn to_writer(writer: impl io::Write, value: &JsonValue) -> io::Result<()> {
+    to_writer_with_options(writer, value, SerializerOptions::default())
+}
+
+pub fn to_writer_pretty(writer: impl io::Write, value: &JsonValue) -> io::Result<()> {
+    let options = SerializerOptions {
+        pretty: Some(PrettyConfig::default()),
+        ..Default::default()
+    };
+    to_writer_with_options(writer, value, options)
+}
+
+/// A number the options say to fail on is an `InvalidData` error.
+pub fn to_writer_with_options(
+    writer: impl io::Write,
+    value: &JsonValue,
+    options: SerializerOptions,
+) -> io::Result<()> {
+    let mut formatter = StandardFormatter::new(options);
+    to_writer_with_formatter(writer, value, &mut formatter).map_err(|error| {
+        match formatter.non_finite {
+            true => io::Error::new(io::ErrorKind::InvalidData, SerializeError::NonFinite),
+            false => error,
+        }
+    })
+}
+
+pub fn to_writer_with_formatter(
+    writer: impl io::Write,
+    value: &JsonValue,
+    formatter: &mut impl Formatter,
+) -> io::Result<()> {
+    let mut adapter = Adapter {
+        writer,
+        error: None,
+    };
+    write_tokens(&mut adapter, value, formatter, false).map_err(|_| {
+        adapter
+            .error
+            .unwrap_or_else(|| io::Error::other("formatting failed"))
+    })
+}
+
+/// Writes the tokens of JSON text, the serializer calls it for every one of
+/// them. Each method has a default giving compact output with the minimal
+/// escapes, a custom style overrides what it needs to, keeping whatever state
+/// it takes in the formatter. The `first` arguments say whether the value or
+/// member comes first in its container.
+pub trait Formatter {
+    fn write_null<W: Write + ?Sized>(&mut self, out: &mut W) -> fmt::Result {
+        out.write_str("null")
+    }
+
+    fn write_bool<W: Write + ?Sized>(&mut self, out: &mut W, value: bool) -> fmt::Result {
+        out.write_str(if value { "true" } else { "false" })
+    }
+
+    /// `NaN` and the infinities are written as `null`.
+    fn write_number<W: Write + ?Sized>(&mut self, out: &mut W, value: &Number) -> fmt::Result {
+        value.write_json(out)
+    }
+
+    /// The string quoted and escaped, object keys included.
+    fn write_string<W: Write + ?Sized>(&mut self, out: &mut W, value: &str) -> fmt::Result {
+        write!(out, "\"{}\"", escape_str(value, EscapeOptions::default()))
+    }
+
+    fn begin_array<W: Write + ?Sized>(&mut self, out: &mut W) -> fmt::Result {
+        out.write_str("[")
+    }
+
+    fn end_array<W: Write + ?Sized>(&mut self, out: &mut W) -> fmt::Result {
+        out.write_str("]")
+    }
+
+    fn begin_array_value<W: Write + ?Sized>(&mut self, out: &mut W, first: bool) -> fmt::Result {
+        if first {
+            Ok(())
+        } else {
+            out.write_str(",")
+        }
+    }
+
+    fn end_array_value<W: Write + ?Sized>(&mut self, _out: &mut W) -> fmt::Result {
+        Ok(())
+    }
+
+    fn begin_object<W: Write + ?Sized>(&mut self, out: &mut W) -> fmt::Result {
+        out.write_str("{")
+    }
+
+    fn end_object<W: Write + ?Sized>(&mut self, out: &mut W) -> fmt::Result {
+        out.write_str("}")
+    }
+
+    /// Comes before the key is written with `write_string`.
+    fn begin_object_key<W: Write + ?Sized>(&mut self, out: &mut W, first: bool) -> fmt::Result {
+        if first {
+            Ok(())
+        } else {
+            out.write_str(",")
+        }
+    }
+
+    /// Goes between the key and the value.
+    fn begin_object_value<W: Write + ?Sized>(&mut self, out: &mut W) -> fmt::Result {
+        out.write_str(":")
+    }
+
+    fn end_object_value<W: Write + ?Sized>(&mut self, _out: &mut W) -> fmt::Result {
+        Ok(())
+    }
+}
+
+/// What the `_with_options` functions write with, compact or pretty and
+/// escaping as the options say. A custom formatter can hold one and pass on
+/// the calls it doesn't change.
+#[derive(Debug, Clone)]
+pub struct StandardFormatter {
+    options: SerializerOptions,
+    depth: usize,
+    /// Whether the innermost open container has any value yet.
+    has_value: bool,
+    /// Whether it failed on a number, as `NonFinite::Error` says.
+    non_finite: bool,
+}
+
+impl StandardFormatter {
+    pub fn new(options: SerializerOptions) -> Self {
+        StandardFormatter {
+            options,
+            depth: 0,
+            has_value: false,
+            non_finite: false,
+        }
+    }
+
+    fn begin(&mut self, out: &mut (impl Write + ?Sized), opening: &str) -> fmt::Result {
+        self.depth += 1;
+        self.has_value = false;
+        out.write_str(opening)
+    }
+
+    fn end(&mut self, out: &mut (impl Write + ?Sized), closing: &str) -> fmt::Result {
+        self.depth -= 1;
+        if let (Some(pretty), true) = (self.options.pretty, self.has_value) {
+            write_line(out, pretty, self.depth)?;
+        }
+        out.write_str(closing)
+    }
+
+    fn begin_value(&mut self, out: &mut (impl Write + ?Sized), first: bool) -> fmt::Result {
+        if !first {
+            out.write_str(",")?;
+        }
+        match self.options.pretty {
+            Some(pretty) => write_line(out, pretty, self.depth),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Formatter for StandardFormatter {
+    fn write_number<W: Write + ?Sized>(&mut self, out: &mut W, value: &Number) -> fmt::Result {
+        match (value.non_finite_literal(), self.options.non_finite) {
+            (None, _) | (_, NonFinite::Null) => value.write_json(out),
+            (Some(literal), NonFinite::Literal) => out.write_str(literal),
+            (Some(_), NonFinite::Error) => {
+                self.non_finite = true;
+                Err(fmt::Error)
+            }
+        }
+    }
+
+    fn write_string<W: Write + ?Sized>(&mut self, out: &mut W, value: &str) -> fmt::Result {
+        write!(out, "\"{}\"", escape_str(value, self.options.escape))
+    }
+
+    fn begin_array<W: Write + ?Sized>(&mut self, out: &mut W) -> fmt::Result {
+        self.begin(out, "[")
+    }
+
+    fn end_array<W: Write + ?Sized>(&mut self, out: &mut W) -> fmt::Result {
+        self.end(out, "]")
+    }
+
+    fn begin_array_value<W: Write + ?Sized>(&mut self, out: &mut W, first: bool) -> fmt::Result {
+        self.begin_value(out, first)
+    }
+
+    fn end_array_value<W: Write + ?Sized>(&mut self, _out: &mut W) -> fmt::Result {
+        self.has_value = true;
+        Ok(())
+    }
+
+    fn begin_object<W: Write + ?Sized>(&mut self, out: &mut W) -> fmt::Result {
+        self.begin(out, "{")
+    }
+
+    fn end_object<W: Write + ?Sized>(&mut self, out: &mut W) -> fmt::Result {
+        self.end(out, "}")
+    }
+
+    fn begin_object_key<W: Write + ?Sized>(&mut self, out: &mut W, first: bool) -> fmt::Result {
+        self.begin_value(out, first)
+    }
+
+    fn begin_object_value<W: Write + ?Sized>(&mut self, out: &mut W) -> fmt::Result {
+        out.write_str(if self.options.pretty.is_some() {
+            ": "
+        } else {
+            ":"
+        })
+    }
+
+    fn end_object_value<W: Write + ?Sized>(&mut self, _out: &mut W) -> fmt::Result {
+        self.has_value = true;
+        Ok(())
+    }
+}
+
+/// The colors `ColorFormatter` gives each kind of token, as the parameters of
+/// an ANSI SGR escape such as `"1;34"` for bold blue. An empty string leaves
+/// the token uncolored, punctuation always is. The default is the theme of
+/// `jq`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ColorTheme {
+    pub key: Cow<'static, str>,
+    pub string: Cow<'static, str>,
+    pub number: Cow<'static, str>,
+    /// `true`, `false` and `null`.
+    pub literal: Cow<'static, str>,
+}
+
+impl Default for ColorTheme {
+    fn default() -> Self {
+        ColorTheme {
+            key: Cow::Borrowed("34;1"),
+            string: Cow::Borrowed("0;32"),
+            number: Cow::Borrowed("0;39"),
+            literal: Cow::Borrowed("1;30"),
+        }
+    }
+}
+
+/// A `StandardFormatter` that colors the tokens it writes as the theme says,
+/// for output to a terminal.
+#[derive(Debug, Clone)]
+pub struct ColorFormatter {
+    inner: StandardFormatter,
+    theme: ColorTheme,
+    /// Whether the string to come is an object key.
+    key: bool,
+}
+
+impl ColorFormatter {
+    pub fn new(options: SerializerOptions, theme: ColorTheme) -> Self {
+        ColorFormatter {
+            inner: StandardFormatter::new(options),
+            theme,
+            key: false,
+        }
+    }
+}
+
+/// Writes the token in the color, resetting it after.
+fn write_colored<W: Write + ?Sized>(
+    out: &mut W,
+    color: &str,
+    token: impl FnOnce(&mut W) -> fmt::Result,
+) -> fmt::Result {
+    if color.is_empty() {
+        return token(out);
+    }
+    write!(out, "\x1b[{color}m")?;
+    token(out)?;
+    out.write_str("\x1b[0m")
+}
+
+impl Formatter for ColorFormatter {
+    fn write_null<W: Write + ?Sized>(&mut self, out: &mut W) -> fmt::Result {
+        write_colored(out, &self.theme.literal, |out| self.inner.write_null(out))
+    }
+
+    fn write_bool<W: Write + ?Sized>(&mut self, out: &mut W, value: bool) -> fmt::Result {
+        write_colored(out, &self.theme.literal, |out| {
+            self.inner.write_bool(out, value)
+        })
+    }
+
+    fn write_number<W: Write + ?Sized>(&mut self, out: &mut W, value: &Number) -> fmt::Result {
+        write_colored(out, &self.theme.number, |out| {
+            self.inner.write_number(out, value)
+        })
+    }
+
+    fn write_string<W: Write + ?Sized>(&mut self, out: &mut W, value: &str) -> fmt::Result {
+        let color = match self.key {
+            true => &self.theme.key,
+            false => &self.theme.string,
+        };
+        write_colored(out, color, |out| self.inner.write_string(out, value))
+    }
+
+    fn begin_array<W: Write + ?Sized>(&mut self, out: &mut W) -> fmt::Result {
+        self.inner.begin_array(out)
+    }
+
+    fn end_array<W: Write + ?Sized>(&mut self, out: &mut W) -> fmt::Result {
+        self.inner.end_array(out)
+    }
+
+    fn begin_array_value<W: Write + ?Sized>(&mut self, out: &mut W, first: bool) -> fmt::Result {
+        self.inner.begin_array_value(out, first)
+    }
+
+    fn end_array_value<W: Write + ?Sized>(&mut self, out: &mut W) -> fmt::Result {
+        self.inner.end_array_value(out)
+    }
+
+    fn begin_object<W: Write + ?Sized>(&mut self, out: &mut W) -> fmt::Result {
+        self.inner.begin_object(out)
+    }
+
+    fn end_object<W: Write + ?Sized>(&mut self, out: &mut W) -> fmt::Result {
+        self.inner.end_object(out)
+    }
+
+    fn begin_object_key<W: Write + ?Sized>(&mut self, out: &mut W, first: bool) -> fmt::Result {
+        self.key = true;
+        self.inner.begin_object_key(out, first)
+    }
+
+    fn begin_object_value<W: Write + ?Sized>(&mut self, out: &mut W) -> fmt::Result {
+        self.key = false;
+        self.inner.begin_object_value(out)
+    }
+
+    fn end_object_value<W: Write + ?Sized>(&mut self, out: &mut W) -> fmt::Result {
+        self.inner.end_object_value(out)
+    }
+}
+
+/// Compact with JavaScript's numbers, keeping track of numbers it can't write
+/// as the only error there is.
+pub(crate) struct CanonicalFormatter {
+    pub(crate) non_finite: bool,
+}
+
+impl Formatter for CanonicalFormatter {
+    fn write_number<W: Write + ?Sized>(&mut self, out: &mut W, value: &Number) -> fmt::Result {
+        let n = value.as_f64();
+        self.non_finite = !n.is_finite();
+        match self.non_finite {
+            true => Err(fmt::Error),
+            false => write_canonical_number(out, n),
+        }
+    }
+}
+
+/// Passes text on to an `io::Write`, keeping the error that `fmt::Error`
+/// can't carry.
+pub(crate) struct Adapter<W> {
+    pub(crate) writer: W,
+    pub(crate) error: Option<io::Error>,
+}
+
+impl<W: io::Write> Write for Adapter<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.writer.write_all(s.as_bytes()).map_err(|error| {
+            self.error = Some(error);
+            fmt::Error
+        })
+    }
+}
+
+type Member<'input> = (Cow<'input, str>, JsonValue<'input>);
+
+enum Open<'value, 'input> {
+    List(std::slice::Iter<'value, JsonValue<'input>>),
+    Object(std::slice::Iter<'value, Member<'input>>),
+    /// The members in canonical order.
+    Sorted(std::vec::IntoIter<&'value Member<'input>>),
+}
+
+/// Starts a line at the indentation of the given depth.
+fn write_line(out: &mut (impl Write + ?Sized), pretty: PrettyConfig, depth: usize) -> fmt::Result {
+    out.write_str(pretty.newline.as_str())?;
+    for _ in 0..pretty.indent_width * depth {
+        out.write_char(pretty.indent_char)?;
+    }
+    Ok(())
+}
+
+/// Writes a finite double as JavaScript's `Number.prototype.toString` does, in
+/// decimal notation from 1e-6 up to 1e21 and in exponential notation outside.
+fn write_canonical_number(out: &mut (impl Write + ?Sized), n: f64) -> fmt::Result {
+    if n == 0.0 {
+        return out.write_str("0");
+    }
+    if n < 0.0 {
+        out.write_str("-")?;
+    }
+    // the shortest digits that read back as the same double
+    let scientific = format!("{:e}", n.abs());
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let digits = mantissa.replace('.', "");
+    let length = digits.len() as i32;
+    // digits times 10 to the power of `point - length`
+    let point = exponent.parse::<i32>().unwrap() + 1;
+    match point {
+        _ if length <= point && point <= 21 => {
+            write!(out, "{digits}{}", "0".repeat((point - length) as usize))
+        }
+        1..=21 => {
+            let (whole, fraction) = digits.split_at(point as usize);
+            write!(out, "{whole}.{fraction}")
+        }
+        -5..=0 => write!(out, "0.{}{digits}", "0".repeat(-point as usize)),
+        _ => {
+            let (first, rest) = digits.split_at(1);
+            let sign = if point > 0 { "+" } else { "-" };
+            match rest {
+                "" => write!(out, "{first}e{sign}{}", (point - 1).abs()),
+                _ => write!(out, "{first}.{rest}e{sign}{}", (point - 1).abs()),
+            }
+        }
+    }
+}
+
+pub(crate) fn write_value(
+    out: &mut impl Write,
+    value: &JsonValue,
+    options: SerializerOptions,
+) -> fmt::Result {
+    write_tokens(out, value, &mut StandardFormatter::new(options), false)
+}
+
+/// Walks the value calling `formatter` for every token, members sorted by the
+/// UTF-16 code units of their keys with `sort_keys`. Nesting is tracked on
+/// the heap, so deep values are fine to write.
+pub(crate) fn write_tokens(
+    out: &mut impl Write,
+    value: &JsonValue,
+    formatter: &mut impl Formatter,
+    sort_keys: bool,
+) -> fmt::Result {
+    // the containers being written, with whether they are still empty
+    let mut stack: Vec<(Open, bool)> = Vec::new();
+    let mut next = Some(value);
+    loop {
+        match next.take() {
+            Some(JsonValue::List(values)) => {
+                formatter.begin_array(out)?;
+                stack.push((Open::List(values.iter()), true));
+            }
+            Some(JsonValue::Object(pairs)) if sort_keys => {
+                formatter.begin_object(out)?;
+                let mut sorted: Vec<_> = pairs.iter().collect();
+                sorted.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+                stack.push((Open::Sorted(sorted.into_iter()), true));
+            }
+            Some(JsonValue::Object(pairs)) => {
+                formatter.begin_object(out)?;
+                stack.push((Open::Object(pairs.iter()), true));
+            }
+            Some(JsonValue::String(s)) => formatter.write_string(out, s)?,
+            Some(JsonValue::Number(n)) => formatter.write_number(out, n)?,
+            Some(JsonValue::Boolean(b)) => formatter.write_bool(out, *b)?,
+            Some(JsonValue::Null) => formatter.write_null(out)?,
+            None => {}
+        }
+        let Some((open, empty)) = stack.last_mut() else {
+            return Ok(());
+        };
+        let first = *empty;
+        *empty = false;
+        // a value of the container has just been written unless it is the
+        // first to come
+        let next_pair = match open {
+            Open::List(values) => {
+                if !first {
+                    formatter.end_array_value(out)?;
+                }
+                let Some(value) = values.next() else {
+                    formatter.end_array(out)?;
+                    stack.pop();
+                    continue;
+                };
+                formatter.begin_array_value(out, first)?;
+                next = Some(value);
+                continue;
+            }
+            Open::Object(pairs) => pairs.next(),
+            Open::Sorted(pairs) => pairs.next(),
+        };
+        if !first {
+            formatter.end_object_value(out)?;
+        }
+        let Some((key, value)) = next_pair else {
+            formatter.end_object(out)?;
+            stack.pop();
+            continue;
+        };
+        formatter.begin_object_key(out, first)?;
+        formatter.write_string(out, key)?;
+        formatter.begin_object_value(out)?;
+        next = Some(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{from_str, from_str_with_options, ParserOptions};
+
+    // test the compact serialization round trip
+    #[test]
+    fn test_to_string() {
+        let options = ParserOptions {
+            allow_nan: true,
+            ..Default::default()
+        };
+        let input = " { \"a\" : [ 1 , -2.5e-3 , \"\\u00e9\\t\\\"\" ] , \"\" : { } , \"b\" : NaN } ";
+        let value = from_str_with_options(input, options).unwrap();
+        let text = to_string(&value);
+        assert_eq!(text, "{\"a\":[1,-0.0025,\"é\\t\\\"\"],\"\":{},\"b\":null}");
+        assert_eq!(to_string(&from_str(&text).unwrap()), text);
+        assert_eq!(
+            to_string(&JsonValue::from(u64::MAX)),
+            "18446744073709551615"
+        );
+    }
+
+    // test the pretty printing
+    #[test]
+    fn test_to_string_pretty() {
+        let value = from_str("{\"a\": [1, [], {\"b\": null}], \"c\": {}}").unwrap();
+        assert_eq!(
+            to_string_pretty(&value),
+            "{\n  \"a\": [\n    1,\n    [],\n    {\n      \"b\": null\n    }\n  ],\n  \"c\": {}\n}"
+        );
+        let options = SerializerOptions {
+            pretty: Some(PrettyConfig {
+                indent_width: 1,
+                indent_char: '\t',
+                newline: Newline::CrLf,
+            }),
+            ..Default::default()
+        };
+        let value = from_str("[1, {\"a\": 2}]").unwrap();
+        assert_eq!(
+            to_string_with_options(&value, options).unwrap(),
+            "[\r\n\t1,\r\n\t{\r\n\t\t\"a\": 2\r\n\t}\r\n]"
+        );
+        assert_eq!(to_string_pretty(&JsonValue::from(1)), "1");
+        assert_eq!(format!("{value:#}"), to_string_pretty(&value));
+    }
+
+    // test the colored pretty printing
+    #[test]
+    fn test_to_string_colored() {
+        let value = from_str("{\"a\": [1, \"b\", true, null]}").unwrap();
+        assert_eq!(
+            to_string_colored(&value, &ColorTheme::default()),
+            "{\n  \x1b[34;1m\"a\"\x1b[0m: [\n    \x1b[0;39m1\x1b[0m,\n    \x1b[0;32m\"b\"\x1b[0m,\n    \x1b[1;30mtrue\x1b[0m,\n    \x1b[1;30mnull\x1b[0m\n  ]\n}"
+        );
+        let theme = ColorTheme {
+            key: Cow::Borrowed(""),
+            string: Cow::Borrowed("31"),
+            number: Cow::Borrowed(""),
+            literal: Cow::Borrowed(""),
+        };
+        let value = from_str("{\"a\": \"b\", \"c\": 1}").unwrap();
+        let mut formatter = ColorFormatter::new(SerializerOptions::default(), theme);
+        assert_eq!(
+            to_string_with_formatter(&value, &mut formatter),
+            "{\"a\":\x1b[31m\"b\"\x1b[0m,\"c\":1}"
+        );
+    }
+
+    // test the writing to an io::Write
+    #[test]
+    fn test_to_writer() {
+        struct Full(usize);
+
+        impl io::Write for Full {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                if self.0 < buf.len() {
+                    return Err(io::Error::new(io::ErrorKind::StorageFull, "full"));
+                }
+                self.0 -= buf.len();
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let value = from_str("{\"a\": [1, \"x\"]}").unwrap();
+        let mut out = Vec::new();
+        to_writer(&mut out, &value).unwrap();
+        assert_eq!(out, to_string(&value).as_bytes());
+        out.clear();
+        to_writer_pretty(&mut out, &value).unwrap();
+        assert_eq!(out, to_string_pretty(&value).as_bytes());
+        let error = to_writer(Full(8), &value).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::StorageFull);
+    }
+
+    // test the canonical form, examples from RFC 8785
+    #[test]
+    fn test_to_string_canonical() {
+        let input = "{\"numbers\": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001, -0, 1e21, 1e20, 0.000001, 1e-7, 9007199254740993], \"string\": \"\\u20ac$\\u000F\\u000aA'\\u0042\\u0022\\u005c\\\\\\\"\\/\", \"literals\": [null, true, false]}";
+        assert_eq!(
+            to_string_canonical(&from_str(input).unwrap()).unwrap(),
+            "{\"literals\":[null,true,false],\"numbers\":[333333333.3333333,1e+30,4.5,0.002,1e-27,0,1e+21,100000000000000000000,0.000001,1e-7,9007199254740992],\"string\":\"€$\\u000f\\nA'B\\\"\\\\\\\\\\\"/\"}"
+        );
+        let input = "{\"\\u20ac\": 1, \"\\r\": 2, \"\\ud83d\\ude00\": 3, \"1\": 4, \"\\u00f6\": 5, \"\\ufb33\": 6}";
+        let keys: Vec<_> = from_str(&to_string_canonical(&from_str(input).unwrap()).unwrap())
+            .unwrap()
+            .as_object()
+            .unwrap()
+            .iter()
+            .map(|(key, _)| key.to_string())
+            .collect();
+        assert_eq!(
+            keys,
+            ["\r", "1", "\u{f6}", "\u{20ac}", "\u{1f600}", "\u{fb33}"]
+        );
+        assert_eq!(
+            to_string_canonical(&JsonValue::from(f64::INFINITY)),
+            Err(SerializeError::NonFinite)
+        );
+    }
+
+    // test the escaping options of the serializer
+    #[test]
+    fn test_to_string_escape() {
+        let value = from_str("{\"clé\": \"</script> 😀\"}").unwrap();
+        let options = SerializerOptions {
+            escape: EscapeOptions {
+                html_safe: true,
+                escape_non_ascii: true,
+            },
+            ..Default::default()
+        };
+        let text = to_string_with_options(&value, options).unwrap();
+        assert_eq!(
+            text,
+            "{\"cl\\u00e9\":\"\\u003c/script\\u003e \\ud83d\\ude00\"}"
+        );
+        assert!(text.is_ascii());
+        assert_eq!(from_str(&text).unwrap(), value);
+    }
+
+    // test the writing with a custom formatter
+    #[test]
+    fn test_to_string_with_formatter() {
+        /// Compact with a space after commas and colons.
+        struct Spaced;
+
+        impl Formatter for Spaced {
+            fn begin_array_value<W: Write + ?Sized>(
+                &mut self,
+                out: &mut W,
+                first: bool,
+            ) -> fmt::Result {
+                out.write_str(if first { "" } else { ", " })
+            }
+
+            fn begin_object_key<W: Write + ?Sized>(
+                &mut self,
+                out: &mut W,
+                first: bool,
+            ) -> fmt::Result {
+                out.write_str(if first { "" } else { ", " })
+            }
+
+            fn begin_object_value<W: Write + ?Sized>(&mut self, out: &mut W) -> fmt::Result {
+                out.write_str(": ")
+            }
+        }
+
+        let value = from_str("{\"a\": [1, [], {}], \"b\": {\"c\": null}}").unwrap();
+        assert_eq!(
+            to_string_with_formatter(&value, &mut Spaced),
+            "{\"a\": [1, [], {}], \"b\": {\"c\": null}}"
+        );
+        let mut out = Vec::new();
+        to_writer_with_formatter(&mut out, &value, &mut Spaced).unwrap();
+        assert_eq!(
+            out,
+            to_string_with_formatter(&value, &mut Spaced).as_bytes()
+        );
+    }
+
+    // test the serialization into bytes
+    #[test]
+    fn test_to_vec() {
+        let value = from_str("{\"a\": [1, \"é\"]}").unwrap();
+        assert_eq!(to_vec(&value), to_string(&value).into_bytes());
+        assert_eq!(to_vec_pretty(&value), to_string_pretty(&value).into_bytes());
+    }
+
+    // test that numbers read back as they were written
+    #[test]
+    fn test_number_round_trip() {
+        let numbers = [
+            "0.1",
+            "0.30000000000000004",
+            "1.0",
+            "-0.0",
+            "123.456",
+            "1e16",
+            "1.7976931348623157e308",
+            "5e-324",
+            "2.2250738585072014e-308",
+            "1e-7",
+            "9007199254740993",
+            "18446744073709551615",
+            "-9223372036854775808",
+        ];
+        for number in numbers {
+            let value = from_str(number).unwrap();
+            assert_eq!(to_string(&value), number);
+            assert_eq!(from_str(&to_string(&value)).unwrap(), value);
+        }
+        assert_eq!(to_string(&JsonValue::from(1.0 / 3.0)), "0.3333333333333333");
+        assert_eq!(to_string(&from_str("1E+2").unwrap()), "100.0");
+    }
+
+    // test the policies for numbers without a JSON form
+    #[test]
+    fn test_non_finite() {
+        let options = ParserOptions {
+            allow_nan: true,
+            ..Default::default()
+        };
+        let value = from_str_with_options("[NaN, Infinity, -Infinity, 1]", options).unwrap();
+        let with = |non_finite| SerializerOptions {
+            non_finite,
+            ..Default::default()
+        };
+        assert_eq!(to_string(&value), "[null,null,null,1]");
+        let literal = to_string_with_options(&value, with(NonFinite::Literal)).unwrap();
+        assert_eq!(literal, "[NaN,Infinity,-Infinity,1]");
+        assert_eq!(from_str_with_options(&literal, options).unwrap(), value);
+        assert_eq!(
+            to_string_with_options(&value, with(NonFinite::Error)),
+            Err(SerializeError::NonFinite)
+        );
+        assert_eq!(
+            to_vec_with_options(&value, with(NonFinite::Error)),
+            Err(SerializeError::NonFinite)
+        );
+        let error = to_writer_with_options(Vec::new(), &value, with(NonFinite::Error)).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        let raw = ParserOptions {
+            arbitrary_precision: true,
+            ..options
+        };
+        let value = from_str_with_options("[-Infinity, 1e400]", raw).unwrap();
+        let literal = to_string_with_options(&value, with(NonFinite::Literal)).unwrap();
+        assert_eq!(literal, "[-Infinity,1e400]");
+    }
+}
@@ -0,0 +1,189 @@
+use std::fmt;
+
+use serde::de::{Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+use crate::{JsonValue, Number, Shared, Value, N};
+
+/// Objects are serialized as maps with their members in document order,
+/// duplicate keys included, it is up to the format what to make of them.
+impl Serialize for JsonValue<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            JsonValue::String(s) => serializer.serialize_str(s),
+            JsonValue::Number(n) => n.serialize(serializer),
+            JsonValue::Object(pairs) => {
+                let mut map = serializer.serialize_map(Some(pairs.len()))?;
+                for (key, value) in pairs.iter() {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            }
+            JsonValue::List(values) => serializer.collect_seq(values.iter()),
+            JsonValue::Boolean(b) => serializer.serialize_bool(*b),
+            JsonValue::Null => serializer.serialize_unit(),
+        }
+    }
+}
+
+/// Integers beyond 64 bits, kept in arbitrary precision mode, go out as
+/// 128 bit ones when they fit. `NaN` and the infinities are passed on as
+/// floats, `serde_json` writes them as `null`.
+impl Serialize for Number<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match &self.0 {
+            N::PosInt(n) => serializer.serialize_u64(*n),
+            N::NegInt(n) => serializer.serialize_i64(*n),
+            N::Float(n) => serializer.serialize_f64(*n),
+            N::Raw(text) => match (self.resolve(), text.parse(), text.parse()) {
+                (N::Float(_), Ok(n), _) => serializer.serialize_u128(n),
+                (N::Float(_), _, Ok(n)) => serializer.serialize_i128(n),
+                (n, _, _) => Number(n).serialize(serializer),
+            },
+        }
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("any value")
+    }
+
+    fn visit_bool<E>(self, b: bool) -> Result<Value, E> {
+        Ok(JsonValue::Boolean(b))
+    }
+
+    fn visit_i64<E>(self, n: i64) -> Result<Value, E> {
+        Ok(JsonValue::from(n))
+    }
+
+    fn visit_u64<E>(self, n: u64) -> Result<Value, E> {
+        Ok(JsonValue::from(n))
+    }
+
+    /// Integers beyond 64 bits are kept as their text, the way arbitrary
+    /// precision mode does.
+    fn visit_i128<E>(self, n: i128) -> Result<Value, E> {
+        Ok(match i64::try_from(n) {
+            Ok(n) => JsonValue::from(n),
+            Err(_) => JsonValue::Number(Number(N::Raw(n.to_string().into()))),
+        })
+    }
+
+    fn visit_u128<E>(self, n: u128) -> Result<Value, E> {
+        Ok(match u64::try_from(n) {
+            Ok(n) => JsonValue::from(n),
+            Err(_) => JsonValue::Number(Number(N::Raw(n.to_string().into()))),
+        })
+    }
+
+    fn visit_f64<E>(self, n: f64) -> Result<Value, E> {
+        Ok(JsonValue::from(n))
+    }
+
+    fn visit_str<E>(self, s: &str) -> Result<Value, E> {
+        Ok(JsonValue::from(s.to_owned()))
+    }
+
+    fn visit_string<E>(self, s: String) -> Result<Value, E> {
+        Ok(JsonValue::from(s))
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(JsonValue::Null)
+    }
+
+    fn visit_none<E>(self) -> Result<Value, E> {
+        Ok(JsonValue::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        Value::deserialize(deserializer)
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Value, D::Error> {
+        Value::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut values = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(value) = seq.next_element()? {
+            values.push(value);
+        }
+        Ok(JsonValue::List(Shared::new(values)))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut pairs = Vec::with_capacity(map.size_hint().unwrap_or(0));
+        while let Some((key, value)) = map.next_entry::<String, Value>()? {
+            pairs.push((key.into(), value));
+        }
+        Ok(JsonValue::Object(Shared::new(pairs)))
+    }
+}
+
+/// Only the owned value, as formats in general can't lend out their strings.
+/// Map keys have to be strings, members are kept in the order the format
+/// gives them.
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{from_str, from_str_with_options, ParserOptions, Value};
+
+    // test the values serialized by serde_json
+    #[test]
+    fn test_serialize() {
+        let input = "{\"a\":[1,-2,2.5,\"x\\n\"],\"b\":{\"c\":null,\"c\":true},\"d\":1e400}";
+        let value = from_str(input).unwrap();
+        assert_eq!(
+            serde_json::to_string(&value).unwrap(),
+            "{\"a\":[1,-2,2.5,\"x\\n\"],\"b\":{\"c\":null,\"c\":true},\"d\":null}"
+        );
+
+        let options = ParserOptions {
+            arbitrary_precision: true,
+            ..Default::default()
+        };
+        let value = from_str_with_options("[18446744073709551616, -1, 1e3]", options).unwrap();
+        assert_eq!(
+            serde_json::to_string(&value).unwrap(),
+            "[18446744073709551616,-1,1000.0]"
+        );
+    }
+
+    // test the values deserialized from serde_json
+    #[test]
+    fn test_deserialize() {
+        let input = "{\"b\": [1, -2, 2.5, \"x\\n\", null], \"a\": {\"c\": true}}";
+        let value: Value = serde_json::from_str(input).unwrap();
+        assert_eq!(value, from_str(input).unwrap());
+        assert_eq!(
+            value.to_string(),
+            "{\"b\":[1,-2,2.5,\"x\\n\",null],\"a\":{\"c\":true}}"
+        );
+
+        let value: Value =
+            serde_json::from_str("[18446744073709551615, -9223372036854775808]").unwrap();
+        assert_eq!(
+            value.pointer("/0").and_then(|n| n.try_as::<u64>().ok()),
+            Some(u64::MAX)
+        );
+        assert_eq!(
+            value.pointer("/1").and_then(|n| n.try_as::<i64>().ok()),
+            Some(i64::MIN)
+        );
+        assert!(serde_json::from_str::<Value>("[1,").is_err());
+    }
+}
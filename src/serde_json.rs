@@ -0,0 +1,163 @@
+use crate::{JsonValue, Member, Number, SerializeError, Shared, Value, N};
+
+/// A container being converted by `From<serde_json::Value>`, with the values
+/// left to convert and those converted, objects along with the key of the
+/// value being converted.
+enum Converting {
+    List(std::vec::IntoIter<serde_json::Value>, Vec<Value>),
+    Object(serde_json::map::IntoIter, Vec<Member<'static>>, String),
+}
+
+fn number(n: serde_json::Number) -> Number<'static> {
+    match (n.as_u64(), n.as_i64(), n.as_f64()) {
+        (Some(n), _, _) => n.into(),
+        (_, Some(n), _) => n.into(),
+        (_, _, Some(n)) => n.into(),
+        // only with its `arbitrary_precision` feature on
+        _ => Number(N::Raw(n.to_string().into())),
+    }
+}
+
+/// Numbers become whichever of `u64`, `i64` and `f64` holds them, members
+/// come in the order `serde_json` keeps them in, sorted by key unless its
+/// `preserve_order` feature is on. Containers are kept on an explicit stack,
+/// so that values of any depth get converted.
+impl From<serde_json::Value> for Value {
+    fn from(value: serde_json::Value) -> Self {
+        let mut stack: Vec<Converting> = Vec::new();
+        let mut value = value;
+        loop {
+            let mut converted = match value {
+                serde_json::Value::Array(values) => {
+                    let converted = Vec::with_capacity(values.len());
+                    stack.push(Converting::List(values.into_iter(), converted));
+                    None
+                }
+                serde_json::Value::Object(map) => {
+                    let converted = Vec::with_capacity(map.len());
+                    stack.push(Converting::Object(
+                        map.into_iter(),
+                        converted,
+                        String::new(),
+                    ));
+                    None
+                }
+                serde_json::Value::String(s) => Some(JsonValue::String(s.into())),
+                serde_json::Value::Number(n) => Some(JsonValue::Number(number(n))),
+                serde_json::Value::Bool(b) => Some(JsonValue::Boolean(b)),
+                serde_json::Value::Null => Some(JsonValue::Null),
+            };
+            // hand the converted value over to its container, completing
+            // those that have all of their values
+            loop {
+                match stack.last_mut() {
+                    None => return converted.expect("a converted value"),
+                    Some(Converting::List(values, converted_values)) => {
+                        converted_values.extend(converted.take());
+                        if let Some(next) = values.next() {
+                            value = next;
+                            break;
+                        }
+                    }
+                    Some(Converting::Object(map, converted_pairs, key)) => {
+                        if let Some(converted) = converted.take() {
+                            converted_pairs.push((std::mem::take(key).into(), converted));
+                        }
+                        if let Some((next_key, next)) = map.next() {
+                            *key = next_key;
+                            value = next;
+                            break;
+                        }
+                    }
+                }
+                converted = Some(match stack.pop() {
+                    Some(Converting::List(_, values)) => JsonValue::List(Shared::new(values)),
+                    Some(Converting::Object(_, pairs, _)) => JsonValue::Object(Shared::new(pairs)),
+                    None => unreachable!("a container was on the stack"),
+                });
+            }
+        }
+    }
+}
+
+/// Fails on `NaN` and the infinities, `serde_json` numbers can't hold them.
+/// Integers beyond 64 bits become floats, as `serde_json` parses them, and
+/// of repeated keys the last one wins. Converts values of any depth, mind
+/// that `serde_json` drops its values recursively.
+impl TryFrom<&JsonValue<'_>> for serde_json::Value {
+    type Error = SerializeError;
+
+    fn try_from(value: &JsonValue) -> Result<Self, SerializeError> {
+        value.fold(
+            |value| {
+                Ok(match value {
+                    JsonValue::String(s) => serde_json::Value::String(s.to_string()),
+                    JsonValue::Number(n) => serde_json::Value::Number(match n.resolve() {
+                        N::PosInt(n) => n.into(),
+                        N::NegInt(n) => n.into(),
+                        N::Float(n) => {
+                            serde_json::Number::from_f64(n).ok_or(SerializeError::NonFinite)?
+                        }
+                        N::Raw(_) => unreachable!(),
+                    }),
+                    JsonValue::Boolean(b) => serde_json::Value::Bool(*b),
+                    _ => serde_json::Value::Null,
+                })
+            },
+            |values| Ok(serde_json::Value::Array(values)),
+            |pairs, values| {
+                let keys = pairs.iter().map(|(key, _)| key.to_string());
+                Ok(serde_json::Value::Object(keys.zip(values).collect()))
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{from_str, from_str_with_options, ParserOptions};
+
+    // test the conversions both ways
+    #[test]
+    fn test_serde_json_value() {
+        let input = "{\"a\": [1, -2, 2.5, 3.0, \"x\\n\"], \"b\": {\"c\": null, \"d\": true}}";
+        let value = from_str(input).unwrap();
+        let converted = serde_json::Value::try_from(&value).unwrap();
+        assert_eq!(
+            converted,
+            serde_json::from_str::<serde_json::Value>(input).unwrap()
+        );
+        assert!(converted["a"][3].is_f64());
+        assert_eq!(Value::from(converted), value);
+
+        let options = ParserOptions {
+            allow_nan: true,
+            ..Default::default()
+        };
+        let value = from_str_with_options("[NaN]", options).unwrap();
+        assert_eq!(
+            serde_json::Value::try_from(&value),
+            Err(SerializeError::NonFinite)
+        );
+        let value = from_str("{\"a\": 1, \"a\": 18446744073709551616}").unwrap();
+        assert_eq!(
+            serde_json::Value::try_from(&value).unwrap().to_string(),
+            "{\"a\":1.8446744073709552e19}"
+        );
+    }
+
+    // test the conversions of values too deep to recurse into
+    #[test]
+    fn test_serde_json_value_deep() {
+        let depth = 100_000;
+        let input = format!("{}{{\"a\": 1}}{}", "[".repeat(depth), "]".repeat(depth));
+        let value = from_str(&input).unwrap();
+        // converted back right away, `serde_json` would drop the deep value
+        // recursively
+        let converted = Value::from(serde_json::Value::try_from(&value).unwrap());
+        assert_eq!(converted, value);
+        converted.drop_iteratively();
+        value.drop_iteratively();
+    }
+}
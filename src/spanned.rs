@@ -0,0 +1,48 @@
+use std::ops::Range;
+
+use crate::{find_structurals, from_str, JsonValue, ParserError, Structural};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spanned<'input> {
+    pub value: JsonValue<'input>,
+    /// Byte ranges of the strings and object keys in the input, quotes
+    /// included, in the order they appear in the document. That is the order
+    /// of a depth first walk of `value` visiting each key before its value.
+    pub strings: Vec<Range<usize>>,
+}
+
+/// Parses the input keeping track of where each string came from, so that
+/// the original text can be highlighted or patched without searching for it.
+pub fn parse_spanned(input: &str) -> Result<Spanned<'_>, ParserError> {
+    let value = from_str(input)?;
+    // the input is valid, so its quotes pair up one string after another
+    let quotes: Vec<_> = find_structurals(input)
+        .filter(|(_, structural)| *structural == Structural::Quote)
+        .map(|(position, _)| position)
+        .collect();
+    let strings = quotes
+        .chunks_exact(2)
+        .map(|pair| pair[0]..pair[1] + 1)
+        .collect();
+    Ok(Spanned { value, strings })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // test the string spans
+    #[test]
+    fn test_parse_spanned() {
+        let input = "{\"a\": [\"b\\\"c\", 1], \"d\": \"\"}";
+        let spanned = parse_spanned(input).unwrap();
+        assert_eq!(spanned.value, from_str(input).unwrap());
+        let strings: Vec<_> = spanned
+            .strings
+            .iter()
+            .map(|span| &input[span.clone()])
+            .collect();
+        assert_eq!(strings, vec!["\"a\"", "\"b\\\"c\"", "\"d\"", "\"\""]);
+        assert!(parse_spanned("[\"a\"").is_err());
+    }
+}
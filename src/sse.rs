@@ -0,0 +1,180 @@
+use std::io::BufRead;
+
+use crate::{from_str, JsonValue, ParserError};
+
+/// A single Server-Sent Event, `data` lines are joined with newlines.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SseEvent {
+    pub event: Option<String>,
+    pub data: String,
+}
+
+impl SseEvent {
+    pub fn value(&self) -> Result<JsonValue<'_>, ParserError> {
+        from_str(&self.data)
+    }
+}
+
+#[derive(Debug, Default)]
+struct EventBuilder {
+    event: Option<String>,
+    data: Option<String>,
+}
+
+impl EventBuilder {
+    fn line(&mut self, line: &str) -> Option<SseEvent> {
+        let line = line.strip_suffix('\n').unwrap_or(line);
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        if line.is_empty() {
+            let event = self.event.take();
+            return self.data.take().map(|data| SseEvent { event, data });
+        }
+        let (field, value) = line.split_once(':').unwrap_or((line, ""));
+        let value = value.strip_prefix(' ').unwrap_or(value);
+        match field {
+            "data" => match &mut self.data {
+                Some(data) => {
+                    data.push('\n');
+                    data.push_str(value);
+                }
+                None => self.data = Some(value.to_string()),
+            },
+            "event" => self.event = Some(value.to_string()),
+            // comments, ids and retry hints don't affect the payload
+            _ => {}
+        }
+        None
+    }
+}
+
+/// Reads events from a blocking stream.
+pub struct SseReader<R> {
+    reader: R,
+    line: String,
+    builder: EventBuilder,
+}
+
+impl<R: BufRead> SseReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            line: String::new(),
+            builder: EventBuilder::default(),
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for SseReader<R> {
+    type Item = std::io::Result<SseEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.line.clear();
+            match self.reader.read_line(&mut self.line) {
+                Err(error) => return Some(Err(error)),
+                // the stream ending acts as a blank line
+                Ok(0) => return self.builder.line("").map(Ok),
+                Ok(_) => {
+                    if let Some(event) = self.builder.line(&self.line) {
+                        return Some(Ok(event));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Decodes events from chunks of bytes as they arrive, for use with any async
+/// runtime: feed it with `push` and drain complete events with `next_event`.
+#[derive(Debug, Default)]
+pub struct SseDecoder {
+    pending: Vec<u8>,
+    builder: EventBuilder,
+}
+
+impl SseDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, chunk: &[u8]) {
+        self.pending.extend_from_slice(chunk);
+    }
+
+    /// The next complete event, `None` when more input is needed.
+    pub fn next_event(&mut self) -> Option<SseEvent> {
+        while let Some(end) = self.pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=end).collect();
+            if let Some(event) = self.builder.line(&String::from_utf8_lossy(&line)) {
+                return Some(event);
+            }
+        }
+        None
+    }
+
+    /// Flushes the last event once the stream is over.
+    pub fn finish(&mut self) -> Option<SseEvent> {
+        let line = String::from_utf8_lossy(&std::mem::take(&mut self.pending)).into_owned();
+        match self.builder.line(&line) {
+            Some(event) => Some(event),
+            None => self.builder.line(""),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STREAM: &str = ": keep-alive\nevent: delta\ndata: {\"text\":\ndata:  \"hi\"}\n\ndata: [1, 2]\r\n\r\nid: 3\n\ndata: null";
+
+    // test the blocking reader
+    #[test]
+    fn test_sse_reader() {
+        let events: Vec<_> = SseReader::new(STREAM.as_bytes())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            events,
+            vec![
+                SseEvent {
+                    event: Some("delta".to_string()),
+                    data: "{\"text\":\n \"hi\"}".to_string()
+                },
+                SseEvent {
+                    event: None,
+                    data: "[1, 2]".to_string()
+                },
+                SseEvent {
+                    event: None,
+                    data: "null".to_string()
+                },
+            ]
+        );
+        assert_eq!(
+            events[0].value().unwrap(),
+            JsonValue::Object(crate::Shared::new(vec![(
+                "text".into(),
+                JsonValue::String("hi".into())
+            )]))
+        );
+    }
+
+    // test the push based decoder
+    #[test]
+    fn test_sse_decoder() {
+        let mut decoder = SseDecoder::new();
+        let mut events = Vec::new();
+        for chunk in STREAM.as_bytes().chunks(5) {
+            decoder.push(chunk);
+            while let Some(event) = decoder.next_event() {
+                events.push(event);
+            }
+        }
+        events.extend(decoder.finish());
+        let expected: Vec<_> = SseReader::new(STREAM.as_bytes())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(events, expected);
+    }
+}
@@ -0,0 +1,89 @@
+use crate::{json_value, whitespace, JsonValue, Parser, ParserError, ParserOptions, State};
+
+/// Parses back to back documents, such as `{"a":1}{"b":2}` or whitespace
+/// separated ones, yielding one value at a time. Iteration stops after the
+/// first error, as there is no telling where the next document starts.
+pub fn from_str_values(input: &str) -> Values<'_> {
+    from_str_values_with_options(input, ParserOptions::default())
+}
+
+pub fn from_str_values_with_options(input: &str, options: ParserOptions) -> Values<'_> {
+    Values {
+        input,
+        options,
+        position: 0,
+        failed: false,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Values<'input> {
+    input: &'input str,
+    options: ParserOptions,
+    position: usize,
+    failed: bool,
+}
+
+impl<'input> Values<'input> {
+    /// Offset of the first byte not consumed yet.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+impl<'input> Iterator for Values<'input> {
+    type Item = Result<JsonValue<'input>, ParserError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let state = State {
+            current: self.position,
+        };
+        let (_, state) = whitespace(self.options).parse(self.input, state).ok()?;
+        self.position = state.current;
+        if self.position == self.input.len() {
+            return None;
+        }
+        match json_value(self.options).parse(self.input, state) {
+            Ok((value, state)) => {
+                self.position = state.current;
+                Some(Ok(value))
+            }
+            Err(error) => {
+                self.failed = true;
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::from_str;
+
+    // test the concatenated documents
+    #[test]
+    fn test_from_str_values() {
+        let input = "{\"a\":1}{\"b\":2}\n[3] \"four\"5 ";
+        let values: Vec<_> = from_str_values(input).collect();
+        assert_eq!(
+            values,
+            vec![
+                from_str("{\"a\":1}"),
+                from_str("{\"b\":2}"),
+                from_str("[3]"),
+                from_str("\"four\""),
+                from_str("5"),
+            ]
+        );
+
+        let mut values = from_str_values("[1] [2, ] [3]");
+        assert!(values.next().unwrap().is_ok());
+        assert!(values.next().unwrap().is_err());
+        assert_eq!(values.next(), None);
+        assert_eq!(from_str_values("  ").count(), 0);
+    }
+}
@@ -0,0 +1,93 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Structural {
+    ObjectStart,
+    ObjectEnd,
+    ListStart,
+    ListEnd,
+    Colon,
+    Comma,
+    /// Either quote of a string, the ones escaped within strings don't count.
+    Quote,
+}
+
+/// Byte offsets of the structural characters of the input. Characters within
+/// strings are skipped, the input doesn't have to be valid JSON and nothing
+/// gets allocated, which makes it a cheap first pass for splitting and
+/// indexing large documents.
+pub fn find_structurals(input: &str) -> Structurals<'_> {
+    Structurals {
+        input: input.as_bytes(),
+        position: 0,
+        in_string: false,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Structurals<'input> {
+    input: &'input [u8],
+    position: usize,
+    in_string: bool,
+}
+
+impl<'input> Iterator for Structurals<'input> {
+    type Item = (usize, Structural);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(&byte) = self.input.get(self.position) {
+            let position = self.position;
+            self.position += 1;
+            if self.in_string {
+                match byte {
+                    b'\\' => self.position += 1,
+                    b'"' => {
+                        self.in_string = false;
+                        return Some((position, Structural::Quote));
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+            let structural = match byte {
+                b'{' => Structural::ObjectStart,
+                b'}' => Structural::ObjectEnd,
+                b'[' => Structural::ListStart,
+                b']' => Structural::ListEnd,
+                b':' => Structural::Colon,
+                b',' => Structural::Comma,
+                b'"' => {
+                    self.in_string = true;
+                    Structural::Quote
+                }
+                _ => continue,
+            };
+            return Some((position, structural));
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // test the structural scan
+    #[test]
+    fn test_find_structurals() {
+        use Structural::*;
+        assert_eq!(
+            find_structurals("{\"a\\\"{\": [1, \"]\\\\\"]}").collect::<Vec<_>>(),
+            vec![
+                (0, ObjectStart),
+                (1, Quote),
+                (6, Quote),
+                (7, Colon),
+                (9, ListStart),
+                (11, Comma),
+                (13, Quote),
+                (17, Quote),
+                (18, ListEnd),
+                (19, ObjectEnd),
+            ]
+        );
+    }
+}
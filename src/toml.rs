@@ -0,0 +1,207 @@
+use crate::{JsonValue, Member, Number, Shared, Value, N};
+
+#[derive(Debug, thiserror::Error)]
+pub enum TomlError {
+    #[error(transparent)]
+    Parse(#[from] toml::de::Error),
+    #[error(transparent)]
+    Serialize(#[from] toml::ser::Error),
+    #[error("Null has no TOML counterpart")]
+    Null,
+    #[error("TOML documents have to be tables")]
+    NotATable,
+    #[error("Number has no exact TOML counterpart")]
+    Number,
+}
+
+/// An array or table being converted by `from_toml`, with the values left to
+/// convert and those converted, tables along with the key of the value being
+/// converted.
+enum Converting<'toml> {
+    Array(std::slice::Iter<'toml, toml::Value>, Vec<Value>),
+    Table(toml::map::Iter<'toml>, Vec<Member<'static>>, &'toml str),
+}
+
+/// Fails on null and on numbers TOML can't hold exactly, integers beyond 64
+/// signed bits and raw numbers that `f64` would round. Converts values of any
+/// depth, mind that `toml` drops its values recursively.
+pub fn to_toml(value: &JsonValue) -> Result<toml::Value, TomlError> {
+    value.fold(
+        |value| {
+            Ok(match value {
+                JsonValue::String(s) => toml::Value::String(s.to_string()),
+                JsonValue::Number(n) => number(n)?,
+                JsonValue::Boolean(b) => toml::Value::Boolean(*b),
+                _ => return Err(TomlError::Null),
+            })
+        },
+        |values| Ok(toml::Value::Array(values)),
+        |pairs, values| {
+            let keys = pairs.iter().map(|(key, _)| key.to_string());
+            Ok(toml::Value::Table(keys.zip(values).collect()))
+        },
+    )
+}
+
+fn number(n: &Number) -> Result<toml::Value, TomlError> {
+    let resolved = Number(n.resolve());
+    if n.as_str().is_some() && resolved != *n {
+        return Err(TomlError::Number);
+    }
+    match resolved.0 {
+        N::PosInt(n) => Ok(toml::Value::Integer(
+            i64::try_from(n).map_err(|_| TomlError::Number)?,
+        )),
+        N::NegInt(n) => Ok(toml::Value::Integer(n)),
+        N::Float(n) => Ok(toml::Value::Float(n)),
+        N::Raw(_) => unreachable!("resolved"),
+    }
+}
+
+/// Converts a TOML value, strings are copied out of it. Datetimes become
+/// strings in their RFC 3339 form, arrays of tables become lists of objects.
+/// Arrays and tables are kept on an explicit stack, so that values of any
+/// depth get converted.
+pub fn from_toml(value: &toml::Value) -> Value {
+    let mut stack: Vec<Converting> = Vec::new();
+    let mut value = value;
+    loop {
+        let mut converted = match value {
+            toml::Value::String(s) => Some(JsonValue::String(s.clone().into())),
+            toml::Value::Integer(n) => Some(JsonValue::Number((*n).into())),
+            toml::Value::Float(n) => Some(JsonValue::Number((*n).into())),
+            toml::Value::Boolean(b) => Some(JsonValue::Boolean(*b)),
+            toml::Value::Datetime(datetime) => Some(JsonValue::String(datetime.to_string().into())),
+            toml::Value::Array(values) => {
+                let converted = Vec::with_capacity(values.len());
+                stack.push(Converting::Array(values.iter(), converted));
+                None
+            }
+            toml::Value::Table(table) => {
+                let converted = Vec::with_capacity(table.len());
+                stack.push(Converting::Table(table.iter(), converted, ""));
+                None
+            }
+        };
+        // hand the converted value over to its container, completing those
+        // that have all of their values
+        loop {
+            match stack.last_mut() {
+                None => return converted.expect("a converted value"),
+                Some(Converting::Array(values, converted_values)) => {
+                    converted_values.extend(converted.take());
+                    if let Some(next) = values.next() {
+                        value = next;
+                        break;
+                    }
+                }
+                Some(Converting::Table(table, converted_pairs, key)) => {
+                    if let Some(converted) = converted.take() {
+                        converted_pairs.push((key.to_string().into(), converted));
+                    }
+                    if let Some((next_key, next)) = table.next() {
+                        *key = next_key;
+                        value = next;
+                        break;
+                    }
+                }
+            }
+            converted = Some(match stack.pop() {
+                Some(Converting::Array(_, values)) => JsonValue::List(Shared::new(values)),
+                Some(Converting::Table(_, pairs, _)) => JsonValue::Object(Shared::new(pairs)),
+                None => unreachable!("a container was on the stack"),
+            });
+        }
+    }
+}
+
+pub fn from_toml_str(input: &str) -> Result<Value, TomlError> {
+    let table: toml::Table = toml::from_str(input)?;
+    Ok(from_toml(&toml::Value::Table(table)))
+}
+
+pub fn to_toml_string(value: &JsonValue) -> Result<String, TomlError> {
+    match to_toml(value)? {
+        toml::Value::Table(table) => Ok(toml::to_string(&table)?),
+        _ => Err(TomlError::NotATable),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // test the toml conversion
+    #[test]
+    fn test_from_toml_str() {
+        let input = "[package]\nname = \"ujson\"\n\n[[bin]]\nname = \"ours\"\n\n[[bin]]\nname = \"serde\"\n\n[release]\ndate = 2024-06-14T10:00:00Z\n";
+        let value = from_toml_str(input).unwrap();
+        let expected = crate::from_str(
+            "{\"bin\": [{\"name\": \"ours\"}, {\"name\": \"serde\"}], \"package\": {\"name\": \"ujson\"}, \"release\": {\"date\": \"2024-06-14T10:00:00Z\"}}",
+        )
+        .unwrap();
+        assert_eq!(value, expected);
+    }
+
+    // test the toml serialization
+    #[test]
+    fn test_to_toml_string() {
+        let value =
+            crate::from_str("{\"name\": \"ujson\", \"version\": 1, \"ratio\": 0.5}").unwrap();
+        assert_eq!(
+            to_toml_string(&value).unwrap(),
+            "name = \"ujson\"\nratio = 0.5\nversion = 1\n"
+        );
+        let value = crate::from_str("{\"missing\": null}").unwrap();
+        assert!(matches!(to_toml_string(&value), Err(TomlError::Null)));
+        let value = crate::from_str("[1]").unwrap();
+        assert!(matches!(to_toml_string(&value), Err(TomlError::NotATable)));
+        let value = crate::from_str("{\"n\": 9223372036854775808}").unwrap();
+        assert!(matches!(to_toml_string(&value), Err(TomlError::Number)));
+        let options = crate::ParserOptions {
+            arbitrary_precision: true,
+            ..Default::default()
+        };
+        let value = crate::from_str_with_options(
+            "{\"a\": -9223372036854775808, \"b\": 0.10, \"c\": 1e23}",
+            options,
+        )
+        .unwrap();
+        assert_eq!(
+            to_toml_string(&value).unwrap(),
+            "a = -9223372036854775808\nb = 0.1\nc = 100000000000000000000000.0\n"
+        );
+        for n in ["12345678901234567890123", "0.30000000000000000001", "1e400"] {
+            let input = format!("{{\"n\": {n}}}");
+            let value = crate::from_str_with_options(&input, options).unwrap();
+            assert!(
+                matches!(to_toml_string(&value), Err(TomlError::Number)),
+                "{n}"
+            );
+        }
+    }
+
+    // test the conversions of values too deep to recurse into
+    #[test]
+    fn test_toml_deep() {
+        let depth = 100_000;
+        let input = format!("{}{{\"a\": 1}}{}", "[".repeat(depth), "]".repeat(depth));
+        let value = crate::from_str(&input).unwrap();
+        let converted = to_toml(&value).unwrap();
+        let back = from_toml(&converted);
+        assert_eq!(back, value);
+        back.drop_iteratively();
+        value.drop_iteratively();
+        // `toml` would drop the deep value recursively
+        let mut pending = vec![converted];
+        while let Some(converted) = pending.pop() {
+            match converted {
+                toml::Value::Array(values) => pending.extend(values),
+                toml::Value::Table(table) => {
+                    pending.extend(table.into_iter().map(|(_, value)| value))
+                }
+                _ => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,253 @@
+use crate::{from_str, JsonValue, ParserError};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Truncated<'input> {
+    pub value: JsonValue<'input>,
+    /// Offset of the first byte that didn't make it into `value`, `None` if the
+    /// document was complete.
+    pub truncated_at: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Expect {
+    Value,
+    ValueOrEnd,
+    Key,
+    KeyOrEnd,
+    Colon,
+    CommaOrEnd,
+    Done,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Frame {
+    closer: Option<char>,
+    expect: Expect,
+}
+
+impl Frame {
+    fn complete_value(&mut self) {
+        self.expect = match self.closer {
+            Some(_) => Expect::CommaOrEnd,
+            None => Expect::Done,
+        };
+    }
+}
+
+fn is_plausible_scalar_prefix(word: &str) -> bool {
+    ["true", "false", "null"]
+        .iter()
+        .any(|literal| literal.starts_with(word))
+        || word
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E'))
+}
+
+/// Scans a document that ends prematurely and finds the longest prefix of it
+/// that can be turned into a complete document by closing open containers.
+/// Returns the length of the prefix together with the missing closing brackets,
+/// `None` if the input doesn't look like a truncated document.
+fn closable_prefix(input: &str) -> Option<(usize, String)> {
+    let mut frames = vec![Frame {
+        closer: None,
+        expect: Expect::Value,
+    }];
+    // the closing brackets of the open containers, outermost first, and at
+    // every cut how many of them were open
+    let mut closers = String::new();
+    let mut cut = None;
+    let mut chars = input.char_indices().peekable();
+
+    while let Some((index, c)) = chars.next() {
+        let frame = frames.last_mut()?;
+        let expects_value = matches!(frame.expect, Expect::Value | Expect::ValueOrEnd);
+        let expects_key = matches!(frame.expect, Expect::Key | Expect::KeyOrEnd);
+        match c {
+            c if c.is_whitespace() => continue,
+            '{' | '[' if expects_value => {
+                let (closer, expect) = match c {
+                    '{' => ('}', Expect::KeyOrEnd),
+                    _ => (']', Expect::ValueOrEnd),
+                };
+                frames.push(Frame {
+                    closer: Some(closer),
+                    expect,
+                });
+                closers.push(closer);
+                cut = Some((index + 1, closers.len()));
+            }
+            '}' | ']'
+                if frame.closer == Some(c)
+                    && matches!(
+                        frame.expect,
+                        Expect::KeyOrEnd | Expect::ValueOrEnd | Expect::CommaOrEnd
+                    ) =>
+            {
+                frames.pop();
+                frames.last_mut()?.complete_value();
+                closers.pop();
+                cut = Some((index + 1, closers.len()));
+            }
+            ',' if frame.expect == Expect::CommaOrEnd => {
+                frame.expect = match frame.closer {
+                    Some('}') => Expect::Key,
+                    _ => Expect::Value,
+                };
+            }
+            ':' if frame.expect == Expect::Colon => frame.expect = Expect::Value,
+            '"' if expects_value || expects_key => {
+                let mut escaped = false;
+                let end = chars.find(|&(_, c)| {
+                    let is_end = c == '"' && !escaped;
+                    escaped = c == '\\' && !escaped;
+                    is_end
+                });
+                match end {
+                    // the input ends inside of this string
+                    None => break,
+                    Some(_) if expects_key => frame.expect = Expect::Colon,
+                    Some((end, _)) => {
+                        frame.complete_value();
+                        cut = Some((end + 1, closers.len()));
+                    }
+                }
+            }
+            _ if expects_value => {
+                let mut end = index + c.len_utf8();
+                while let Some(&(next, c)) = chars.peek() {
+                    if c.is_whitespace() || "{}[],:\"".contains(c) {
+                        break;
+                    }
+                    end = next + c.len_utf8();
+                    chars.next();
+                }
+                if chars.peek().is_none() {
+                    // the input ends inside of this scalar
+                    if !is_plausible_scalar_prefix(&input[index..end]) {
+                        return None;
+                    }
+                    break;
+                }
+                frame.complete_value();
+                cut = Some((end, closers.len()));
+            }
+            _ => return None,
+        }
+    }
+
+    let (cut, open) = cut?;
+    Some((cut, closers[..open].chars().rev().collect()))
+}
+
+/// Parses a document that may have been cut off, for instance by a crashed
+/// writer. Open containers get closed, trailing incomplete scalars and object
+/// keys are dropped.
+pub fn from_str_truncated(input: &str) -> Result<Truncated<'_>, ParserError> {
+    let error = match from_str(input) {
+        Ok(value) => {
+            return Ok(Truncated {
+                value,
+                truncated_at: None,
+            })
+        }
+        Err(error) => error,
+    };
+    let (cut, closing) = closable_prefix(input).ok_or(error.clone())?;
+    let completed = format!("{}{}", &input[..cut], closing);
+    let value = from_str(&completed).map_err(|_| error)?;
+    // the completed buffer starts with a copy of the input, every string of
+    // the value lies within that copy, so that it can be rebased onto the
+    // input itself
+    let rebased = value.map_strs(&|s| {
+        let start = s.as_ptr() as usize - completed.as_ptr() as usize;
+        &input[start..start + s.len()]
+    });
+    value.drop_iteratively();
+    Ok(Truncated {
+        value: rebased,
+        truncated_at: Some(cut),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Shared;
+
+    // test the truncated parser on complete documents
+    #[test]
+    fn test_complete() {
+        let result = from_str_truncated("[1, 2]").unwrap();
+        assert_eq!(
+            result,
+            Truncated {
+                value: JsonValue::List(Shared::new(vec![
+                    JsonValue::Number(1u64.into()),
+                    JsonValue::Number(2u64.into())
+                ])),
+                truncated_at: None
+            }
+        );
+    }
+
+    // test the truncated parser on cut off documents
+    #[test]
+    fn test_truncated() {
+        let input = "{\"a\": [1, 2, tr";
+        let result = from_str_truncated(input).unwrap();
+        assert_eq!(
+            result,
+            Truncated {
+                value: JsonValue::Object(Shared::new(vec![(
+                    "a".into(),
+                    JsonValue::List(Shared::new(vec![
+                        JsonValue::Number(1u64.into()),
+                        JsonValue::Number(2u64.into())
+                    ]))
+                )])),
+                truncated_at: Some(11)
+            }
+        );
+
+        let input = "{\"a\": \"value\", \"b\": \"unfinished";
+        let result = from_str_truncated(input).unwrap();
+        assert_eq!(
+            result,
+            Truncated {
+                value: JsonValue::Object(Shared::new(vec![(
+                    "a".into(),
+                    JsonValue::String("value".into())
+                )])),
+                truncated_at: Some(13)
+            }
+        );
+    }
+
+    // test that garbage isn't mistaken for truncation
+    #[test]
+    fn test_not_truncated() {
+        assert!(from_str_truncated("[1, 2, xx").is_err());
+        assert!(from_str_truncated("[1, 2]]").is_err());
+        assert!(from_str_truncated("\"unfinished").is_err());
+    }
+
+    // test that deeply nested documents take time in proportion to their size
+    #[test]
+    fn test_truncated_deep() {
+        let depth = 100_000;
+        let input = format!("{}1, {{\"a\": [true", "[".repeat(depth));
+        let result = from_str_truncated(&input).unwrap();
+        assert_eq!(result.truncated_at, Some(input.len() - "true".len()));
+
+        let mut value = &result.value;
+        for _ in 0..depth {
+            value = &value[0];
+        }
+        assert_eq!(value, &JsonValue::from(1));
+        assert_eq!(
+            result.value.pointer(&"/0".repeat(depth - 1)).unwrap().len(),
+            2
+        );
+        result.value.drop_iteratively();
+    }
+}
@@ -0,0 +1,211 @@
+use std::borrow::Cow;
+
+use crate::JsonValue;
+
+/// What malformed escape sequences and unpaired surrogates turn into when
+/// decoding strings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Replacement {
+    Char(char),
+    Remove,
+    /// Keep the raw text of the escape sequence.
+    Preserve,
+}
+
+impl Default for Replacement {
+    fn default() -> Self {
+        Replacement::Char(char::REPLACEMENT_CHARACTER)
+    }
+}
+
+fn hex4(s: &str) -> Option<u32> {
+    let digits = s.get(..4)?;
+    if digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        u32::from_str_radix(digits, 16).ok()
+    } else {
+        None
+    }
+}
+
+/// Decodes a single escape sequence at the start of `s`, which begins with a
+/// backslash. Returns the decoded character, `None` for malformed sequences
+/// and unpaired surrogates, along with the length of the sequence. The quote
+/// the string is delimited by can be escaped as well.
+fn escape(s: &str, quote: u8) -> (Option<char>, usize) {
+    let c = match s.as_bytes().get(1) {
+        Some(b'"') => '"',
+        Some(&b) if b == quote => b as char,
+        Some(b'\\') => '\\',
+        Some(b'/') => '/',
+        Some(b'b') => '\u{8}',
+        Some(b'f') => '\u{c}',
+        Some(b'n') => '\n',
+        Some(b'r') => '\r',
+        Some(b't') => '\t',
+        Some(b'u') => {
+            return match hex4(&s[2..]) {
+                Some(high @ 0xd800..=0xdbff) => match (s.get(6..8), s.get(8..).and_then(hex4)) {
+                    (Some("\\u"), Some(low @ 0xdc00..=0xdfff)) => (
+                        char::from_u32(0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)),
+                        12,
+                    ),
+                    _ => (None, 6),
+                },
+                Some(unit) => (char::from_u32(unit), 6),
+                None => (None, 2),
+            };
+        }
+        _ => return (None, 1 + s[1..].chars().next().map_or(0, char::len_utf8)),
+    };
+    (Some(c), 2)
+}
+
+/// Decodes the escape sequences of a raw string span, borrowing it as is when
+/// there is nothing to decode. Malformed escapes and unpaired surrogates are
+/// handled according to `replacement`.
+pub(crate) fn unescape(raw: &str, replacement: Replacement) -> Cow<'_, str> {
+    match try_unescape(raw, Some(replacement), b'"') {
+        Ok(decoded) => decoded,
+        Err(_) => panic!("internal error in unescape, replacement is set"),
+    }
+}
+
+/// Same as `unescape` for a string delimited by `quote`, but with no
+/// `replacement` a malformed escape or an unpaired surrogate is an error,
+/// holding its byte offset within `raw`.
+pub(crate) fn try_unescape(
+    raw: &str,
+    replacement: Option<Replacement>,
+    quote: u8,
+) -> Result<Cow<'_, str>, usize> {
+    let Some(first) = raw.find('\\') else {
+        return Ok(Cow::Borrowed(raw));
+    };
+    let mut decoded = String::with_capacity(raw.len());
+    decoded.push_str(&raw[..first]);
+    let mut rest = &raw[first..];
+    while !rest.is_empty() {
+        match rest.find('\\') {
+            Some(0) => {
+                let (c, len) = escape(rest, quote);
+                match (c, replacement) {
+                    (Some(c), _) => decoded.push(c),
+                    (None, Some(Replacement::Char(c))) => decoded.push(c),
+                    (None, Some(Replacement::Remove)) => {}
+                    (None, Some(Replacement::Preserve)) => decoded.push_str(&rest[..len]),
+                    (None, None) => return Err(raw.len() - rest.len()),
+                }
+                rest = &rest[len..];
+            }
+            Some(index) => {
+                decoded.push_str(&rest[..index]);
+                rest = &rest[index..];
+            }
+            None => {
+                decoded.push_str(rest);
+                break;
+            }
+        }
+    }
+    Ok(Cow::Owned(decoded))
+}
+
+/// Checks the escape sequences of a raw string span as `try_unescape` would
+/// decode them, decoding nothing. The error holds the byte offset of the first
+/// malformed escape or unpaired surrogate within `raw`, there is none with a
+/// `replacement`.
+pub(crate) fn check_escapes(
+    raw: &str,
+    replacement: Option<Replacement>,
+    quote: u8,
+) -> Result<(), usize> {
+    if replacement.is_some() {
+        return Ok(());
+    }
+    let mut rest = raw;
+    while let Some(index) = memchr::memchr(b'\\', rest.as_bytes()) {
+        rest = &rest[index..];
+        match escape(rest, quote) {
+            (Some(_), len) => rest = &rest[len..],
+            (None, _) => return Err(raw.len() - rest.len()),
+        }
+    }
+    Ok(())
+}
+
+impl<'input> JsonValue<'input> {
+    /// The text of a string value, same as `as_str` now that the parser
+    /// decodes escape sequences itself.
+    #[deprecated(note = "strings are decoded when parsed, use `as_str`")]
+    #[allow(deprecated)]
+    pub fn decoded_str(&self) -> Option<Cow<'input, str>> {
+        self.decoded_str_with(Replacement::default())
+    }
+
+    /// Same as `decoded_str`, the replacement is taken from
+    /// `ParserOptions::replacement` when parsing instead.
+    #[deprecated(note = "strings are decoded when parsed, use `as_str`")]
+    pub fn decoded_str_with(&self, _replacement: Replacement) -> Option<Cow<'input, str>> {
+        match self {
+            JsonValue::String(s) => Some(s.clone()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // test the unescape function
+    #[test]
+    fn test_unescape() {
+        let replacement = Replacement::default();
+        assert!(matches!(
+            unescape("plain", replacement),
+            Cow::Borrowed("plain")
+        ));
+        assert_eq!(unescape("a\\nb\\t\\/\\\\", replacement), "a\nb\t/\\");
+        assert_eq!(unescape("caf\\u00e9", replacement), "café");
+        assert_eq!(unescape("\\uD83D\\uDE00!", replacement), "😀!");
+        assert_eq!(unescape("\\uD83D?", replacement), "\u{fffd}?");
+        assert_eq!(
+            unescape("\\uDE00\\q\\u12", replacement),
+            "\u{fffd}\u{fffd}\u{fffd}12"
+        );
+    }
+
+    // test the replacement of malformed escapes
+    #[test]
+    fn test_unescape_replacement() {
+        let raw = "a\\uD83Db\\qc";
+        assert_eq!(unescape(raw, Replacement::Char('?')), "a?b?c");
+        assert_eq!(unescape(raw, Replacement::Remove), "abc");
+        assert_eq!(unescape(raw, Replacement::Preserve), raw);
+    }
+
+    // test the decoding of parsed strings
+    #[test]
+    fn test_parse_escapes() {
+        let value = crate::from_str("\"tab\\there\\u00e9\"").unwrap();
+        assert_eq!(value.as_str(), Some("tab\thereé"));
+        let value = crate::from_str("\"plain\"").unwrap();
+        assert!(matches!(
+            value,
+            crate::JsonValue::String(Cow::Borrowed("plain"))
+        ));
+        assert_eq!(crate::JsonValue::Null.as_str(), None);
+    }
+
+    // test the deprecated decoded string accessors
+    #[test]
+    #[allow(deprecated)]
+    fn test_decoded_str() {
+        let value = crate::from_str("\"\\u00e9t\\u00e9\"").unwrap();
+        assert_eq!(value.decoded_str().unwrap(), "été");
+        assert_eq!(value.decoded_str_with(Replacement::Remove).unwrap(), "été");
+        let value = crate::from_str("\"plain\"").unwrap();
+        assert!(matches!(value.decoded_str(), Some(Cow::Borrowed("plain"))));
+        assert_eq!(JsonValue::Null.decoded_str(), None);
+    }
+}
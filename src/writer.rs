@@ -0,0 +1,254 @@
+use std::io;
+
+use crate::ser::{write_tokens, Adapter};
+use crate::{Formatter, JsonValue, SerializerOptions, StandardFormatter};
+
+#[derive(Debug, thiserror::Error)]
+pub enum WriterError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    /// A value or the end of an object where the key of a member goes.
+    #[error("Expected a key")]
+    ExpectedKey,
+    /// The end of an object or of the document where the value goes.
+    #[error("Expected a value")]
+    ExpectedValue,
+    #[error("Keys only go in objects")]
+    UnexpectedKey,
+    #[error("Closing a container that isn't the innermost open one")]
+    UnexpectedEnd,
+    #[error("The document has a single top level value")]
+    Complete,
+    #[error("{0} containers are still open")]
+    Unclosed(usize),
+}
+
+enum Container {
+    Array {
+        first: bool,
+    },
+    /// `key` when the key of a member has been written but not its value.
+    Object {
+        first: bool,
+        key: bool,
+    },
+}
+
+/// Writes a document from events pushed one by one, for data that doesn't
+/// exist as a `JsonValue`. The writer takes care of commas, colons and
+/// escaping, and refuses events that would give invalid JSON, such as a
+/// value where a key goes or closing an array with `end_object`.
+pub struct JsonWriter<W, F = StandardFormatter> {
+    out: Adapter<W>,
+    formatter: F,
+    stack: Vec<Container>,
+    complete: bool,
+}
+
+impl<W: io::Write> JsonWriter<W> {
+    pub fn new(writer: W) -> Self {
+        JsonWriter::with_options(writer, SerializerOptions::default())
+    }
+
+    pub fn with_options(writer: W, options: SerializerOptions) -> Self {
+        JsonWriter::with_formatter(writer, StandardFormatter::new(options))
+    }
+}
+
+impl<W: io::Write, F: Formatter> JsonWriter<W, F> {
+    pub fn with_formatter(writer: W, formatter: F) -> Self {
+        JsonWriter {
+            out: Adapter {
+                writer,
+                error: None,
+            },
+            formatter,
+            stack: Vec::new(),
+            complete: false,
+        }
+    }
+
+    pub fn begin_object(&mut self) -> Result<(), WriterError> {
+        self.before_value()?;
+        self.write(|formatter, out| formatter.begin_object(out))?;
+        self.stack.push(Container::Object {
+            first: true,
+            key: false,
+        });
+        Ok(())
+    }
+
+    pub fn key(&mut self, key: &str) -> Result<(), WriterError> {
+        let first = match self.stack.last_mut() {
+            Some(Container::Object { key: true, .. }) => return Err(WriterError::ExpectedValue),
+            Some(Container::Object { first, key }) => {
+                *key = true;
+                std::mem::replace(first, false)
+            }
+            _ => return Err(WriterError::UnexpectedKey),
+        };
+        self.write(|formatter, out| {
+            formatter.begin_object_key(out, first)?;
+            formatter.write_string(out, key)?;
+            formatter.begin_object_value(out)
+        })
+    }
+
+    pub fn end_object(&mut self) -> Result<(), WriterError> {
+        match self.stack.last() {
+            Some(Container::Object { key: true, .. }) => return Err(WriterError::ExpectedValue),
+            Some(Container::Object { .. }) => {}
+            _ => return Err(WriterError::UnexpectedEnd),
+        }
+        self.stack.pop();
+        self.write(|formatter, out| formatter.end_object(out))?;
+        self.after_value()
+    }
+
+    pub fn begin_array(&mut self) -> Result<(), WriterError> {
+        self.before_value()?;
+        self.write(|formatter, out| formatter.begin_array(out))?;
+        self.stack.push(Container::Array { first: true });
+        Ok(())
+    }
+
+    pub fn end_array(&mut self) -> Result<(), WriterError> {
+        let Some(Container::Array { .. }) = self.stack.last() else {
+            return Err(WriterError::UnexpectedEnd);
+        };
+        self.stack.pop();
+        self.write(|formatter, out| formatter.end_array(out))?;
+        self.after_value()
+    }
+
+    /// Writes a whole value, `writer.value(&"text".into())` for a scalar.
+    pub fn value(&mut self, value: &JsonValue) -> Result<(), WriterError> {
+        self.before_value()?;
+        self.write(|formatter, out| write_tokens(out, value, formatter, false))?;
+        self.after_value()
+    }
+
+    /// Checks that the document is complete and gives the writer back.
+    pub fn finish(self) -> Result<W, WriterError> {
+        match self.stack.len() {
+            0 if self.complete => Ok(self.out.writer),
+            0 => Err(WriterError::ExpectedValue),
+            open => Err(WriterError::Unclosed(open)),
+        }
+    }
+
+    fn before_value(&mut self) -> Result<(), WriterError> {
+        match self.stack.last_mut() {
+            None if self.complete => Err(WriterError::Complete),
+            None => Ok(()),
+            Some(Container::Object { key: false, .. }) => Err(WriterError::ExpectedKey),
+            Some(Container::Object { .. }) => Ok(()),
+            Some(Container::Array { first }) => {
+                let first = std::mem::replace(first, false);
+                self.write(|formatter, out| formatter.begin_array_value(out, first))
+            }
+        }
+    }
+
+    fn after_value(&mut self) -> Result<(), WriterError> {
+        match self.stack.last_mut() {
+            None => {
+                self.complete = true;
+                Ok(())
+            }
+            Some(Container::Array { .. }) => {
+                self.write(|formatter, out| formatter.end_array_value(out))
+            }
+            Some(Container::Object { key, .. }) => {
+                *key = false;
+                self.write(|formatter, out| formatter.end_object_value(out))
+            }
+        }
+    }
+
+    fn write(
+        &mut self,
+        f: impl FnOnce(&mut F, &mut Adapter<W>) -> std::fmt::Result,
+    ) -> Result<(), WriterError> {
+        f(&mut self.formatter, &mut self.out).map_err(|_| {
+            let error = self.out.error.take();
+            WriterError::Io(error.unwrap_or_else(|| io::Error::other("formatting failed")))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{from_str, PrettyConfig};
+
+    // test the writing of a document from events
+    #[test]
+    fn test_json_writer() {
+        let mut writer = JsonWriter::new(Vec::new());
+        writer.begin_object().unwrap();
+        writer.key("a\"").unwrap();
+        writer.begin_array().unwrap();
+        for n in 0..3 {
+            writer.value(&n.into()).unwrap();
+        }
+        writer.begin_object().unwrap();
+        writer.end_object().unwrap();
+        writer.end_array().unwrap();
+        writer.key("b").unwrap();
+        writer.value(&from_str("{\"c\": null}").unwrap()).unwrap();
+        writer.end_object().unwrap();
+        let out = writer.finish().unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "{\"a\\\"\":[0,1,2,{}],\"b\":{\"c\":null}}"
+        );
+
+        let options = SerializerOptions {
+            pretty: Some(PrettyConfig::default()),
+            ..Default::default()
+        };
+        let mut writer = JsonWriter::with_options(Vec::new(), options);
+        writer.begin_array().unwrap();
+        writer.value(&true.into()).unwrap();
+        writer.end_array().unwrap();
+        let out = writer.finish().unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "[\n  true\n]");
+    }
+
+    // test the events that would give invalid JSON
+    #[test]
+    fn test_json_writer_errors() {
+        let mut writer = JsonWriter::new(Vec::new());
+        assert!(matches!(writer.key("a"), Err(WriterError::UnexpectedKey)));
+        assert!(matches!(
+            writer.end_array(),
+            Err(WriterError::UnexpectedEnd)
+        ));
+        writer.begin_object().unwrap();
+        assert!(matches!(
+            writer.value(&1.into()),
+            Err(WriterError::ExpectedKey)
+        ));
+        assert!(matches!(
+            writer.end_array(),
+            Err(WriterError::UnexpectedEnd)
+        ));
+        writer.key("a").unwrap();
+        assert!(matches!(writer.key("b"), Err(WriterError::ExpectedValue)));
+        assert!(matches!(
+            writer.end_object(),
+            Err(WriterError::ExpectedValue)
+        ));
+        writer.value(&1.into()).unwrap();
+        writer.end_object().unwrap();
+        assert!(matches!(writer.begin_array(), Err(WriterError::Complete)));
+        assert_eq!(writer.finish().unwrap(), b"{\"a\":1}");
+
+        let mut writer = JsonWriter::new(Vec::new());
+        writer.begin_array().unwrap();
+        assert!(matches!(writer.finish(), Err(WriterError::Unclosed(1))));
+        let writer = JsonWriter::new(Vec::new());
+        assert!(matches!(writer.finish(), Err(WriterError::ExpectedValue)));
+    }
+}
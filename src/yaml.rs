@@ -0,0 +1,170 @@
+use crate::{JsonValue, Member, Shared};
+
+#[derive(Debug, Clone, Copy, thiserror::Error, PartialEq, Eq)]
+pub enum YamlError {
+    #[error("Mapping key is not a string")]
+    NonStringKey,
+    #[error("Tagged values have no JSON counterpart")]
+    Tagged,
+}
+
+/// A mapping or sequence being converted by `from_yaml`, with the values left
+/// to convert and those converted, mappings along with the key of the value
+/// being converted.
+enum Converting<'yaml> {
+    Sequence(
+        std::slice::Iter<'yaml, serde_yaml::Value>,
+        Vec<JsonValue<'yaml>>,
+    ),
+    Mapping(
+        serde_yaml::mapping::Iter<'yaml>,
+        Vec<Member<'yaml>>,
+        &'yaml str,
+    ),
+}
+
+/// Converts values of any depth, mind that `serde_yaml` drops its values
+/// recursively.
+pub fn to_yaml(value: &JsonValue) -> serde_yaml::Value {
+    let Ok(converted) = value.fold::<_, std::convert::Infallible>(
+        |value| {
+            Ok(match value {
+                JsonValue::String(s) => serde_yaml::Value::String(s.to_string()),
+                JsonValue::Number(n) => serde_yaml::Value::Number(match (n.as_u64(), n.as_i64()) {
+                    (Some(n), _) => n.into(),
+                    (_, Some(n)) => n.into(),
+                    _ => n.as_f64().into(),
+                }),
+                JsonValue::Boolean(b) => serde_yaml::Value::Bool(*b),
+                _ => serde_yaml::Value::Null,
+            })
+        },
+        |values| Ok(serde_yaml::Value::Sequence(values)),
+        |pairs, values| {
+            let keys = pairs
+                .iter()
+                .map(|(key, _)| serde_yaml::Value::String(key.to_string()));
+            Ok(serde_yaml::Value::Mapping(keys.zip(values).collect()))
+        },
+    );
+    converted
+}
+
+/// Converts the JSON compatible subset of YAML, strings are borrowed from
+/// the YAML value. Mappings and sequences are kept on an explicit stack, so
+/// that values of any depth get converted.
+pub fn from_yaml(value: &serde_yaml::Value) -> Result<JsonValue<'_>, YamlError> {
+    let mut stack: Vec<Converting> = Vec::new();
+    let mut value = value;
+    loop {
+        let mut converted = match value {
+            serde_yaml::Value::Null => Some(JsonValue::Null),
+            serde_yaml::Value::Bool(b) => Some(JsonValue::Boolean(*b)),
+            serde_yaml::Value::Number(n) => {
+                Some(JsonValue::Number(match (n.as_u64(), n.as_i64()) {
+                    (Some(n), _) => n.into(),
+                    (_, Some(n)) => n.into(),
+                    _ => n.as_f64().unwrap_or(f64::NAN).into(),
+                }))
+            }
+            serde_yaml::Value::String(s) => Some(JsonValue::String(s.into())),
+            serde_yaml::Value::Sequence(values) => {
+                let converted = Vec::with_capacity(values.len());
+                stack.push(Converting::Sequence(values.iter(), converted));
+                None
+            }
+            serde_yaml::Value::Mapping(mapping) => {
+                let converted = Vec::with_capacity(mapping.len());
+                stack.push(Converting::Mapping(mapping.iter(), converted, ""));
+                None
+            }
+            serde_yaml::Value::Tagged(_) => return Err(YamlError::Tagged),
+        };
+        // hand the converted value over to its container, completing those
+        // that have all of their values
+        loop {
+            match stack.last_mut() {
+                None => return Ok(converted.expect("a converted value")),
+                Some(Converting::Sequence(values, converted_values)) => {
+                    converted_values.extend(converted.take());
+                    if let Some(next) = values.next() {
+                        value = next;
+                        break;
+                    }
+                }
+                Some(Converting::Mapping(mapping, converted_pairs, key)) => {
+                    if let Some(converted) = converted.take() {
+                        converted_pairs.push(((*key).into(), converted));
+                    }
+                    match mapping.next() {
+                        Some((serde_yaml::Value::String(next_key), next)) => {
+                            *key = next_key;
+                            value = next;
+                            break;
+                        }
+                        Some(_) => return Err(YamlError::NonStringKey),
+                        None => {}
+                    }
+                }
+            }
+            converted = Some(match stack.pop() {
+                Some(Converting::Sequence(_, values)) => JsonValue::List(Shared::new(values)),
+                Some(Converting::Mapping(_, pairs, _)) => JsonValue::Object(Shared::new(pairs)),
+                None => unreachable!("a container was on the stack"),
+            });
+        }
+    }
+}
+
+pub fn to_yaml_string(value: &JsonValue) -> Result<String, serde_yaml::Error> {
+    serde_yaml::to_string(&to_yaml(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // test the yaml round trip
+    #[test]
+    fn test_yaml_round_trip() {
+        let value =
+            crate::from_str("{\"name\": \"ujson\", \"tags\": [1, 2.5, true, null]}").unwrap();
+        let yaml = to_yaml_string(&value).unwrap();
+        assert_eq!(yaml, "name: ujson\ntags:\n- 1\n- 2.5\n- true\n- null\n");
+        let parsed: serde_yaml::Value = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(from_yaml(&parsed).unwrap(), value);
+    }
+
+    // test the yaml values without a json counterpart
+    #[test]
+    fn test_from_yaml_errors() {
+        let parsed: serde_yaml::Value = serde_yaml::from_str("1: one").unwrap();
+        assert_eq!(from_yaml(&parsed), Err(YamlError::NonStringKey));
+        let parsed: serde_yaml::Value = serde_yaml::from_str("!custom value").unwrap();
+        assert_eq!(from_yaml(&parsed), Err(YamlError::Tagged));
+        let parsed: serde_yaml::Value = serde_yaml::from_str("[[a, {1: one}]]").unwrap();
+        assert_eq!(from_yaml(&parsed), Err(YamlError::NonStringKey));
+    }
+
+    // test the conversions of values too deep to recurse into
+    #[test]
+    fn test_yaml_deep() {
+        let depth = 100_000;
+        let input = format!("{}{{\"a\": 1}}{}", "[".repeat(depth), "]".repeat(depth));
+        let value = crate::from_str(&input).unwrap();
+        let yaml = to_yaml(&value);
+        let converted = from_yaml(&yaml).unwrap();
+        assert_eq!(converted, value);
+        converted.drop_iteratively();
+        value.drop_iteratively();
+        // `serde_yaml` would drop the deep value recursively
+        let mut pending = vec![yaml];
+        while let Some(yaml) = pending.pop() {
+            match yaml {
+                serde_yaml::Value::Sequence(values) => pending.extend(values),
+                serde_yaml::Value::Mapping(mapping) => pending.extend(mapping.into_values()),
+                _ => {}
+            }
+        }
+    }
+}